@@ -150,3 +150,21 @@ pub fn syscall(payload: u64) -> u64 {
         // Ignore unrelated responses
     }
 }
+
+/// Argument block of a frontend syscall; the host reads it by address, so keep it aligned.
+#[repr(C, align(64))]
+struct MagicMem([u64; 8]);
+
+/// Ask the frontend server to run syscall `n` (RISC-V Linux numbering) on the host.
+///
+/// The host reads the number and arguments from a buffer whose address is the packet payload,
+/// runs the call, stores the result in the buffer's first word and acks on `fromhost`. Pointer
+/// arguments are guest addresses. Returns the host's result (`-errno` on failure).
+pub fn frontend_syscall(n: u64, args: [u64; 6]) -> i64 {
+    let mut mem = MagicMem([n, args[0], args[1], args[2], args[3], args[4], args[5], 0]);
+    let addr = core::ptr::addr_of_mut!(mem.0);
+    core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+    syscall(addr as usize as u64);
+    core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+    unsafe { core::ptr::read_volatile(addr as *const u64) as i64 }
+}
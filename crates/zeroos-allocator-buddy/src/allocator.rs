@@ -2,6 +2,7 @@ use core::alloc::{GlobalAlloc, Layout};
 use core::ptr;
 
 use buddy_system_allocator::LockedHeap;
+use foundation::ops::MemoryStats;
 
 // Upstream buddy allocator. The const generic is the max order, i.e. the maximum
 // heap size is bounded by \(2^\text{ORDER}\) bytes.
@@ -30,6 +31,15 @@ pub(crate) fn dealloc(ptr: *mut u8, layout: Layout) {
     }
 }
 
+pub(crate) fn stats() -> MemoryStats {
+    let heap = HEAP.lock();
+    let total = heap.stats_total_bytes();
+    MemoryStats {
+        total,
+        free: total.saturating_sub(heap.stats_alloc_actual()),
+    }
+}
+
 pub(crate) fn realloc(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
     if ptr.is_null() {
         let new_layout = match Layout::from_size_align(new_size, old_layout.align()) {
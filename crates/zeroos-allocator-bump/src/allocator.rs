@@ -2,6 +2,8 @@ use core::alloc::Layout;
 use core::ptr;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
+use foundation::ops::MemoryStats;
+
 #[cfg(test)]
 extern crate alloc;
 
@@ -9,6 +11,8 @@ pub(crate) struct BumpAllocator {
     next: AtomicUsize,
 
     end: AtomicUsize,
+
+    size: AtomicUsize,
 }
 
 impl BumpAllocator {
@@ -16,6 +20,7 @@ impl BumpAllocator {
         Self {
             next: AtomicUsize::new(0),
             end: AtomicUsize::new(0),
+            size: AtomicUsize::new(0),
         }
     }
 
@@ -23,6 +28,7 @@ impl BumpAllocator {
         self.next.store(heap_start, Ordering::SeqCst);
         let end = heap_start.checked_add(heap_size).unwrap_or(heap_start);
         self.end.store(end, Ordering::SeqCst);
+        self.size.store(end - heap_start, Ordering::SeqCst);
     }
 
     pub(crate) fn alloc(&self, layout: Layout) -> *mut u8 {
@@ -57,7 +63,6 @@ impl BumpAllocator {
         self.next.store(start, Ordering::Release);
     }
 
-    #[allow(dead_code)]
     fn get_capacity(&self) -> usize {
         let end = self.end.load(Ordering::Acquire);
         let next = self.next.load(Ordering::Acquire);
@@ -86,6 +91,13 @@ pub(crate) fn alloc(layout: Layout) -> *mut u8 {
     ALLOCATOR.alloc(layout)
 }
 
+pub(crate) fn stats() -> MemoryStats {
+    MemoryStats {
+        total: ALLOCATOR.size.load(Ordering::Acquire),
+        free: ALLOCATOR.get_capacity(),
+    }
+}
+
 pub(crate) fn dealloc(_ptr: *mut u8, _layout: Layout) {}
 
 pub(crate) fn realloc(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
@@ -1,5 +1,6 @@
 use core::alloc::Layout;
 use core::ptr;
+use foundation::ops::MemoryStats;
 use linked_list_allocator::LockedHeap;
 
 #[cfg(test)]
@@ -29,6 +30,14 @@ pub(crate) fn dealloc(ptr: *mut u8, layout: Layout) {
     }
 }
 
+pub(crate) fn stats() -> MemoryStats {
+    let heap = HEAP.lock();
+    MemoryStats {
+        total: heap.size(),
+        free: heap.free(),
+    }
+}
+
 pub(crate) fn realloc(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
     if ptr.is_null() {
         let new_layout = match Layout::from_size_align(new_size, old_layout.align()) {
@@ -9,4 +9,5 @@ pub const LINKED_LIST_ALLOCATOR_OPS: MemoryOps = MemoryOps {
     alloc: allocator::alloc,
     dealloc: allocator::dealloc,
     realloc: allocator::realloc,
+    stats: allocator::stats,
 };
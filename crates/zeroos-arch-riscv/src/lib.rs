@@ -6,6 +6,7 @@
 pub mod boot;
 pub mod ops;
 pub mod ret_from_fork;
+pub mod signal;
 pub mod switch_to;
 pub mod thread_ctx;
 pub mod trap;
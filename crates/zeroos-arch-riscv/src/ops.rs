@@ -119,6 +119,187 @@ unsafe fn trap_frame_get_fault_addr(regs: *const u8) -> usize {
     r.mtval
 }
 
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_get_sp(regs: *const u8) -> usize {
+    let r = &*(regs as *const TrapFrame);
+    r.sp
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_get_fp(regs: *const u8) -> usize {
+    let r = &*(regs as *const TrapFrame);
+    r.s0
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+unsafe fn trap_frame_regs(regs: *const u8, f: &mut dyn FnMut(&'static str, usize)) {
+    let r = &*(regs as *const TrapFrame);
+    let saved = [
+        ("ra", r.ra),
+        ("sp", r.sp),
+        ("gp", r.gp),
+        ("tp", r.tp),
+        ("t0", r.t0),
+        ("t1", r.t1),
+        ("t2", r.t2),
+        ("s0", r.s0),
+        ("s1", r.s1),
+        ("a0", r.a0),
+        ("a1", r.a1),
+        ("a2", r.a2),
+        ("a3", r.a3),
+        ("a4", r.a4),
+        ("a5", r.a5),
+        ("a6", r.a6),
+        ("a7", r.a7),
+        ("s2", r.s2),
+        ("s3", r.s3),
+        ("s4", r.s4),
+        ("s5", r.s5),
+        ("s6", r.s6),
+        ("s7", r.s7),
+        ("s8", r.s8),
+        ("s9", r.s9),
+        ("s10", r.s10),
+        ("s11", r.s11),
+        ("t3", r.t3),
+        ("t4", r.t4),
+        ("t5", r.t5),
+        ("t6", r.t6),
+        ("mstatus", r.mstatus),
+        ("mcause", r.mcause),
+    ];
+    for (name, value) in saved {
+        f(name, value);
+    }
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_set_arg(regs: *mut u8, idx: usize, val: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    match idx {
+        0 => r.a0 = val,
+        1 => r.a1 = val,
+        2 => r.a2 = val,
+        3 => r.a3 = val,
+        4 => r.a4 = val,
+        5 => r.a5 = val,
+        _ => {}
+    }
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_set_ra(regs: *mut u8, ra: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    r.ra = ra;
+}
+
+#[inline(always)]
+fn instret() -> u64 {
+    riscv::register::minstret::read64()
+}
+
+/// Whether the hart implements Smepmp's `mseccfg` CSR.
+///
+/// Probed once by setting `mseccfg.RLB` under a temporary trap vector that skips the access if
+/// it raises an illegal-instruction exception. `mstatus` and `mepc` are restored afterwards, so
+/// this is safe inside a trap handler too.
+#[cfg(any(feature = "kstack-guard", feature = "ustack-guard"))]
+fn smepmp_rlb() -> bool {
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    const UNKNOWN: u8 = 0;
+    const ABSENT: u8 = 1;
+    const PRESENT: u8 = 2;
+    const CSR_MSECCFG_RLB: usize = 1 << 2;
+    static SMEPMP: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+    match SMEPMP.load(Ordering::Relaxed) {
+        UNKNOWN => {}
+        state => return state == PRESENT,
+    }
+    let present: usize;
+    unsafe {
+        core::arch::asm!(
+            "csrr {mstatus}, mstatus",
+            "csrr {mepc}, mepc",
+            "la {vec}, 2f",
+            "csrrw {vec}, mtvec, {vec}",
+            "li {present}, 1",
+            "csrs 0x747, {rlb}",
+            "j 3f",
+            ".balign 4",
+            "2:",
+            "li {present}, 0",
+            "3:",
+            "csrw mtvec, {vec}",
+            "csrw mepc, {mepc}",
+            "csrw mstatus, {mstatus}",
+            mstatus = out(reg) _,
+            mepc = out(reg) _,
+            vec = out(reg) _,
+            present = out(reg) present,
+            rlb = in(reg) CSR_MSECCFG_RLB,
+        );
+    }
+    if present == 0 {
+        debug::writeln!("[PMP] no Smepmp: stack guards are not enforced in M-mode");
+    }
+    SMEPMP.store(
+        if present != 0 { PRESENT } else { ABSENT },
+        Ordering::Relaxed,
+    );
+    present != 0
+}
+
+/// Protect `[base, base + size)` with a no-access PMP TOR range.
+///
+/// Slot `n` uses PMP entries `2n` (bottom, OFF) and `2n + 1` (top, TOR), so any 4-byte aligned
+/// range works. Everything runs in M-mode, so the entry must be locked to apply; re-programming
+/// a locked entry needs Smepmp's rule-locking bypass (`mseccfg.RLB`). Without Smepmp the entry
+/// stays unlocked: it then only applies to lower privilege modes, and overflows are left to the
+/// stack canaries.
+#[cfg(any(feature = "kstack-guard", feature = "ustack-guard"))]
+fn protect_region(slot: usize, base: usize, size: usize) {
+    const PMP_L: usize = 0x80;
+    const PMP_TOR: usize = 0x08;
+
+    debug_assert!(slot <= 1 && base.is_multiple_of(4) && size.is_multiple_of(4));
+    // Two 8-bit config fields per slot in pmpcfg0.
+    let shift = slot * 16;
+    let (bottom, top) = (base >> 2, (base + size) >> 2);
+    let cfg = if smepmp_rlb() {
+        PMP_L | PMP_TOR
+    } else {
+        PMP_TOR
+    };
+    unsafe {
+        core::arch::asm!("csrc pmpcfg0, {0}", in(reg) 0xffffusize << shift);
+        if size != 0 {
+            if slot == 0 {
+                core::arch::asm!("csrw pmpaddr0, {0}", in(reg) bottom);
+                core::arch::asm!("csrw pmpaddr1, {0}", in(reg) top);
+            } else {
+                core::arch::asm!("csrw pmpaddr2, {0}", in(reg) bottom);
+                core::arch::asm!("csrw pmpaddr3, {0}", in(reg) top);
+            }
+            core::arch::asm!("csrs pmpcfg0, {0}", in(reg) cfg << (shift + 8));
+        }
+    }
+}
+
+#[cfg(not(any(feature = "kstack-guard", feature = "ustack-guard")))]
+fn protect_region(_slot: usize, _base: usize, _size: usize) {}
+
 pub const ARCH_OPS: ArchOps = ArchOps {
     thread_ctx_size: crate::thread_ctx::thread_ctx_size,
     thread_ctx_align: crate::thread_ctx::thread_ctx_align,
@@ -143,4 +324,15 @@ pub const ARCH_OPS: ArchOps = ArchOps {
     trap_frame_get_arg,
     trap_frame_get_cause,
     trap_frame_get_fault_addr,
+    trap_frame_get_sp,
+    trap_frame_get_fp,
+    trap_frame_regs,
+    trap_frame_set_arg,
+    trap_frame_set_ra,
+    sigreturn_trampoline: crate::signal::sigreturn_trampoline,
+    mcontext_size: crate::signal::mcontext_size,
+    mcontext_save: crate::signal::mcontext_save,
+    mcontext_restore: crate::signal::mcontext_restore,
+    instret,
+    protect_region,
 };
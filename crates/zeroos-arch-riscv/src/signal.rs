@@ -0,0 +1,75 @@
+//! Signal frame support: the `rt_sigreturn` trampoline and `mcontext_t` conversion.
+//!
+//! The machine context follows the Linux RISC-V `struct sigcontext` layout so that libc code
+//! inspecting `ucontext_t::uc_mcontext` sees familiar offsets: `gregs[0]` is the PC and
+//! `gregs[1..32]` are `x1..x31`, followed by a (zeroed, never restored) FP state area.
+
+use core::mem::size_of;
+
+use crate::trap::TrapFrame;
+
+/// `__NR_rt_sigreturn` in the generic syscall table used by RISC-V.
+const SYS_RT_SIGRETURN: usize = 139;
+
+/// Number of general-purpose register slots in `mcontext_t` (PC + x1..x31).
+const MCONTEXT_GREGS: usize = 32;
+
+/// Size of `union __riscv_fp_state` (the Q-extension variant is the largest).
+const MCONTEXT_FP_STATE_SIZE: usize = 528;
+
+/// Signal handlers return here (`ra`), which re-enters the kernel via `rt_sigreturn`.
+///
+/// # Safety
+/// Only meant to be reached by returning from a signal handler frame built by the kernel.
+#[unsafe(naked)]
+#[no_mangle]
+pub unsafe extern "C" fn __sigreturn_trampoline() -> ! {
+    core::arch::naked_asm!(
+        "li a7, {nr}",
+        "ecall",
+        "unimp",
+        nr = const SYS_RT_SIGRETURN,
+    )
+}
+
+pub fn sigreturn_trampoline() -> usize {
+    __sigreturn_trampoline as *const () as usize
+}
+
+pub fn mcontext_size() -> usize {
+    MCONTEXT_GREGS * size_of::<usize>() + MCONTEXT_FP_STATE_SIZE
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame` and `mctx` must be writable for
+/// `mcontext_size()` bytes (usize-aligned).
+pub unsafe fn mcontext_save(regs: *const u8, mctx: *mut u8) {
+    let r = regs as *const TrapFrame;
+    let gregs = mctx as *mut usize;
+    gregs.write((*r).mepc);
+    // `ra..t6` are laid out as x1..x31 in the trap frame.
+    core::ptr::copy_nonoverlapping(
+        core::ptr::addr_of!((*r).ra),
+        gregs.add(1),
+        MCONTEXT_GREGS - 1,
+    );
+    core::ptr::write_bytes(
+        gregs.add(MCONTEXT_GREGS) as *mut u8,
+        0,
+        MCONTEXT_FP_STATE_SIZE,
+    );
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame` and `mctx` must be readable for
+/// `mcontext_size()` bytes (usize-aligned).
+pub unsafe fn mcontext_restore(regs: *mut u8, mctx: *const u8) {
+    let r = regs as *mut TrapFrame;
+    let gregs = mctx as *const usize;
+    (*r).mepc = gregs.read();
+    core::ptr::copy_nonoverlapping(
+        gregs.add(1),
+        core::ptr::addr_of_mut!((*r).ra),
+        MCONTEXT_GREGS - 1,
+    );
+}
@@ -155,6 +155,15 @@ cfg_if! {
     }
 }
 
+/// Stack for kernel traps taken after the kernel stack has overflowed.
+const EMERGENCY_STACK_SIZE: usize = 4096;
+
+#[repr(C, align(16))]
+struct EmergencyStack([u8; EMERGENCY_STACK_SIZE]);
+
+#[allow(dead_code)]
+static mut KSTACK_EMERGENCY: EmergencyStack = EmergencyStack([0; EMERGENCY_STACK_SIZE]);
+
 mod imp {
     use super::*;
 
@@ -180,6 +189,17 @@ mod imp {
                         store!(t6, {ThreadAnchor.stash0}(tp) @k),
                         "li t6, 1",
                         store!(sp, {ThreadAnchor.kernel_sp}(tp)),
+
+                        // A kernel trap whose sp already ran into the anchor (or the guard below
+                        // it) would clobber it further while saving the frame: use the emergency
+                        // stack so the overflow can still be reported.
+                        store!(t5, {ThreadAnchor.stash1}(tp)),
+                        "addi t5, tp, {KSTACK_FLOOR}",
+                        "bgeu sp, t5, 2f",
+                        "la t5, {emergency_stack}+{EMERGENCY_STACK_SIZE}",
+                        store!(t5, {ThreadAnchor.kernel_sp}(tp) @emergency),
+                        "2:",
+                        load!(t5, {ThreadAnchor.stash1}(tp)),
                         "j .Lcommon_save_context",
 
                         ".Lsave_context:",
@@ -242,7 +262,14 @@ mod imp {
 
                         "mv a0, sp",
                         "call {trap_handler}",
-                        "j ret_from_exception",
+
+                        // Kernel stack canary check (tp = current anchor, 0 before threading).
+                        "beqz tp, ret_from_exception",
+                        load!(t0, {ThreadAnchor.canary}(tp)),
+                        "li t1, {KSTACK_CANARY}",
+                        "beq t0, t1, ret_from_exception",
+                        "mv a0, tp",
+                        "call {kcheck_kstack}",
 
                         "ret_from_exception:",
                         load!(t6, {TrapFrame.from_kernel}(sp)),
@@ -294,7 +321,12 @@ mod imp {
                     "mret",
 
                     FRAME_SIZE = const core::mem::size_of::<TrapFrame>(),
+                    KSTACK_FLOOR = const core::mem::size_of::<ThreadAnchor>() + core::mem::size_of::<TrapFrame>(),
+                    KSTACK_CANARY = const foundation::kfn::thread::KSTACK_CANARY,
+                    EMERGENCY_STACK_SIZE = const EMERGENCY_STACK_SIZE,
+                    emergency_stack = sym KSTACK_EMERGENCY,
                     trap_handler = sym crate::trap_handler,
+                    kcheck_kstack = sym foundation::kfn::thread::kcheck_kstack_current,
                 );
             } else {
                  core::arch::naked_asm!("unimp");
@@ -74,6 +74,10 @@ impl BacktraceCapture for DwarfBacktrace {
         // For nostd mode with DWARF, manually walking .eh_frame is complex
         // and not currently implemented. Use frame-pointers mode instead.
     }
+
+    unsafe fn walk_frames(_fp: usize, _f: &mut dyn FnMut(usize)) {
+        // Same limitation as `print_backtrace`: crash reports carry no frames in this mode.
+    }
 }
 
 // NOTE: The .init_array hook (__zeroos_register_eh_frame and __ZEROOS_EH_FRAME_INIT)
@@ -76,6 +76,17 @@ impl BacktraceCapture for FramePointerBacktrace {
         // Safety: get_frame_pointer returns the current valid frame pointer
         unsafe { print_backtrace_from_fp(get_frame_pointer()) };
     }
+
+    #[inline(never)]
+    unsafe fn walk_frames(fp: usize, f: &mut dyn FnMut(usize)) {
+        let fp = if fp == 0 {
+            get_frame_pointer()
+        } else {
+            fp as *const usize
+        };
+        // Safety: the caller passes a frame pointer on a live stack (or 0 for this one)
+        unsafe { walk_from_fp(fp, f) };
+    }
 }
 
 /// Print a stack backtrace starting from a given frame pointer.
@@ -83,7 +94,7 @@ impl BacktraceCapture for FramePointerBacktrace {
 /// # Safety
 ///
 /// The caller must ensure `fp` is a valid frame pointer from the current stack.
-unsafe fn print_backtrace_from_fp(mut fp: *const usize) {
+unsafe fn print_backtrace_from_fp(fp: *const usize) {
     // Use a simple writer that outputs to platform stdout
     struct StdoutWriter;
 
@@ -104,26 +115,48 @@ unsafe fn print_backtrace_from_fp(mut fp: *const usize) {
     let _ = writeln!(w, "stack backtrace:");
 
     let mut frame_num = 0usize;
+    let complete = unsafe {
+        walk_from_fp(fp, &mut |ra| {
+            // Format matches Rust stdlib backtrace: "  N:         0xADDR - <unknown>"
+            let _ = writeln!(w, "  {frame_num}:         0x{ra:x} - <unknown>");
+            frame_num += 1;
+        })
+    };
+
+    if !complete {
+        let _ = writeln!(w, "  ... (truncated)");
+    }
+}
 
-    while !fp.is_null() && frame_num < MAX_FRAMES {
+/// Call `f` with up to [`MAX_FRAMES`] return addresses, following the chain from `fp`.
+///
+/// Returns `false` if the walk stopped at the frame limit.
+///
+/// # Safety
+///
+/// The caller must ensure `fp` is null or a valid frame pointer.
+unsafe fn walk_from_fp(mut fp: *const usize, f: &mut dyn FnMut(usize)) -> bool {
+    for _ in 0..MAX_FRAMES {
+        if fp.is_null() || !fp.is_aligned() {
+            return true;
+        }
         // Safety: We check fp is not null
         let ra = unsafe { read_return_address(fp) };
 
         if ra == 0 {
-            break;
+            return true;
         }
+        f(ra);
 
-        // Format matches Rust stdlib backtrace: "  N:         0xADDR - <unknown>"
-        let _ = writeln!(w, "  {frame_num}:         0x{ra:x} - <unknown>");
-
-        // Move to previous frame
-        fp = unsafe { read_previous_fp(fp) };
-        frame_num += 1;
-    }
-
-    if frame_num == MAX_FRAMES {
-        let _ = writeln!(w, "  ... (truncated)");
+        // Move to previous frame; callers live higher up the stack, so anything else means the
+        // chain is corrupt (or was never built, in code compiled without frame pointers).
+        let prev = unsafe { read_previous_fp(fp) };
+        if prev <= fp {
+            return true;
+        }
+        fp = prev;
     }
+    false
 }
 
 /// Get the current frame pointer (s0/fp register on RISC-V).
@@ -56,6 +56,16 @@ pub trait BacktraceCapture {
     /// - Frame pointers (if used) are correctly maintained
     /// - This is called from a valid execution context (e.g., panic handler)
     unsafe fn print_backtrace();
+
+    /// Call `f` with the return address of each frame, innermost first, walking from frame
+    /// pointer `fp` (the caller's frame if `fp` is 0).
+    ///
+    /// Used for crash reports. Modes that cannot walk frames report none.
+    ///
+    /// # Safety
+    ///
+    /// `fp` must be 0 or a frame pointer on a live stack (e.g. saved in a trap frame).
+    unsafe fn walk_frames(fp: usize, f: &mut dyn FnMut(usize));
 }
 
 // Conditional module inclusion and re-exports based on compile-time configuration
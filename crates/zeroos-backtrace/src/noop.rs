@@ -64,4 +64,7 @@ impl BacktraceCapture for NoopBacktrace {
     unsafe fn print_backtrace() {
         // No backtrace output
     }
+
+    #[inline(always)]
+    unsafe fn walk_frames(_fp: usize, _f: &mut dyn FnMut(usize)) {}
 }
@@ -0,0 +1,221 @@
+use std::io::Write;
+
+/// First line of a guest crash record (`foundation::kfn::crash`).
+pub const CRASH_BEGIN: &str = "@@CRASH BEGIN";
+/// Last line of a guest crash record.
+pub const CRASH_END: &str = "@@CRASH END";
+
+/// A crash record as printed by the guest kernel.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CrashRecord {
+    /// `panic`, `oom`, `unsupported-syscall`, `exception` or `signal`.
+    pub cause: String,
+    pub message: String,
+    pub exit_code: Option<i32>,
+    pub tid: Option<u64>,
+    pub pc: Option<u64>,
+    pub mtval: Option<u64>,
+    /// Saved registers, in the guest's order.
+    pub regs: Vec<(String, u64)>,
+    /// Last syscall that failed as unsupported: number and name.
+    pub enosys: Option<(u64, String)>,
+    /// Size of the last failed allocation.
+    pub oom: Option<u64>,
+    /// Return addresses, innermost first.
+    pub frames: Vec<u64>,
+    /// Whether the end marker was seen (a crash while reporting cuts the record short).
+    pub complete: bool,
+}
+
+/// Split the start of a crash record off an output line.
+///
+/// Returns the guest output preceding the marker, which may be partial output that did not end
+/// in a newline.
+pub fn split_crash_begin(line: &str) -> Option<&str> {
+    let pos = line.find(CRASH_BEGIN)?;
+    Some(&line[..pos])
+}
+
+fn parse_hex(s: &str) -> Option<u64> {
+    u64::from_str_radix(s.strip_prefix("0x")?, 16).ok()
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+impl CrashRecord {
+    /// Apply one line of a record body.
+    ///
+    /// Returns `false` on the end marker. Unknown keys are skipped, so records from newer
+    /// kernels still parse.
+    pub fn parse_line(&mut self, line: &str) -> bool {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line == CRASH_END {
+            self.complete = true;
+            return false;
+        }
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "cause" => self.cause = value.to_string(),
+            "message" => self.message = unescape(value),
+            "exit" => self.exit_code = value.parse().ok(),
+            "tid" => self.tid = value.parse().ok(),
+            "pc" => self.pc = parse_hex(value),
+            "mtval" => self.mtval = parse_hex(value),
+            "reg" => {
+                if let Some((name, v)) = value.split_once(' ') {
+                    if let Some(v) = parse_hex(v) {
+                        self.regs.push((name.to_string(), v));
+                    }
+                }
+            }
+            "enosys" => {
+                if let Some((nr, name)) = value.split_once(' ') {
+                    if let Ok(nr) = nr.parse() {
+                        self.enosys = Some((nr, name.to_string()));
+                    }
+                }
+            }
+            "oom" => self.oom = value.parse().ok(),
+            "frame" => self.frames.extend(parse_hex(value)),
+            _ => {}
+        }
+        true
+    }
+
+    /// Print the record for people.
+    ///
+    /// `symbolize` resolves a code address to `function at file:line`; the flag says whether
+    /// it is a return address (which points after the call).
+    pub fn render(
+        &self,
+        out: &mut dyn Write,
+        symbolize: &dyn Fn(u64, bool) -> Option<String>,
+    ) -> std::io::Result<()> {
+        let sym =
+            |addr, is_return| symbolize(addr, is_return).unwrap_or_else(|| "<unknown>".into());
+
+        write!(out, "\n==== guest crash: {}", self.cause)?;
+        if let Some(code) = self.exit_code {
+            write!(out, " (exit {code})")?;
+        }
+        if !self.complete {
+            write!(out, " [record truncated]")?;
+        }
+        writeln!(out, " ====")?;
+        for line in self.message.lines() {
+            writeln!(out, "  {line}")?;
+        }
+        if let Some(tid) = self.tid {
+            writeln!(out, "thread:  {tid}")?;
+        }
+        if let Some(pc) = self.pc {
+            writeln!(out, "pc:      {pc:#x} - {}", sym(pc, false))?;
+        }
+        if let Some(mtval) = self.mtval {
+            writeln!(out, "mtval:   {mtval:#x}")?;
+        }
+        if let Some((nr, name)) = &self.enosys {
+            writeln!(out, "last unsupported syscall: {name} ({nr})")?;
+        }
+        if let Some(size) = self.oom {
+            writeln!(out, "failed allocation: {size} bytes")?;
+        }
+        if !self.regs.is_empty() {
+            writeln!(out, "registers:")?;
+            for row in self.regs.chunks(4) {
+                write!(out, " ")?;
+                for (name, value) in row {
+                    write!(out, " {name:>7} {value:#018x}")?;
+                }
+                writeln!(out)?;
+            }
+        }
+        if !self.frames.is_empty() {
+            writeln!(out, "backtrace:")?;
+            for (i, &ra) in self.frames.iter().enumerate() {
+                writeln!(
+                    out,
+                    "{i:>4}: {:>18} - {}",
+                    format!("{ra:#x}"),
+                    sym(ra, true)
+                )?;
+            }
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_record() {
+        let output = "partial@@CRASH BEGIN\n\
+                      cause exception\n\
+                      message SIGSEGV at 0x0\\nsecond \\\\ line\n\
+                      exit 139\n\
+                      tid 2\n\
+                      pc 0x80001a2c\n\
+                      mtval 0x0\n\
+                      reg ra 0x80001a10\n\
+                      reg sp 0x80ffff40\n\
+                      enosys 291 SYS_statx\n\
+                      future-key whatever\n\
+                      frame 0x80001a10\n\
+                      frame 0x80000f3e\n\
+                      @@CRASH END\n";
+        let mut lines = output.lines();
+        assert_eq!(split_crash_begin(lines.next().unwrap()), Some("partial"));
+
+        let mut record = CrashRecord::default();
+        for line in lines.by_ref() {
+            if !record.parse_line(line) {
+                break;
+            }
+        }
+        assert!(lines.next().is_none());
+        assert_eq!(
+            record,
+            CrashRecord {
+                cause: "exception".into(),
+                message: "SIGSEGV at 0x0\nsecond \\ line".into(),
+                exit_code: Some(139),
+                tid: Some(2),
+                pc: Some(0x80001a2c),
+                mtval: Some(0),
+                regs: vec![("ra".into(), 0x80001a10), ("sp".into(), 0x80ffff40)],
+                enosys: Some((291, "SYS_statx".into())),
+                oom: None,
+                frames: vec![0x80001a10, 0x80000f3e],
+                complete: true,
+            }
+        );
+
+        let mut rendered = Vec::new();
+        record
+            .render(&mut rendered, &|addr, _| {
+                (addr == 0x80001a2c).then(|| "main".into())
+            })
+            .unwrap();
+        let rendered = String::from_utf8(rendered).unwrap();
+        assert!(rendered.contains("guest crash: exception (exit 139)"));
+        assert!(rendered.contains("pc:      0x80001a2c - main"));
+        assert!(rendered.contains("   1:         0x80000f3e - <unknown>"));
+    }
+}
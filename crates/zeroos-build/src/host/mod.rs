@@ -1 +1,2 @@
 pub mod backtrace;
+pub mod crash;
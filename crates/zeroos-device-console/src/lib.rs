@@ -1,6 +1,117 @@
+//! Console devices: the platform's standard streams.
+//!
+//! Every console descriptor answers the terminal ioctls (`TCGETS`/`TCSETS*`, `TIOCGWINSZ`/
+//! `TIOCSWINSZ`, `TIOCGPGRP`/`TIOCSPGRP`) from one synthetic terminal, so `isatty` is true
+//! and libc line-buffers stdout as it would on a Linux TTY. Settings written with `TCSETS*`
+//! are remembered but do not change how bytes are transferred.
+//!
+//! Output can additionally be buffered in the kernel with an [`Output`] stream.
+
 #![no_std]
 
-use vfs_core::{noop_close, noop_ioctl, noop_seek, FileOps};
+use foundation::utils::KernelLock;
+use vfs_core::{
+    chrdev_fstat, noop_close, noop_getdents64, noop_seek, poll_readable, poll_writable, FdEntry,
+    FileOps, VfsResult,
+};
+
+pub type ReadFn = fn(*mut u8, *mut u8, usize) -> isize;
+pub type WriteFn = fn(*mut u8, *const u8, usize) -> isize;
+
+/// The kernel's `struct termios` (asm-generic layout), as `TCGETS`/`TCSETS` exchange it.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct KernelTermios {
+    c_iflag: u32,
+    c_oflag: u32,
+    c_cflag: u32,
+    c_lflag: u32,
+    c_line: u8,
+    c_cc: [u8; 19],
+}
+
+/// Cooked mode with the usual control characters, as a fresh Linux terminal reports.
+const DEFAULT_TERMIOS: KernelTermios = {
+    let mut c_cc = [0u8; 19];
+    c_cc[libc::VINTR] = 0x03;
+    c_cc[libc::VQUIT] = 0x1c;
+    c_cc[libc::VERASE] = 0x7f;
+    c_cc[libc::VKILL] = 0x15;
+    c_cc[libc::VEOF] = 0x04;
+    c_cc[libc::VMIN] = 1;
+    c_cc[libc::VSTART] = 0x11;
+    c_cc[libc::VSTOP] = 0x13;
+    c_cc[libc::VSUSP] = 0x1a;
+    c_cc[libc::VREPRINT] = 0x12;
+    c_cc[libc::VDISCARD] = 0x0f;
+    c_cc[libc::VWERASE] = 0x17;
+    c_cc[libc::VLNEXT] = 0x16;
+    KernelTermios {
+        c_iflag: libc::ICRNL | libc::IXON,
+        c_oflag: libc::OPOST | libc::ONLCR,
+        c_cflag: libc::B38400 | libc::CS8 | libc::CREAD | libc::HUPCL,
+        c_lflag: libc::ISIG
+            | libc::ICANON
+            | libc::ECHO
+            | libc::ECHOE
+            | libc::ECHOK
+            | libc::ECHOCTL
+            | libc::ECHOKE
+            | libc::IEXTEN,
+        c_line: 0,
+        c_cc,
+    }
+};
+
+struct Terminal {
+    termios: KernelTermios,
+    winsize: libc::winsize,
+}
+
+static TERMINAL: KernelLock<Terminal> = KernelLock::new(Terminal {
+    termios: DEFAULT_TERMIOS,
+    winsize: libc::winsize {
+        ws_row: 24,
+        ws_col: 80,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    },
+});
+
+/// Terminal ioctls shared by every console descriptor (and `/dev/tty`).
+pub fn console_ioctl(_file: *mut u8, request: usize, arg: usize) -> isize {
+    const TCGETS: usize = libc::TCGETS as usize;
+    const TCSETS: usize = libc::TCSETS as usize;
+    const TCSETSW: usize = libc::TCSETSW as usize;
+    const TCSETSF: usize = libc::TCSETSF as usize;
+    const TIOCGWINSZ: usize = libc::TIOCGWINSZ as usize;
+    const TIOCSWINSZ: usize = libc::TIOCSWINSZ as usize;
+    const TIOCGPGRP: usize = libc::TIOCGPGRP as usize;
+    const TIOCSPGRP: usize = libc::TIOCSPGRP as usize;
+
+    if arg == 0 {
+        return match request {
+            TCGETS | TCSETS | TCSETSW | TCSETSF | TIOCGWINSZ | TIOCSWINSZ | TIOCGPGRP
+            | TIOCSPGRP => -(libc::EFAULT as isize),
+            _ => -(libc::ENOTTY as isize),
+        };
+    }
+    TERMINAL.with_mut(|t| unsafe {
+        match request {
+            TCGETS => (arg as *mut KernelTermios).write_unaligned(t.termios),
+            TCSETS | TCSETSW | TCSETSF => {
+                t.termios = (arg as *const KernelTermios).read_unaligned();
+            }
+            TIOCGWINSZ => (arg as *mut libc::winsize).write_unaligned(t.winsize),
+            TIOCSWINSZ => t.winsize = (arg as *const libc::winsize).read_unaligned(),
+            // The guest is the only process: its own group is the foreground one.
+            TIOCGPGRP => (arg as *mut i32).write_unaligned(1),
+            TIOCSPGRP => {}
+            _ => return -(libc::ENOTTY as isize),
+        }
+        0
+    })
+}
 
 fn console_read_eof(_file: *mut u8, _buf: *mut u8, _count: usize) -> isize {
     0
@@ -14,7 +125,7 @@ fn console_write_unsupported(_file: *mut u8, _buf: *const u8, _count: usize) ->
     -(libc::EBADF as isize)
 }
 
-pub const fn read_only_fops(read_fn: Option<fn(*mut u8, *mut u8, usize) -> isize>) -> FileOps {
+pub const fn read_only_fops(read_fn: Option<ReadFn>) -> FileOps {
     FileOps {
         read: if let Some(f) = read_fn {
             f
@@ -24,20 +135,333 @@ pub const fn read_only_fops(read_fn: Option<fn(*mut u8, *mut u8, usize) -> isize
         write: console_write_unsupported,
         release: noop_close,
         llseek: noop_seek,
-        ioctl: noop_ioctl,
+        ioctl: console_ioctl,
+        fstat: chrdev_fstat,
+        getdents64: noop_getdents64,
+        pread: None,
+        pwrite: None,
+        poll: poll_readable,
+        mmap: None,
     }
 }
 
-pub const fn write_only_fops(write_fn: fn(*mut u8, *const u8, usize) -> isize) -> FileOps {
+pub const fn write_only_fops(write_fn: WriteFn) -> FileOps {
     FileOps {
         read: console_read_unsupported,
         write: write_fn,
         release: noop_close,
         llseek: noop_seek,
-        ioctl: noop_ioctl,
+        ioctl: console_ioctl,
+        fstat: chrdev_fstat,
+        getdents64: noop_getdents64,
+        pread: None,
+        pwrite: None,
+        poll: poll_writable,
+        mmap: None,
     }
 }
 
 pub use read_only_fops as stdin_fops;
 pub use write_only_fops as stdout_fops;
 pub use write_only_fops as stderr_fops;
+
+/// When an [`Output`] passes buffered bytes on to its sink.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferMode {
+    /// Every write goes straight to the sink, like a Linux TTY.
+    Unbuffered,
+    /// Complete lines are passed on; a partial line waits for its newline.
+    Line,
+    /// Bytes are passed on only when the buffer fills.
+    Block,
+}
+
+impl BufferMode {
+    /// Parse `unbuffered`, `line` or `block` at compile time; `None` is [`Self::Unbuffered`].
+    pub const fn parse(s: Option<&str>) -> Self {
+        let Some(s) = s else {
+            return Self::Unbuffered;
+        };
+        match s.as_bytes() {
+            b"unbuffered" => Self::Unbuffered,
+            b"line" => Self::Line,
+            b"block" => Self::Block,
+            _ => panic!("console buffer mode must be unbuffered, line or block"),
+        }
+    }
+}
+
+/// Bytes an [`Output`] holds back.
+pub const OUTPUT_BUFFER_SIZE: usize = 1024;
+
+struct OutputState {
+    sink: WriteFn,
+    mode: BufferMode,
+    buf: [u8; OUTPUT_BUFFER_SIZE],
+    len: usize,
+}
+
+impl OutputState {
+    fn flush(&mut self) {
+        if self.len > 0 {
+            (self.sink)(core::ptr::null_mut(), self.buf.as_ptr(), self.len);
+            self.len = 0;
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let pass_through = match self.mode {
+            BufferMode::Unbuffered => bytes.len(),
+            BufferMode::Line => bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1),
+            BufferMode::Block => 0,
+        };
+        let (now, later) = bytes.split_at(pass_through);
+        if !now.is_empty() {
+            if self.len + now.len() <= OUTPUT_BUFFER_SIZE {
+                self.buf[self.len..self.len + now.len()].copy_from_slice(now);
+                self.len += now.len();
+                self.flush();
+            } else {
+                self.flush();
+                (self.sink)(core::ptr::null_mut(), now.as_ptr(), now.len());
+            }
+        }
+        for chunk in later.chunks(OUTPUT_BUFFER_SIZE) {
+            if self.len + chunk.len() > OUTPUT_BUFFER_SIZE {
+                self.flush();
+            }
+            self.buf[self.len..self.len + chunk.len()].copy_from_slice(chunk);
+            self.len += chunk.len();
+        }
+    }
+}
+
+/// A console output stream that buffers in the kernel before calling its sink.
+///
+/// Register it with [`Output::entry`]; the buffer is flushed when the descriptor is closed
+/// (including at shutdown), on `TCSETSW`/`TCSETSF`, and when the mode changes.
+pub struct Output(KernelLock<OutputState>);
+
+impl Output {
+    pub const fn new(sink: WriteFn, mode: BufferMode) -> Self {
+        Self(KernelLock::new(OutputState {
+            sink,
+            mode,
+            buf: [0; OUTPUT_BUFFER_SIZE],
+            len: 0,
+        }))
+    }
+
+    pub fn set_mode(&self, mode: BufferMode) {
+        self.0.with_mut(|out| {
+            out.flush();
+            out.mode = mode;
+        });
+    }
+
+    pub fn mode(&self) -> BufferMode {
+        self.0.with(|out| out.mode)
+    }
+
+    pub fn flush(&self) {
+        self.0.with_mut(OutputState::flush);
+    }
+
+    /// A descriptor writing to this stream.
+    pub fn entry(&'static self) -> FdEntry {
+        FdEntry {
+            ops: &OUTPUT_FOPS,
+            private_data: self as *const Self as *mut u8,
+        }
+    }
+
+    /// Create device node `path` (under `/dev`) writing to this stream.
+    pub fn register_device(&'static self, path: &'static str) -> VfsResult<()> {
+        vfs_core::register_device_with(path, open_output, self as *const Self as *mut u8)
+    }
+}
+
+/// Open callback of an [`Output`] device node (`ctx` is the stream); it cannot be read, so
+/// read-only opens are refused.
+pub fn open_output(ctx: *mut u8, _path: &str, flags: i32, _mode: u32) -> VfsResult<FdEntry> {
+    if flags & libc::O_ACCMODE == libc::O_RDONLY {
+        return Err(-(libc::EACCES as isize));
+    }
+    Ok(output(ctx).entry())
+}
+
+fn output(file: *mut u8) -> &'static Output {
+    unsafe { &*(file as *const Output) }
+}
+
+fn output_write(file: *mut u8, buf: *const u8, count: usize) -> isize {
+    if count == 0 {
+        return 0;
+    }
+    if buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    let bytes = unsafe { core::slice::from_raw_parts(buf, count) };
+    output(file).0.with_mut(|out| out.write(bytes));
+    count as isize
+}
+
+fn output_release(file: *mut u8) -> isize {
+    output(file).flush();
+    0
+}
+
+fn output_ioctl(file: *mut u8, request: usize, arg: usize) -> isize {
+    if request == libc::TCSETSW as usize || request == libc::TCSETSF as usize {
+        output(file).flush();
+    }
+    console_ioctl(file, request, arg)
+}
+
+pub const OUTPUT_FOPS: FileOps = FileOps {
+    read: console_read_unsupported,
+    write: output_write,
+    release: output_release,
+    llseek: noop_seek,
+    ioctl: output_ioctl,
+    fstat: chrdev_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: poll_writable,
+    mmap: None,
+};
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    static SINK: KernelLock<Vec<Vec<u8>>> = KernelLock::new(Vec::new());
+
+    fn record(_file: *mut u8, buf: *const u8, count: usize) -> isize {
+        let bytes = unsafe { core::slice::from_raw_parts(buf, count) };
+        SINK.with_mut(|s| s.push(bytes.to_vec()));
+        count as isize
+    }
+
+    #[test]
+    fn test_console_is_a_tty() {
+        let mut termios = DEFAULT_TERMIOS;
+        termios.c_lflag = 0;
+        let r = console_ioctl(
+            core::ptr::null_mut(),
+            libc::TCGETS as usize,
+            &mut termios as *mut _ as usize,
+        );
+        assert_eq!(r, 0, "TCGETS should succeed so isatty is true");
+        assert_ne!(
+            termios.c_lflag & libc::ICANON,
+            0,
+            "terminal starts in cooked mode"
+        );
+
+        let mut ws: libc::winsize = unsafe { core::mem::zeroed() };
+        let r = console_ioctl(
+            core::ptr::null_mut(),
+            libc::TIOCGWINSZ as usize,
+            &mut ws as *mut _ as usize,
+        );
+        assert_eq!(r, 0);
+        assert_eq!((ws.ws_row, ws.ws_col), (24, 80));
+        assert_eq!(
+            console_ioctl(core::ptr::null_mut(), libc::FIONBIO as usize, 1),
+            -(libc::ENOTTY as isize)
+        );
+        assert_eq!(
+            console_ioctl(core::ptr::null_mut(), libc::TCGETS as usize, 0),
+            -(libc::EFAULT as isize)
+        );
+    }
+
+    #[test]
+    fn test_output_buffering_modes() {
+        static OUT: Output = Output::new(record, BufferMode::Line);
+        let entry = OUT.entry();
+        let write = |s: &[u8]| (entry.ops.write)(entry.private_data, s.as_ptr(), s.len());
+
+        write(b"hello ");
+        assert!(SINK.with(|s| s.is_empty()), "a partial line is held back");
+        write(b"world\nand ");
+        assert_eq!(SINK.with(|s| s.concat()), b"hello world\n");
+
+        OUT.set_mode(BufferMode::Block);
+        assert_eq!(SINK.with(|s| s.concat()), b"hello world\nand ");
+        write(b"more\n");
+        assert_eq!(
+            SINK.with(|s| s.len()),
+            2,
+            "block mode waits for a full buffer"
+        );
+        (entry.ops.release)(entry.private_data);
+        assert_eq!(SINK.with(|s| s.concat()), b"hello world\nand more\n");
+
+        OUT.set_mode(BufferMode::Unbuffered);
+        write(b"x");
+        assert_eq!(SINK.with(|s| s.last().cloned()), Some(b"x".to_vec()));
+    }
+
+    #[test]
+    fn test_buffer_mode_parse() {
+        assert_eq!(BufferMode::parse(None), BufferMode::Unbuffered);
+        assert_eq!(
+            BufferMode::parse(Some("unbuffered")),
+            BufferMode::Unbuffered
+        );
+        assert_eq!(BufferMode::parse(Some("line")), BufferMode::Line);
+        assert_eq!(BufferMode::parse(Some("block")), BufferMode::Block);
+    }
+
+    #[test]
+    #[should_panic(expected = "console buffer mode")]
+    fn test_buffer_mode_parse_rejects_unknown_names() {
+        BufferMode::parse(Some("full"));
+    }
+
+    #[test]
+    fn test_output_flushes_at_mode_boundaries() {
+        static FLUSHED: KernelLock<Vec<usize>> = KernelLock::new(Vec::new());
+        fn count(_file: *mut u8, _buf: *const u8, count: usize) -> isize {
+            FLUSHED.with_mut(|f| f.push(count));
+            count as isize
+        }
+        static OUT: Output = Output::new(count, BufferMode::Line);
+        let entry = OUT.entry();
+        let write = |s: &[u8]| (entry.ops.write)(entry.private_data, s.as_ptr(), s.len());
+
+        // Line mode passes on everything up to and including the last newline.
+        write(b"ab");
+        write(b"c\nde\nf");
+        assert_eq!(FLUSHED.with(|f| f.clone()), [7]);
+        OUT.flush();
+        assert_eq!(FLUSHED.with(|f| f.clone()), [7, 1]);
+
+        // Block mode holds a full buffer and flushes it when the next byte does not fit.
+        OUT.set_mode(BufferMode::Block);
+        write(&[b'x'; OUTPUT_BUFFER_SIZE]);
+        assert_eq!(FLUSHED.with(|f| f.len()), 2);
+        write(b"y");
+        assert_eq!(FLUSHED.with(|f| f.clone()), [7, 1, OUTPUT_BUFFER_SIZE]);
+        OUT.flush();
+        assert_eq!(FLUSHED.with(|f| f.clone()), [7, 1, OUTPUT_BUFFER_SIZE, 1]);
+    }
+
+    #[test]
+    fn test_output_device_open() {
+        static OUT: Output = Output::new(record, BufferMode::Unbuffered);
+        let ctx = &OUT as *const Output as *mut u8;
+        let entry = open_output(ctx, "/dev/console", libc::O_WRONLY, 0).unwrap();
+        assert_eq!(entry.private_data, ctx);
+        assert_eq!(
+            open_output(ctx, "/dev/console", libc::O_RDONLY, 0).err(),
+            Some(-(libc::EACCES as isize))
+        );
+    }
+}
@@ -0,0 +1,70 @@
+#![no_std]
+
+use core::ptr::null_mut;
+use vfs_core::{
+    chrdev_fstat, noop_close, noop_getdents64, noop_ioctl, noop_seek, poll_ready, FdEntry, FileOps,
+};
+
+fn full_read(_file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    if count == 0 {
+        return 0;
+    }
+    if buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+
+    unsafe {
+        core::ptr::write_bytes(buf, 0, count);
+    }
+
+    count as isize
+}
+
+fn full_write(_file: *mut u8, _buf: *const u8, _count: usize) -> isize {
+    -(libc::ENOSPC as isize)
+}
+
+pub const FULL_FOPS: FileOps = FileOps {
+    read: full_read,
+    write: full_write,
+    release: noop_close,
+    llseek: noop_seek,
+    ioctl: noop_ioctl,
+    fstat: chrdev_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: poll_ready,
+    mmap: None,
+};
+
+pub fn full_factory() -> FdEntry {
+    FdEntry {
+        ops: &FULL_FOPS,
+        private_data: null_mut(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_read() {
+        let mut buf = [0xffu8; 64];
+        let result = full_read(null_mut(), buf.as_mut_ptr(), buf.len());
+        assert_eq!(result, 64, "/dev/full read should succeed");
+        assert!(buf.iter().all(|&b| b == 0), "/dev/full should read zeros");
+    }
+
+    #[test]
+    fn test_full_write() {
+        let buf = [0u8; 64];
+        let result = full_write(null_mut(), buf.as_ptr(), buf.len());
+        assert_eq!(
+            result,
+            -(libc::ENOSPC as isize),
+            "/dev/full write should fail with ENOSPC"
+        );
+    }
+}
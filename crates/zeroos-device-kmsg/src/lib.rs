@@ -0,0 +1,196 @@
+//! `/dev/kmsg`: the kernel log ring (`foundation::kfn::log`).
+//!
+//! Each read returns one record as `prio,seq,usec,-;text\n`, like Linux. Each descriptor keeps
+//! its own position; a reader that falls behind the ring gets `EPIPE` once and then continues
+//! at the oldest record still kept. Writes append one record each, honouring a `<N>` priority
+//! prefix.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::fmt::Write;
+
+use foundation::kfn::log::{klog_bounds, klog_next, klog_record, KLOG_LINE};
+use vfs_core::{chrdev_fstat, noop_getdents64, noop_ioctl, FdEntry, FileOps};
+
+/// Priority of records written without a `<N>` prefix (`LOG_USER | LOG_INFO`).
+const USER_INFO: u8 = 14;
+
+/// Sequence number of the next record this descriptor reads.
+fn cursor<'a>(file: *mut u8) -> &'a mut u64 {
+    unsafe { &mut *(file as *mut u64) }
+}
+
+/// `core::fmt::Write` into a byte slice; fails once it is full.
+struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(core::fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Reading past the newest record returns `EAGAIN` rather than blocking, as if the descriptor
+/// were non-blocking (how `dmesg`-style readers open it).
+fn kmsg_read(file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    if buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    let pos = cursor(file);
+    let (oldest, _) = klog_bounds();
+    if *pos < oldest {
+        *pos = oldest;
+        return -(libc::EPIPE as isize);
+    }
+    let Some(record) = klog_next(*pos) else {
+        return -(libc::EAGAIN as isize);
+    };
+
+    let mut line = [0u8; KLOG_LINE + 64];
+    let mut w = SliceWriter {
+        buf: &mut line,
+        len: 0,
+    };
+    let _ = write!(
+        w,
+        "{},{},{},-;",
+        record.prio,
+        record.seq,
+        record.time_ns / 1000
+    );
+    for &b in record.text() {
+        let _ = match b {
+            b'\\' => w.write_str("\\\\"),
+            b' '..=b'~' => w.write_char(b as char),
+            _ => write!(w, "\\x{b:02x}"),
+        };
+    }
+    let _ = w.write_char('\n');
+    let n = w.len;
+    if n > count {
+        return -(libc::EINVAL as isize);
+    }
+    unsafe { core::ptr::copy_nonoverlapping(line.as_ptr(), buf, n) };
+    *pos = record.seq + 1;
+    n as isize
+}
+
+fn kmsg_write(_file: *mut u8, buf: *const u8, count: usize) -> isize {
+    if count == 0 {
+        return 0;
+    }
+    if buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    let mut text = unsafe { core::slice::from_raw_parts(buf, count) };
+    let mut prio = USER_INFO;
+    if let Some(rest) = text.strip_prefix(b"<") {
+        if let Some(end) = rest.iter().position(|&b| b == b'>') {
+            let parsed = core::str::from_utf8(&rest[..end])
+                .ok()
+                .and_then(|s| s.parse::<u8>().ok());
+            if let Some(p) = parsed {
+                prio = p;
+                text = &rest[end + 1..];
+            }
+        }
+    }
+    klog_record(prio, text.strip_suffix(b"\n").unwrap_or(text));
+    count as isize
+}
+
+/// Only rewinding to the oldest record (`SEEK_SET`) or skipping to the end (`SEEK_END`).
+fn kmsg_llseek(file: *mut u8, offset: isize, whence: i32) -> isize {
+    if offset != 0 {
+        return -(libc::ESPIPE as isize);
+    }
+    let (oldest, next) = klog_bounds();
+    *cursor(file) = match whence {
+        libc::SEEK_SET => oldest,
+        libc::SEEK_END => next,
+        _ => return -(libc::EINVAL as isize),
+    };
+    0
+}
+
+fn kmsg_release(file: *mut u8) -> isize {
+    drop(unsafe { Box::from_raw(file as *mut u64) });
+    0
+}
+
+fn kmsg_poll(file: *mut u8) -> i16 {
+    let readable = if klog_next(*cursor(file)).is_some() {
+        libc::POLLIN | libc::POLLRDNORM
+    } else {
+        0
+    };
+    readable | libc::POLLOUT | libc::POLLWRNORM
+}
+
+pub const KMSG_FOPS: FileOps = FileOps {
+    read: kmsg_read,
+    write: kmsg_write,
+    release: kmsg_release,
+    llseek: kmsg_llseek,
+    ioctl: noop_ioctl,
+    fstat: chrdev_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: kmsg_poll,
+    mmap: None,
+};
+
+/// Open a reader positioned at the oldest record still kept.
+pub fn kmsg_factory() -> FdEntry {
+    let (oldest, _) = klog_bounds();
+    FdEntry {
+        ops: &KMSG_FOPS,
+        private_data: Box::into_raw(Box::new(oldest)) as *mut u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kmsg_write_then_read() {
+        let entry = kmsg_factory();
+        let file = entry.private_data;
+        kmsg_llseek(file, 0, libc::SEEK_END);
+
+        let msg = b"<3>disk on fire\n";
+        assert_eq!(
+            kmsg_write(file, msg.as_ptr(), msg.len()),
+            msg.len() as isize
+        );
+
+        let mut buf = [0u8; 256];
+        let n = kmsg_read(file, buf.as_mut_ptr(), buf.len());
+        assert!(n > 0, "a record should be readable");
+        let line = core::str::from_utf8(&buf[..n as usize]).unwrap();
+        assert!(line.starts_with("3,"), "priority comes first: {line}");
+        assert!(
+            line.ends_with(",-;disk on fire\n"),
+            "text comes last: {line}"
+        );
+
+        assert_eq!(
+            kmsg_read(file, buf.as_mut_ptr(), buf.len()),
+            -(libc::EAGAIN as isize)
+        );
+        kmsg_release(file);
+    }
+}
@@ -1,7 +1,9 @@
 #![no_std]
 
 use core::ptr::null_mut;
-use vfs_core::{noop_close, noop_ioctl, noop_seek, FdEntry, FileOps};
+use vfs_core::{
+    chrdev_fstat, noop_close, noop_getdents64, noop_ioctl, noop_seek, poll_ready, FdEntry, FileOps,
+};
 
 fn null_read(_file: *mut u8, _buf: *mut u8, _count: usize) -> isize {
     0
@@ -11,12 +13,26 @@ fn null_write(_file: *mut u8, _buf: *const u8, count: usize) -> isize {
     count as isize
 }
 
+fn null_pread(_file: *mut u8, _buf: *mut u8, _count: usize, _offset: usize) -> isize {
+    0
+}
+
+fn null_pwrite(_file: *mut u8, _buf: *const u8, count: usize, _offset: usize) -> isize {
+    count as isize
+}
+
 pub const NULL_FOPS: FileOps = FileOps {
     read: null_read,
     write: null_write,
     release: noop_close,
     llseek: noop_seek,
     ioctl: noop_ioctl,
+    fstat: chrdev_fstat,
+    getdents64: noop_getdents64,
+    pread: Some(null_pread),
+    pwrite: Some(null_pwrite),
+    poll: poll_ready,
+    mmap: None,
 };
 
 pub fn null_factory() -> FdEntry {
@@ -43,4 +59,11 @@ mod tests {
         let result = null_write(null_mut(), buf.as_ptr(), buf.len());
         assert_eq!(result, 64, "/dev/null write should succeed");
     }
+
+    #[test]
+    fn test_null_pread_is_eof_at_any_offset() {
+        let mut buf = [0u8; 64];
+        let result = null_pread(null_mut(), buf.as_mut_ptr(), buf.len(), 4096);
+        assert_eq!(result, 0, "/dev/null pread should return EOF");
+    }
 }
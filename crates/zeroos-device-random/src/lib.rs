@@ -0,0 +1,44 @@
+//! `/dev/random`, drawing from the same kernel RNG as `/dev/urandom`.
+//!
+//! The RNG is deterministic and never runs out, so reads never block.
+
+#![no_std]
+
+use core::ptr::null_mut;
+
+use vfs_core::{
+    chrdev_fstat, noop_close, noop_getdents64, noop_ioctl, noop_seek, poll_ready, FileOps,
+};
+
+fn random_read(_file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    unsafe { foundation::kfn::random::krandom(buf, count) }
+}
+
+/// Writes are accepted, as on Linux, but not mixed in: the RNG stays a function of its seed.
+fn random_write(_file: *mut u8, _buf: *const u8, count: usize) -> isize {
+    count as isize
+}
+
+pub const RANDOM_FOPS: FileOps = FileOps {
+    read: random_read,
+    write: random_write,
+    release: noop_close,
+    llseek: noop_seek,
+    ioctl: noop_ioctl,
+    fstat: chrdev_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: poll_ready,
+    mmap: None,
+};
+
+pub fn random_factory() -> vfs_core::FdEntry {
+    vfs_core::FdEntry {
+        ops: &RANDOM_FOPS,
+        private_data: null_mut(),
+    }
+}
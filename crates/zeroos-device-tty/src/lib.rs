@@ -0,0 +1,68 @@
+//! `/dev/tty`: the controlling terminal, i.e. the platform console.
+//!
+//! Output goes straight to the console; there is no input, so reads hit end-of-file. The
+//! terminal ioctls are the console's, so `/dev/tty` and the standard streams share one
+//! termios and window size.
+
+#![no_std]
+
+use core::ptr::null_mut;
+
+use device_console::console_ioctl;
+use vfs_core::{
+    chrdev_fstat, noop_close, noop_getdents64, noop_seek, poll_ready, FdEntry, FileOps,
+};
+
+fn tty_read(_file: *mut u8, _buf: *mut u8, _count: usize) -> isize {
+    0
+}
+
+fn tty_write(_file: *mut u8, buf: *const u8, count: usize) -> isize {
+    if count == 0 {
+        return 0;
+    }
+    if buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    foundation::kfn::kconsole_write(unsafe { core::slice::from_raw_parts(buf, count) });
+    count as isize
+}
+
+pub const TTY_FOPS: FileOps = FileOps {
+    read: tty_read,
+    write: tty_write,
+    release: noop_close,
+    llseek: noop_seek,
+    ioctl: console_ioctl,
+    fstat: chrdev_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: poll_ready,
+    mmap: None,
+};
+
+pub fn tty_factory() -> FdEntry {
+    FdEntry {
+        ops: &TTY_FOPS,
+        private_data: null_mut(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tty_reports_terminal() {
+        let mut ws: libc::winsize = unsafe { core::mem::zeroed() };
+        let r = console_ioctl(
+            null_mut(),
+            libc::TIOCGWINSZ as usize,
+            &mut ws as *mut _ as usize,
+        );
+        assert_eq!(r, 0, "TIOCGWINSZ should succeed");
+        assert_eq!((ws.ws_row, ws.ws_col), (24, 80));
+        assert_eq!(tty_read(null_mut(), null_mut(), 16), 0, "no input: EOF");
+    }
+}
@@ -2,7 +2,7 @@
 
 use core::ptr::null_mut;
 
-use vfs_core::FileOps;
+use vfs_core::{chrdev_fstat, noop_getdents64, poll_ready, FileOps};
 
 fn urandom_read(_file: *mut u8, buf: *mut u8, count: usize) -> isize {
     if count != 0 && buf.is_null() {
@@ -33,6 +33,12 @@ pub const URANDOM_FOPS: FileOps = FileOps {
     release: urandom_close,
     llseek: urandom_seek,
     ioctl: urandom_ioctl,
+    fstat: chrdev_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: poll_ready,
+    mmap: None,
 };
 
 pub fn urandom_factory() -> vfs_core::FdEntry {
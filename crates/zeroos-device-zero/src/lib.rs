@@ -1,7 +1,9 @@
 #![no_std]
 
 use core::ptr::null_mut;
-use vfs_core::{noop_close, noop_ioctl, noop_seek, FdEntry, FileOps};
+use vfs_core::{
+    chrdev_fstat, noop_close, noop_getdents64, noop_ioctl, noop_seek, poll_ready, FdEntry, FileOps,
+};
 
 fn zero_read(_file: *mut u8, buf: *mut u8, count: usize) -> isize {
     if count == 0 {
@@ -22,12 +24,26 @@ fn zero_write(_file: *mut u8, _buf: *const u8, count: usize) -> isize {
     count as isize
 }
 
+fn zero_pread(file: *mut u8, buf: *mut u8, count: usize, _offset: usize) -> isize {
+    zero_read(file, buf, count)
+}
+
+fn zero_pwrite(_file: *mut u8, _buf: *const u8, count: usize, _offset: usize) -> isize {
+    count as isize
+}
+
 pub const ZERO_FOPS: FileOps = FileOps {
     read: zero_read,
     write: zero_write,
     release: noop_close,
     llseek: noop_seek,
     ioctl: noop_ioctl,
+    fstat: chrdev_fstat,
+    getdents64: noop_getdents64,
+    pread: Some(zero_pread),
+    pwrite: Some(zero_pwrite),
+    poll: poll_ready,
+    mmap: None,
 };
 
 pub fn zero_factory() -> FdEntry {
@@ -55,4 +71,12 @@ mod tests {
         let result = zero_write(null_mut(), buf.as_ptr(), buf.len());
         assert_eq!(result, 64, "/dev/zero write should succeed");
     }
+
+    #[test]
+    fn test_zero_pread_ignores_offset() {
+        let mut buf = [0xFFu8; 64];
+        let result = zero_pread(null_mut(), buf.as_mut_ptr(), buf.len(), 4096);
+        assert_eq!(result, 64, "/dev/zero pread should succeed");
+        assert!(buf.iter().all(|&b| b == 0), "Buffer should be all zeros");
+    }
 }
@@ -0,0 +1,10 @@
+//! Linux errno values shared by the kernel crates.
+//!
+//! The `libc` crate has no errno constants for bare-metal targets, where `runtime-nostd` guests
+//! run the same kernel functions and scheduler.
+
+pub const EPERM: i32 = 1;
+pub const ESRCH: i32 = 3;
+pub const EAGAIN: i32 = 11;
+pub const EDEADLK: i32 = 35;
+pub const ETIMEDOUT: i32 = 110;
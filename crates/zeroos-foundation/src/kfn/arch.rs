@@ -184,6 +184,91 @@ cfg_if! {
         pub unsafe fn ktrap_frame_get_fault_addr(regs: *const u8) -> usize {
             (crate::KERNEL.arch.trap_frame_get_fault_addr)(regs)
         }
+
+        /// Get the stack pointer from a trap frame.
+        ///
+        /// # Safety
+        /// `regs` must point to a valid trap frame.
+        #[inline(always)]
+        pub unsafe fn ktrap_frame_get_sp(regs: *const u8) -> usize {
+            (crate::KERNEL.arch.trap_frame_get_sp)(regs)
+        }
+
+        /// Get the frame pointer from a trap frame.
+        ///
+        /// # Safety
+        /// `regs` must point to a valid trap frame.
+        #[inline(always)]
+        pub unsafe fn ktrap_frame_get_fp(regs: *const u8) -> usize {
+            (crate::KERNEL.arch.trap_frame_get_fp)(regs)
+        }
+
+        /// Call `f` with the name and value of every register saved in a trap frame.
+        ///
+        /// # Safety
+        /// `regs` must point to a valid trap frame.
+        #[inline]
+        pub unsafe fn ktrap_frame_regs(regs: *const u8, f: &mut dyn FnMut(&'static str, usize)) {
+            (crate::KERNEL.arch.trap_frame_regs)(regs, f)
+        }
+
+        /// Set an argument register in a trap frame.
+        ///
+        /// # Safety
+        /// `regs` must point to a valid trap frame.
+        #[inline(always)]
+        pub unsafe fn ktrap_frame_set_arg(regs: *mut u8, idx: usize, val: usize) {
+            (crate::KERNEL.arch.trap_frame_set_arg)(regs, idx, val)
+        }
+
+        /// Set the return address in a trap frame.
+        ///
+        /// # Safety
+        /// `regs` must point to a valid trap frame.
+        #[inline(always)]
+        pub unsafe fn ktrap_frame_set_ra(regs: *mut u8, ra: usize) {
+            (crate::KERNEL.arch.trap_frame_set_ra)(regs, ra)
+        }
+
+        #[inline]
+        pub fn ksigreturn_trampoline() -> usize {
+            unsafe { (crate::KERNEL.arch.sigreturn_trampoline)() }
+        }
+
+        #[inline]
+        pub fn kmcontext_size() -> usize {
+            unsafe { (crate::KERNEL.arch.mcontext_size)() }
+        }
+
+        /// Save the user registers of a trap frame into a `mcontext_t`.
+        ///
+        /// # Safety
+        /// `regs` must point to a valid trap frame and `mctx` to `kmcontext_size()` bytes.
+        #[inline]
+        pub unsafe fn kmcontext_save(regs: *const u8, mctx: *mut u8) {
+            (crate::KERNEL.arch.mcontext_save)(regs, mctx)
+        }
+
+        /// Restore the user registers of a trap frame from a `mcontext_t`.
+        ///
+        /// # Safety
+        /// `regs` must point to a valid trap frame and `mctx` to `kmcontext_size()` bytes.
+        #[inline]
+        pub unsafe fn kmcontext_restore(regs: *mut u8, mctx: *const u8) {
+            (crate::KERNEL.arch.mcontext_restore)(regs, mctx)
+        }
+
+        /// Return the number of instructions retired by this hart.
+        #[inline(always)]
+        pub fn kinstret() -> u64 {
+            unsafe { (crate::KERNEL.arch.instret)() }
+        }
+
+        /// Make `[base, base + size)` inaccessible (replaces the previous region of `slot`).
+        #[inline]
+        pub fn kprotect_region(slot: usize, base: usize, size: usize) {
+            unsafe { (crate::KERNEL.arch.protect_region)(slot, base, size) }
+        }
     } else {
         /// Stub implementation of `kswitch_to`.
         ///
@@ -366,5 +451,87 @@ cfg_if! {
         pub unsafe fn ktrap_frame_get_fault_addr(_regs: *const u8) -> usize {
             0
         }
+
+        /// Stub implementation of `ktrap_frame_get_sp`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn ktrap_frame_get_sp(_regs: *const u8) -> usize {
+            0
+        }
+
+        /// Stub implementation of `ktrap_frame_get_fp`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn ktrap_frame_get_fp(_regs: *const u8) -> usize {
+            0
+        }
+
+        /// Stub implementation of `ktrap_frame_regs`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn ktrap_frame_regs(_regs: *const u8, _f: &mut dyn FnMut(&'static str, usize)) {}
+
+        /// Stub implementation of `ktrap_frame_set_arg`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn ktrap_frame_set_arg(_regs: *mut u8, _idx: usize, _val: usize) {}
+
+        /// Stub implementation of `ktrap_frame_set_ra`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn ktrap_frame_set_ra(_regs: *mut u8, _ra: usize) {}
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn ksigreturn_trampoline() -> usize {
+            0
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kmcontext_size() -> usize {
+            0
+        }
+
+        /// Stub implementation of `kmcontext_save`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn kmcontext_save(_regs: *const u8, _mctx: *mut u8) {}
+
+        /// Stub implementation of `kmcontext_restore`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn kmcontext_restore(_regs: *mut u8, _mctx: *const u8) {}
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kinstret() -> u64 {
+            0
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kprotect_region(_slot: usize, _base: usize, _size: usize) {}
     }
 }
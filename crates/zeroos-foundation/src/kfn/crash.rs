@@ -0,0 +1,207 @@
+//! Structured crash reports.
+//!
+//! A run that dies of a panic, an unhandled exception or a fatal signal ends in [`kcrash`],
+//! which prints one machine-readable record on the platform console before exiting:
+//!
+//! ```text
+//! @@CRASH BEGIN
+//! cause exception
+//! message SIGSEGV: load access fault at 0x0
+//! exit 139
+//! tid 1
+//! pc 0x80001a2c
+//! mtval 0x0
+//! reg ra 0x80001a10
+//! reg sp 0x80ffff40
+//! enosys 291 SYS_statx
+//! frame 0x80001a10
+//! frame 0x80000f3e
+//! @@CRASH END
+//! ```
+//!
+//! Each line is a key and a value separated by one space; `message` escapes `\` and newlines
+//! as `\\` and `\n`. `pc`, `mtval` and the `reg` lines come from the trap frame of the crashing
+//! context and are left out when there is none (a panic in code that did not trap). `frame`
+//! lines are return addresses, innermost first, from the unwinder registered with
+//! [`kregister_unwinder`]. `enosys` is the last syscall that failed as unsupported. With the
+//! `crash-oom` feature, `oom` is the size of the last allocation that failed, if it has not
+//! succeeded since, and a panic or signal that follows a failed allocation is reported as
+//! [`CrashCause::OutOfMemory`]; without it allocations are not tracked.
+//!
+//! `spike-build run` extracts the record and prints it symbolized.
+
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use super::ConsoleWriter;
+use crate::utils::KernelLock;
+
+/// First line of a crash record.
+pub const CRASH_BEGIN: &str = "@@CRASH BEGIN";
+/// Last line of a crash record.
+pub const CRASH_END: &str = "@@CRASH END";
+
+/// Why the run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashCause {
+    /// A Rust panic in the kernel or a `no_std` guest.
+    Panic,
+    /// A panic or fatal signal after an allocation failed.
+    OutOfMemory,
+    /// A syscall the OS personality does not implement (fatal only in strict builds).
+    UnsupportedSyscall,
+    /// A CPU exception that no handler resolved.
+    Exception,
+    /// A signal whose action terminates the process (e.g. `SIGABRT` from `abort()`).
+    Signal,
+}
+
+impl CrashCause {
+    /// Name used in the `cause` line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CrashCause::Panic => "panic",
+            CrashCause::OutOfMemory => "oom",
+            CrashCause::UnsupportedSyscall => "unsupported-syscall",
+            CrashCause::Exception => "exception",
+            CrashCause::Signal => "signal",
+        }
+    }
+}
+
+pub struct CrashReport<'a> {
+    pub cause: CrashCause,
+    pub message: fmt::Arguments<'a>,
+    /// Exit status of the run (`128 + sig` for signals, as on Linux).
+    pub exit_code: i32,
+    /// Trap frame of the crashing context, or null.
+    pub regs: *const u8,
+}
+
+/// Walk the frame chain from frame pointer `fp` (the caller's frame if 0), passing each return
+/// address to `f`.
+pub type Unwinder = unsafe fn(fp: usize, f: &mut dyn FnMut(usize));
+
+static UNWINDER: KernelLock<Option<Unwinder>> = KernelLock::new(None);
+static LAST_ENOSYS: KernelLock<Option<(usize, &'static str)>> = KernelLock::new(None);
+/// Size of the last failed allocation; 0 once an allocation succeeds again.
+static ALLOC_FAILED: AtomicUsize = AtomicUsize::new(0);
+static CRASHED: AtomicBool = AtomicBool::new(false);
+
+/// Set the unwinder that produces the `frame` lines.
+pub fn kregister_unwinder(unwinder: Unwinder) {
+    UNWINDER.with_mut(|u| *u = Some(unwinder));
+}
+
+/// Remember syscall `nr` as the last one that failed as unsupported.
+pub fn knote_unsupported_syscall(nr: usize, name: &'static str) {
+    LAST_ENOSYS.with_mut(|e| *e = Some((nr, name)));
+}
+
+/// Record the outcome of an allocation of `size` bytes; `kmalloc` and `krealloc` call this with
+/// the `crash-oom` feature.
+#[inline]
+pub fn knote_alloc(size: usize, ok: bool) {
+    if !ok {
+        ALLOC_FAILED.store(size.max(1), Ordering::Relaxed);
+    } else if ALLOC_FAILED.load(Ordering::Relaxed) != 0 {
+        ALLOC_FAILED.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Print the crash record for `report` and exit with its status.
+///
+/// Locks stop waiting first, as they do during shutdown, so a crash inside a critical section
+/// still gets its record out. A crash while reporting (e.g. a fault while walking a corrupt
+/// stack) goes straight to the exit.
+pub fn kcrash(report: &CrashReport) -> ! {
+    kcrash_report(report);
+    super::kexit(report.exit_code)
+}
+
+/// Print the crash record for `report` without exiting, for callers that leave through a
+/// platform hook of their own. Only the first record of a run is printed.
+pub fn kcrash_report(report: &CrashReport) {
+    if !CRASHED.swap(true, Ordering::AcqRel) {
+        super::shutdown::kbegin_teardown();
+        emit(report);
+    }
+}
+
+fn emit(report: &CrashReport) {
+    let mut w = ConsoleWriter;
+    let oom = ALLOC_FAILED.load(Ordering::Relaxed);
+    let cause = match report.cause {
+        CrashCause::Panic | CrashCause::Signal if oom != 0 => CrashCause::OutOfMemory,
+        cause => cause,
+    };
+
+    let _ = writeln!(w, "{CRASH_BEGIN}");
+    let _ = writeln!(w, "cause {}", cause.as_str());
+    let _ = w.write_str("message ");
+    let _ = Escaped(&mut w).write_fmt(report.message);
+    let _ = writeln!(w, "\nexit {}", report.exit_code);
+    let _ = writeln!(w, "tid {}", super::scheduler::kcurrent_tid());
+
+    let mut fp = 0;
+    let regs = report.regs;
+    if !regs.is_null() {
+        use super::arch::*;
+        unsafe {
+            let _ = writeln!(w, "pc {:#x}", ktrap_frame_get_pc(regs));
+            let _ = writeln!(w, "mtval {:#x}", ktrap_frame_get_fault_addr(regs));
+            ktrap_frame_regs(regs, &mut |name, value| {
+                let _ = writeln!(w, "reg {name} {value:#x}");
+            });
+            fp = ktrap_frame_get_fp(regs);
+        }
+    }
+    if let Some((nr, name)) = LAST_ENOSYS.with(|e| *e) {
+        let _ = writeln!(w, "enosys {nr} {name}");
+    }
+    if oom != 0 {
+        let _ = writeln!(w, "oom {oom}");
+    }
+    if let Some(unwind) = UNWINDER.with(|u| *u) {
+        unsafe {
+            unwind(fp, &mut |ra| {
+                let _ = writeln!(w, "frame {ra:#x}");
+            })
+        };
+    }
+    let _ = writeln!(w, "{CRASH_END}");
+}
+
+/// Keeps a message on its line: escapes `\` and newlines.
+struct Escaped<W>(W);
+
+impl<W: Write> Write for Escaped<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.0.write_str("\\n")?;
+            }
+            for (j, part) in line.split('\\').enumerate() {
+                if j > 0 {
+                    self.0.write_str("\\\\")?;
+                }
+                self.0.write_str(part)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    #[test]
+    fn test_message_is_escaped_onto_one_line() {
+        let mut out = String::new();
+        let path = "C:\\tmp";
+        let _ = write!(Escaped(&mut out), "panicked at a.rs:1:2:\n{path}");
+        assert_eq!(out, "panicked at a.rs:1:2:\\nC:\\\\tmp");
+    }
+}
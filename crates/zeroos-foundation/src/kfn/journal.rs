@@ -0,0 +1,268 @@
+//! Record/replay journal for nondeterministic kernel events.
+//!
+//! In `Record` mode every scheduling decision, clock reading and nondeterministic syscall result
+//! is appended to a platform sink (`__platform_journal_write`). In `Replay` mode the same events
+//! are consumed from the platform (`__platform_journal_read`) instead of being computed, so a run
+//! recorded on one host reproduces exactly on another.
+//!
+//! Journaled inputs: `ktime_ns` (and so every clock syscall), `getrandom`, and the data returned
+//! by `read`, `readv`, `pread64`, `preadv` and `recvfrom` on any descriptor. Data moved between
+//! descriptors inside the kernel (`sendfile`, `copy_file_range`) is not journaled, so replaying
+//! such a copy from an outside source (the console, a host file, `/dev/random`) can diverge.
+//!
+//! Records are `(kind, payload)` pairs; framing is up to the platform.
+
+use cfg_if::cfg_if;
+
+/// Scheduler choice of the next thread (payload: tid as `u64`, 0 = keep running).
+#[allow(dead_code)]
+pub const J_SCHED: u8 = 1;
+/// `getrandom` output bytes.
+#[allow(dead_code)]
+pub const J_RANDOM: u8 = 2;
+/// Input transfer result (payload: return value as `u64`, then the bytes read); see
+/// [`kjournal_read`].
+#[allow(dead_code)]
+pub const J_READ: u8 = 3;
+/// Clock reading (payload: nanoseconds as `u64`); recording retires extra instructions, so the
+/// replayed clock comes from here rather than from the instruction counter.
+#[allow(dead_code)]
+pub const J_TIME: u8 = 4;
+/// End of run (payload: exit code as `u64`); written by the shutdown sequence.
+#[allow(dead_code)]
+pub const J_EXIT: u8 = 5;
+
+/// Maximum payload size of a single record.
+#[allow(dead_code)]
+pub const JOURNAL_MAX_RECORD: usize = 256;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalMode {
+    Off,
+    Record,
+    Replay,
+}
+
+cfg_if! {
+    if #[cfg(feature = "journal")] {
+        use crate::utils::KernelLock;
+
+        extern "C" {
+            fn __platform_journal_write(kind: u8, data: *const u8, len: usize);
+            fn __platform_journal_read(kind: *mut u8, buf: *mut u8, cap: usize) -> isize;
+        }
+
+        static MODE: KernelLock<JournalMode> = KernelLock::new(JournalMode::Off);
+
+        /// Select the journal mode. Call once during platform bootstrap, before threads exist.
+        pub fn kjournal_set_mode(mode: JournalMode) {
+            MODE.with_mut(|m| *m = mode);
+        }
+
+        #[inline(always)]
+        pub fn kjournal_mode() -> JournalMode {
+            MODE.with(|m| *m)
+        }
+
+        #[cold]
+        fn diverged(kind: u8, got: isize, got_kind: u8) -> ! {
+            // Reporting reads the clock (log timestamps), which must not replay any further.
+            MODE.with_mut(|m| *m = JournalMode::Off);
+            crate::kprintln!(
+                "[JOURNAL] replay diverged: expected kind={} got kind={} len={}",
+                kind,
+                got_kind,
+                got
+            );
+            panic!("journal replay diverged");
+        }
+
+        fn replay_into(kind: u8, buf: &mut [u8]) -> usize {
+            let mut got_kind = 0u8;
+            let n = unsafe { __platform_journal_read(&mut got_kind, buf.as_mut_ptr(), buf.len()) };
+            if n < 0 || got_kind != kind {
+                diverged(kind, n, got_kind);
+            }
+            n as usize
+        }
+
+        /// Journal a byte buffer in place: logged when recording, overwritten when replaying.
+        pub fn kjournal_bytes(kind: u8, buf: &mut [u8]) {
+            for chunk in buf.chunks_mut(JOURNAL_MAX_RECORD) {
+                match kjournal_mode() {
+                    JournalMode::Off => return,
+                    JournalMode::Record => unsafe {
+                        __platform_journal_write(kind, chunk.as_ptr(), chunk.len())
+                    },
+                    JournalMode::Replay => {
+                        let n = replay_into(kind, chunk);
+                        if n != chunk.len() {
+                            diverged(kind, n as isize, kind);
+                        }
+                    }
+                }
+            }
+        }
+
+        /// Journal a scalar: returns `value` (logging it) or the replayed value.
+        pub fn kjournal_u64(kind: u8, value: u64) -> u64 {
+            let mut bytes = value.to_le_bytes();
+            kjournal_bytes(kind, &mut bytes);
+            u64::from_le_bytes(bytes)
+        }
+
+        /// Journal a transfer of input into `buf` (at most `count` bytes) under [`J_READ`].
+        ///
+        /// `read` performs the transfer and returns a byte count or `-errno`. When replaying, the
+        /// recorded result and bytes replace the live ones; `read` still runs first if `rerun`,
+        /// to keep its side effects (file offsets, drained buffers). The console must not be
+        /// re-read: its input is the journal itself.
+        ///
+        /// # Safety
+        /// `buf` must be valid for writes of `count` bytes.
+        pub unsafe fn kjournal_read(
+            buf: *mut u8,
+            count: usize,
+            rerun: bool,
+            read: impl FnOnce() -> isize,
+        ) -> isize {
+            let ret = match kjournal_mode() {
+                JournalMode::Off => return read(),
+                JournalMode::Replay if !rerun => 0,
+                _ => read(),
+            };
+            let ret = kjournal_u64(J_READ, ret as u64) as isize;
+            if ret > 0 {
+                kjournal_bytes(J_READ, core::slice::from_raw_parts_mut(buf, (ret as usize).min(count)));
+            }
+            ret
+        }
+
+        /// Close the journal with a [`J_EXIT`] record.
+        ///
+        /// When replaying, the recorded exit code is checked so a run that ends differently is
+        /// reported instead of silently passing.
+        pub fn kjournal_commit(code: i32) {
+            let recorded = kjournal_u64(J_EXIT, code as i64 as u64) as i64 as i32;
+            if recorded != code {
+                crate::kprintln!(
+                    "[JOURNAL] replay diverged: recorded exit code {} got {}",
+                    recorded,
+                    code
+                );
+            }
+        }
+    } else {
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kjournal_set_mode(_mode: JournalMode) {}
+
+        #[inline(always)]
+        #[allow(dead_code)]
+        pub fn kjournal_mode() -> JournalMode {
+            JournalMode::Off
+        }
+
+        #[inline(always)]
+        #[allow(dead_code)]
+        pub fn kjournal_bytes(_kind: u8, _buf: &mut [u8]) {}
+
+        #[inline(always)]
+        #[allow(dead_code)]
+        pub fn kjournal_u64(_kind: u8, value: u64) -> u64 {
+            value
+        }
+
+        #[inline(always)]
+        pub fn kjournal_commit(_code: i32) {}
+    }
+}
+
+#[cfg(all(test, feature = "journal"))]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::kfn::time::ktime_ns;
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    /// Records written so far, and how many of them replay has consumed.
+    struct Journal {
+        records: Vec<(u8, Vec<u8>)>,
+        next: usize,
+    }
+
+    static JOURNAL: Mutex<Journal> = Mutex::new(Journal {
+        records: Vec::new(),
+        next: 0,
+    });
+
+    #[no_mangle]
+    extern "C" fn __platform_journal_write(kind: u8, data: *const u8, len: usize) {
+        let data = unsafe { core::slice::from_raw_parts(data, len) }.to_vec();
+        JOURNAL.lock().unwrap().records.push((kind, data));
+    }
+
+    #[no_mangle]
+    extern "C" fn __platform_journal_read(kind: *mut u8, buf: *mut u8, cap: usize) -> isize {
+        let mut journal = JOURNAL.lock().unwrap();
+        let Some((k, data)) = journal.records.get(journal.next).cloned() else {
+            return -1;
+        };
+        journal.next += 1;
+        let n = data.len().min(cap);
+        unsafe {
+            *kind = k;
+            core::ptr::copy_nonoverlapping(data.as_ptr(), buf, n);
+        }
+        n as isize
+    }
+
+    #[no_mangle]
+    extern "C" fn __platform_stdout_write(msg: *const u8, len: usize) {
+        use std::io::Write;
+        let _ = std::io::stdout().write_all(unsafe { core::slice::from_raw_parts(msg, len) });
+    }
+
+    #[no_mangle]
+    extern "C" fn __platform_exit(code: i32) -> ! {
+        std::process::exit(code)
+    }
+
+    /// A read that interleaves with clock readings, as a polling loop does.
+    fn read_between_clocks(input: &[u8], rerun: bool) -> (u64, isize, Vec<u8>, u64) {
+        let mut buf = [0u8; 16];
+        let ptr = buf.as_mut_ptr();
+        let before = ktime_ns();
+        let n = unsafe {
+            kjournal_read(ptr, buf.len(), rerun, || {
+                core::ptr::copy_nonoverlapping(input.as_ptr(), ptr, input.len());
+                input.len() as isize
+            })
+        };
+        let after = ktime_ns();
+        (before, n, buf[..n.max(0) as usize].to_vec(), after)
+    }
+
+    #[test]
+    fn test_replay_reproduces_clock_and_reads() {
+        kjournal_set_mode(JournalMode::Record);
+        let console = read_between_clocks(b"typed input", false);
+        let file = read_between_clocks(b"file data", true);
+        kjournal_commit(0);
+
+        // The live clock keeps ticking and the live sources now return something else.
+        kjournal_set_mode(JournalMode::Replay);
+        let replayed_console = read_between_clocks(b"", false);
+        let replayed_file = read_between_clocks(b"changed", true);
+        kjournal_commit(0);
+        kjournal_set_mode(JournalMode::Off);
+
+        assert_eq!(replayed_console, console);
+        assert_eq!(replayed_file, file);
+        let journal = JOURNAL.lock().unwrap();
+        assert_eq!(journal.next, journal.records.len());
+    }
+}
@@ -0,0 +1,128 @@
+//! Kernel log ring buffer.
+//!
+//! Every [`kprintln!`](crate::kprintln) line is kept as a record here as well as printed, and
+//! guests may append their own (`/dev/kmsg` writes). Records are numbered from 0; once the ring
+//! is full the oldest are overwritten, so readers must expect gaps.
+
+use crate::utils::KernelLock;
+
+/// Records kept before the oldest are overwritten.
+pub const KLOG_RECORDS: usize = 64;
+
+/// Longest record text; longer lines are truncated.
+pub const KLOG_LINE: usize = 120;
+
+/// Syslog priority of kernel messages (`LOG_KERN | LOG_INFO`).
+pub const KLOG_KERN_INFO: u8 = 6;
+
+/// One log line with its syslog priority and the [`ktime_ns`](super::time::ktime_ns) it was
+/// logged at.
+#[derive(Clone, Copy)]
+pub struct LogRecord {
+    pub seq: u64,
+    pub prio: u8,
+    pub time_ns: u64,
+    len: u8,
+    text: [u8; KLOG_LINE],
+}
+
+impl LogRecord {
+    const EMPTY: Self = Self {
+        seq: 0,
+        prio: 0,
+        time_ns: 0,
+        len: 0,
+        text: [0; KLOG_LINE],
+    };
+
+    pub fn text(&self) -> &[u8] {
+        &self.text[..self.len as usize]
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        let len = self.len as usize;
+        let n = bytes.len().min(KLOG_LINE - len);
+        self.text[len..len + n].copy_from_slice(&bytes[..n]);
+        self.len += n as u8;
+    }
+}
+
+struct LogRing {
+    records: [LogRecord; KLOG_RECORDS],
+    next_seq: u64,
+    /// `kprintln!` line still being assembled.
+    partial: LogRecord,
+}
+
+impl LogRing {
+    fn oldest(&self) -> u64 {
+        self.next_seq.saturating_sub(KLOG_RECORDS as u64)
+    }
+
+    fn commit(&mut self, mut record: LogRecord) {
+        record.seq = self.next_seq;
+        record.time_ns = super::time::ktime_ns();
+        self.records[(self.next_seq % KLOG_RECORDS as u64) as usize] = record;
+        self.next_seq += 1;
+    }
+}
+
+static LOG: KernelLock<LogRing> = KernelLock::new(LogRing {
+    records: [LogRecord::EMPTY; KLOG_RECORDS],
+    next_seq: 0,
+    partial: LogRecord::EMPTY,
+});
+
+/// Append one record with syslog priority `prio`.
+pub fn klog_record(prio: u8, text: &[u8]) {
+    let mut record = LogRecord {
+        prio,
+        ..LogRecord::EMPTY
+    };
+    record.push(text);
+    LOG.with_mut(|log| log.commit(record));
+}
+
+/// Append kernel output; each `\n` completes a record.
+pub fn klog_write(mut bytes: &[u8]) {
+    LOG.with_mut(|log| {
+        while !bytes.is_empty() {
+            let line = bytes.split(|&b| b == b'\n').next().unwrap_or(bytes);
+            log.partial.push(line);
+            if line.len() == bytes.len() {
+                break;
+            }
+            let record = LogRecord {
+                prio: KLOG_KERN_INFO,
+                ..log.partial
+            };
+            log.partial = LogRecord::EMPTY;
+            log.commit(record);
+            bytes = &bytes[line.len() + 1..];
+        }
+    });
+}
+
+/// The first record numbered `seq` or later that is still in the ring.
+pub fn klog_next(seq: u64) -> Option<LogRecord> {
+    LOG.with(|log| {
+        let seq = seq.max(log.oldest());
+        (seq < log.next_seq).then(|| log.records[(seq % KLOG_RECORDS as u64) as usize])
+    })
+}
+
+/// Sequence numbers of the oldest record still kept and of the next record to be written.
+pub fn klog_bounds() -> (u64, u64) {
+    LOG.with(|log| (log.oldest(), log.next_seq))
+}
+
+/// `core::fmt::Write` adapter that prints to the platform console and logs to the ring.
+pub struct KlogWriter;
+
+impl core::fmt::Write for KlogWriter {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        super::kconsole_write(s.as_bytes());
+        klog_write(s.as_bytes());
+        Ok(())
+    }
+}
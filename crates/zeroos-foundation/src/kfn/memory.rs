@@ -3,11 +3,16 @@ use core::ptr;
 
 use cfg_if::cfg_if;
 
+use crate::ops::MemoryStats;
+
 cfg_if! {
     if #[cfg(feature = "memory")] {
         #[inline]
         pub fn kmalloc(layout: Layout) -> *mut u8 {
-            unsafe { (crate::KERNEL.memory.alloc)(layout) }
+            let ptr = unsafe { (crate::KERNEL.memory.alloc)(layout) };
+            #[cfg(feature = "crash-oom")]
+            super::crash::knote_alloc(layout.size(), !ptr.is_null());
+            ptr
         }
 
         #[inline]
@@ -17,13 +22,21 @@ cfg_if! {
 
         #[inline]
         pub fn krealloc(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
-            unsafe { (crate::KERNEL.memory.realloc)(ptr, old_layout, new_size) }
+            let ptr = unsafe { (crate::KERNEL.memory.realloc)(ptr, old_layout, new_size) };
+            #[cfg(feature = "crash-oom")]
+            super::crash::knote_alloc(new_size, !ptr.is_null());
+            ptr
         }
 
         #[inline]
         pub fn kinit(heap_start: usize, heap_size: usize) {
             unsafe { (crate::KERNEL.memory.init)(heap_start, heap_size) }
         }
+
+        #[inline]
+        pub fn kstats() -> MemoryStats {
+            unsafe { (crate::KERNEL.memory.stats)() }
+        }
     } else {
         #[inline]
         #[allow(dead_code)]
@@ -44,6 +57,12 @@ cfg_if! {
         #[inline]
         #[allow(dead_code)]
         pub fn kinit(_heap_start: usize, _heap_size: usize) {}
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kstats() -> MemoryStats {
+            MemoryStats::default()
+        }
     }
 }
 
@@ -95,3 +114,111 @@ pub extern "C" fn krealloc_size(ptr: *mut u8, old_size: usize, new_size: usize)
         .map(|l| krealloc(ptr, l, new_size))
         .unwrap_or(ptr::null_mut())
 }
+
+// The region table is only fed when the memory subsystem is built in.
+cfg_if! {
+    if #[cfg(feature = "memory")] {
+        use crate::ops::MemoryRegion;
+        use crate::utils::KernelLock;
+
+        /// Maximum number of disjoint regions [`kmap_region`] tracks.
+        pub const MAX_REGIONS: usize = 64;
+
+        /// Address-space layout for `/proc/self/maps`: the image sections the platform registers at boot
+        /// plus whatever `mmap` hands out. Bookkeeping only; nothing here enforces permissions.
+        struct RegionTable {
+            regions: [MemoryRegion; MAX_REGIONS],
+            len: usize,
+        }
+
+        impl RegionTable {
+            /// Split the region containing `addr` (if any) so that no region straddles it.
+            fn split_at(&mut self, addr: usize) {
+                if let Some(i) = self.regions[..self.len]
+                    .iter()
+                    .position(|r| r.start < addr && addr < r.end)
+                {
+                    let upper = MemoryRegion {
+                        start: addr,
+                        ..self.regions[i]
+                    };
+                    // If the table is full the upper part is dropped.
+                    self.push(upper);
+                    self.regions[i].end = addr;
+                }
+            }
+
+            fn push(&mut self, region: MemoryRegion) {
+                if self.len < MAX_REGIONS {
+                    self.regions[self.len] = region;
+                    self.len += 1;
+                }
+            }
+
+            fn remove(&mut self, start: usize, end: usize) {
+                self.split_at(start);
+                self.split_at(end);
+                let mut i = 0;
+                while i < self.len {
+                    let r = self.regions[i];
+                    if r.start >= start && r.end <= end {
+                        self.len -= 1;
+                        self.regions[i] = self.regions[self.len];
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+
+            fn protect(&mut self, start: usize, end: usize, perms: [u8; 3]) {
+                self.split_at(start);
+                self.split_at(end);
+                for r in &mut self.regions[..self.len] {
+                    if r.start >= start && r.end <= end {
+                        r.perms[..3].copy_from_slice(&perms);
+                    }
+                }
+            }
+        }
+
+        const EMPTY_REGION: MemoryRegion = MemoryRegion {
+            start: 0,
+            end: 0,
+            perms: *b"---p",
+            name: "",
+        };
+
+        static REGIONS: KernelLock<RegionTable> = KernelLock::new(RegionTable {
+            regions: [EMPTY_REGION; MAX_REGIONS],
+            len: 0,
+        });
+
+        /// Record `region`, replacing whatever was recorded for its range.
+        pub fn kmap_region(region: MemoryRegion) {
+            if region.start < region.end {
+                REGIONS.with_mut(|t| {
+                    t.remove(region.start, region.end);
+                    t.push(region);
+                });
+            }
+        }
+
+        /// Forget `[start, end)`; regions partially covered are trimmed.
+        pub fn kunmap_region(start: usize, end: usize) {
+            REGIONS.with_mut(|t| t.remove(start, end));
+        }
+
+        /// Set the `rwx` part of the permissions of every recorded byte in `[start, end)`.
+        pub fn kset_region_perms(start: usize, end: usize, perms: [u8; 3]) {
+            REGIONS.with_mut(|t| t.protect(start, end, perms));
+        }
+
+        /// Call `f` for each recorded region, in address order.
+        pub fn kregions(mut f: impl FnMut(&MemoryRegion)) {
+            let (mut regions, len) = REGIONS.with(|t| (t.regions, t.len));
+            let regions = &mut regions[..len];
+            regions.sort_unstable_by_key(|r| r.start);
+            regions.iter().for_each(&mut f);
+        }
+    }
+}
@@ -2,14 +2,51 @@ use cfg_if::cfg_if;
 
 extern "C" {
     fn __platform_exit(code: i32) -> !;
+    fn __platform_stdout_write(msg: *const u8, len: usize);
 }
 
+/// Run the shutdown sequence (see [`shutdown`]) and exit the platform with `code`.
 #[inline]
 pub fn kexit(code: i32) -> ! {
+    shutdown::kshutdown(code);
     unsafe { __platform_exit(code) }
 }
 
+/// Write raw bytes to the platform console, bypassing the VFS.
+///
+/// Kernel diagnostics use this so they stay visible even when the `debug` crate is disabled.
+#[inline]
+pub fn kconsole_write(bytes: &[u8]) {
+    if !bytes.is_empty() {
+        unsafe { __platform_stdout_write(bytes.as_ptr(), bytes.len()) }
+    }
+}
+
+/// `core::fmt::Write` adapter over [`kconsole_write`].
+pub struct ConsoleWriter;
+
+impl core::fmt::Write for ConsoleWriter {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        kconsole_write(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Print a line to the platform console and the kernel log (always enabled, unlike
+/// `debug::writeln!`).
+#[macro_export]
+macro_rules! kprintln {
+    ($($arg:tt)*) => {{
+        use core::fmt::Write;
+        let _ = core::writeln!($crate::kfn::log::KlogWriter, $($arg)*);
+    }};
+}
+
+pub mod crash;
+pub mod log;
+pub mod shutdown;
 pub mod thread;
+pub mod time;
 
 cfg_if! {
     if #[cfg(feature = "memory")] {
@@ -58,3 +95,11 @@ cfg_if! {
         pub(crate) mod trap;
     }
 }
+
+cfg_if! {
+    if #[cfg(feature = "journal")] {
+        pub mod journal;
+    } else {
+        pub(crate) mod journal;
+    }
+}
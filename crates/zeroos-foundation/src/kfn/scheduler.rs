@@ -1,11 +1,14 @@
 use cfg_if::cfg_if;
 
+#[allow(unused_imports)]
+use crate::ops::{ThreadInfo, ThreadStatus};
+
 #[cfg(feature = "scheduler")]
 #[allow(unused_imports)]
 pub use crate::kfn::thread::ktrap_frame_addr;
 #[cfg(feature = "scheduler")]
 #[allow(unused_imports)]
-pub use crate::kfn::thread::{kalloc_kstack, ThreadAnchor};
+pub use crate::kfn::thread::{kalloc_kstack, kfree_kstack, ThreadAnchor};
 
 cfg_if! {
     if #[cfg(feature = "scheduler")] {
@@ -33,6 +36,11 @@ cfg_if! {
             }
         }
 
+        #[inline]
+        pub fn kspawn_kernel_thread(entry: extern "C" fn(arg: usize) -> !, arg: usize) -> isize {
+            unsafe { (crate::KERNEL.scheduler.spawn_kernel_thread)(entry, arg) }
+        }
+
         #[inline]
         pub fn ksched_yield() -> isize {
             unsafe { (crate::KERNEL.scheduler.yield_now)() }
@@ -58,6 +66,11 @@ cfg_if! {
             unsafe { (crate::KERNEL.scheduler.wait_on_addr)(addr, expected) }
         }
 
+        #[inline]
+        pub fn kwait_on_addr_until(addr: usize, expected: i32, deadline_ns: u64) -> isize {
+            unsafe { (crate::KERNEL.scheduler.wait_on_addr_until)(addr, expected, deadline_ns) }
+        }
+
         #[inline]
         pub fn kwake_on_addr(addr: usize, count: usize) -> usize {
             unsafe { (crate::KERNEL.scheduler.wake_on_addr)(addr, count) }
@@ -67,6 +80,26 @@ cfg_if! {
         pub fn kset_clear_on_exit_addr(addr: usize) -> isize {
             unsafe { (crate::KERNEL.scheduler.set_clear_on_exit_addr)(addr) }
         }
+
+        #[inline]
+        pub fn kjoin_thread(tid: usize) -> isize {
+            unsafe { (crate::KERNEL.scheduler.join_thread)(tid) }
+        }
+
+        #[inline]
+        pub fn kthread_info(index: usize) -> Option<ThreadInfo> {
+            unsafe { (crate::KERNEL.scheduler.thread_info)(index) }
+        }
+
+        #[inline]
+        pub fn kset_thread_name(name: &[u8]) {
+            unsafe { (crate::KERNEL.scheduler.set_thread_name)(name) }
+        }
+
+        #[inline(always)]
+        pub fn kaccount_syscall() {
+            unsafe { (crate::KERNEL.scheduler.account_syscall)() }
+        }
     } else {
         #[inline]
         #[allow(dead_code)]
@@ -86,6 +119,12 @@ cfg_if! {
             -1
         }
 
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kspawn_kernel_thread(_entry: extern "C" fn(arg: usize) -> !, _arg: usize) -> isize {
+            -1
+        }
+
         #[inline]
         #[allow(dead_code)]
         pub fn ksched_yield() -> isize {
@@ -116,6 +155,14 @@ cfg_if! {
             0
         }
 
+        /// Without a scheduler nothing else runs to change `addr`; just wait out the deadline.
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kwait_on_addr_until(_addr: usize, _expected: i32, deadline_ns: u64) -> isize {
+            crate::kfn::time::kspin_until(deadline_ns);
+            -(crate::errno::ETIMEDOUT as isize)
+        }
+
         #[inline]
         #[allow(dead_code)]
         pub fn kwake_on_addr(_addr: usize, _count: usize) -> usize {
@@ -127,5 +174,71 @@ cfg_if! {
         pub fn kset_clear_on_exit_addr(_addr: usize) -> isize {
             0
         }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kjoin_thread(_tid: usize) -> isize {
+            -3 // ESRCH
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kthread_info(_index: usize) -> Option<ThreadInfo> {
+            None
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kset_thread_name(_name: &[u8]) {}
+
+        #[inline(always)]
+        #[allow(dead_code)]
+        pub fn kaccount_syscall() {}
+    }
+}
+
+/// Iterate over a snapshot of every thread known to the scheduler.
+#[allow(dead_code)]
+pub fn kthreads() -> impl Iterator<Item = ThreadInfo> {
+    (0..kthread_count()).filter_map(kthread_info)
+}
+
+/// Return a snapshot of thread `tid`, if it exists.
+#[allow(dead_code)]
+pub fn kthread_by_tid(tid: usize) -> Option<ThreadInfo> {
+    kthreads().find(|t| t.tid == tid)
+}
+
+/// Print a `ps`-like table of all threads to the platform console.
+#[allow(dead_code)]
+pub fn kdump_threads() {
+    let current = kcurrent_tid();
+    crate::kprintln!(
+        "{:>5} {:<16} {:<8} {:>10} {:>8} {:>14}",
+        "TID",
+        "NAME",
+        "STATE",
+        "SYSCALLS",
+        "YIELDS",
+        "INSTRET"
+    );
+    for t in kthreads() {
+        let name = core::str::from_utf8(t.name()).unwrap_or("?");
+        let state = match t.status {
+            ThreadStatus::Ready => "ready",
+            ThreadStatus::Running => "running",
+            ThreadStatus::Blocked => "blocked",
+            ThreadStatus::Exited => "exited",
+        };
+        crate::kprintln!(
+            "{}{:>4} {:<16} {:<8} {:>10} {:>8} {:>14}",
+            if t.tid == current { '*' } else { ' ' },
+            t.tid,
+            name,
+            state,
+            t.syscalls,
+            t.yields,
+            t.instret
+        );
     }
 }
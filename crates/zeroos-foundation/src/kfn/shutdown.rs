@@ -0,0 +1,99 @@
+//! Ordered kernel shutdown, run by [`kexit`](super::kexit) before the platform exits.
+//!
+//! Stages run in declaration order of [`ShutdownStage`]; hooks within a stage run in
+//! registration order. Built-in work (closing VFS descriptors, committing the journal) runs
+//! first in its stage. A hook that exits again (e.g. by panicking) skips the rest of the chain
+//! and goes straight to the platform.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::utils::KernelLock;
+
+/// Shutdown stages, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ShutdownStage {
+    /// Flush and close open files so device buffers reach the host.
+    Vfs,
+    /// Print statistics and reports.
+    Stats,
+    /// Commit the record/replay journal.
+    Journal,
+}
+
+/// A shutdown hook; receives the exit code.
+pub type ShutdownHook = fn(code: i32);
+
+/// Maximum number of registered hooks.
+pub const MAX_SHUTDOWN_HOOKS: usize = 16;
+
+struct Hooks {
+    hooks: [(ShutdownStage, Option<ShutdownHook>); MAX_SHUTDOWN_HOOKS],
+    len: usize,
+}
+
+static HOOKS: KernelLock<Hooks> = KernelLock::new(Hooks {
+    hooks: [(ShutdownStage::Vfs, None); MAX_SHUTDOWN_HOOKS],
+    len: 0,
+});
+
+/// Set once the kernel is going down, by the shutdown chain or a crash report.
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+/// Set once the shutdown chain has started.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Register `hook` to run during `stage`. Returns `false` if the table is full.
+pub fn kshutdown_register(stage: ShutdownStage, hook: ShutdownHook) -> bool {
+    HOOKS.with_mut(|h| {
+        if h.len == MAX_SHUTDOWN_HOOKS {
+            return false;
+        }
+        h.hooks[h.len] = (stage, Some(hook));
+        h.len += 1;
+        true
+    })
+}
+
+/// Whether the kernel is going down: the shutdown sequence or a crash report has started.
+#[inline]
+pub fn kshutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::Acquire)
+}
+
+/// Mark the kernel as going down ahead of the shutdown chain (see [`kcrash`](super::crash)).
+pub(crate) fn kbegin_teardown() {
+    SHUTTING_DOWN.store(true, Ordering::Release);
+}
+
+fn run_stage(stage: ShutdownStage, code: i32) {
+    match stage {
+        ShutdownStage::Vfs => super::vfs::kshutdown(),
+        ShutdownStage::Stats => {}
+        ShutdownStage::Journal => super::journal::kjournal_commit(code),
+    }
+
+    // Hooks may register further hooks; re-read the length each time.
+    let mut i = 0;
+    while let Some((s, hook)) = HOOKS.with(|h| (i < h.len).then(|| h.hooks[i])) {
+        if s == stage {
+            if let Some(hook) = hook {
+                hook(code);
+            }
+        }
+        i += 1;
+    }
+}
+
+/// Run the shutdown chain; a no-op if it is already running (re-entered exit).
+pub(crate) fn kshutdown(code: i32) {
+    if STARTED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    kbegin_teardown();
+    for stage in [
+        ShutdownStage::Vfs,
+        ShutdownStage::Stats,
+        ShutdownStage::Journal,
+    ] {
+        run_stage(stage, code);
+    }
+}
@@ -26,8 +26,23 @@ pub struct ThreadAnchor {
 
     /// Cached trap-frame address (computed at kstack allocation).
     pub trap_frame_addr: usize,
+
+    /// Overflow canary, always [`KSTACK_CANARY`].
+    /// Kept last: the kernel stack grows down towards the anchor, so this word is hit first.
+    pub canary: usize,
 }
 
+/// Value stored in `ThreadAnchor::canary`; checked on trap exit and context switch.
+pub const KSTACK_CANARY: usize = 0x57AC_CA4A_57AC_CA4A_u64 as usize;
+
+/// Size of the inaccessible guard region below each kernel stack (`kstack-guard` only).
+#[cfg(feature = "kstack-guard")]
+pub const KSTACK_GUARD_SIZE: usize = 4096;
+#[cfg(feature = "kstack-guard")]
+const KSTACK_GUARD_SIZE_OR_ZERO: usize = KSTACK_GUARD_SIZE;
+#[cfg(not(feature = "kstack-guard"))]
+const KSTACK_GUARD_SIZE_OR_ZERO: usize = 0;
+
 #[inline(always)]
 fn compute_trap_frame_addr(
     kstack_base: usize,
@@ -78,10 +93,13 @@ pub fn kalloc_kstack(
 ) -> *mut ThreadAnchor {
     assert!(kstack_size.is_power_of_two());
 
-    let base = crate::kfn::memory::kmalloc_aligned(kstack_size, kstack_size);
-    if base.is_null() {
+    // With `kstack-guard`: [guard | anchor ... stack ... trap frame]; the guard is never handed out.
+    let alloc =
+        crate::kfn::memory::kmalloc_aligned(kstack_size + KSTACK_GUARD_SIZE_OR_ZERO, kstack_size);
+    if alloc.is_null() {
         return core::ptr::null_mut();
     }
+    let base = alloc.wrapping_add(KSTACK_GUARD_SIZE_OR_ZERO);
     let anchor_ptr = base as *mut ThreadAnchor;
     let tf_addr = compute_trap_frame_addr(
         base as usize,
@@ -102,12 +120,96 @@ pub fn kalloc_kstack(
                 stash1: 0,
                 stash2: 0,
                 trap_frame_addr: tf_addr,
+                canary: KSTACK_CANARY,
             },
         );
     }
     anchor_ptr
 }
 
+/// Free a kernel stack returned by [`kalloc_kstack`], including its guard region.
+///
+/// # Safety
+/// `anchor_ptr` must come from [`kalloc_kstack`], and no thread may still run on the stack.
+pub unsafe fn kfree_kstack(anchor_ptr: *mut ThreadAnchor) {
+    let kstack_size = (*anchor_ptr).kstack_size;
+    let layout =
+        core::alloc::Layout::from_size_align(kstack_size + KSTACK_GUARD_SIZE_OR_ZERO, kstack_size)
+            .expect("invalid kernel stack layout");
+    crate::kfn::memory::kfree(
+        (anchor_ptr as *mut u8).wrapping_sub(KSTACK_GUARD_SIZE_OR_ZERO),
+        layout,
+    );
+}
+
+/// Report a kernel stack overflow of thread `tid` and stop.
+#[cold]
+#[inline(never)]
+pub fn kstack_overflow(anchor_ptr: *const ThreadAnchor, tid: usize, fault_addr: usize) -> ! {
+    crate::kprintln!(
+        "[KSTACK] kernel stack overflow: tid={} anchor=0x{:x} fault_addr=0x{:x}",
+        tid,
+        anchor_ptr as usize,
+        fault_addr
+    );
+    panic!("kernel stack overflow (tid={})", tid);
+}
+
+/// Verify the overflow canary of a kernel stack, reporting an overflow if it was clobbered.
+///
+/// # Safety
+/// `anchor_ptr` must point to an anchor returned by [`kalloc_kstack`].
+#[inline(always)]
+pub unsafe fn kcheck_kstack(anchor_ptr: *const ThreadAnchor, tid: usize) {
+    if (*anchor_ptr).canary != KSTACK_CANARY {
+        kstack_overflow(anchor_ptr, tid, 0);
+    }
+}
+
+/// [`kcheck_kstack`] for the running thread, called from the arch trap-exit path.
+///
+/// # Safety
+/// `anchor_ptr` must point to the current thread's anchor.
+#[inline(never)]
+pub unsafe extern "C" fn kcheck_kstack_current(anchor_ptr: *const ThreadAnchor) {
+    kcheck_kstack(anchor_ptr, crate::kfn::scheduler::kcurrent_tid());
+}
+
+/// Report a fault at `fault_addr` as a kernel stack overflow of the running thread if it hit the
+/// guard region (or the anchor itself) below its stack. Returns normally otherwise.
+///
+/// # Safety
+/// `anchor_ptr` must be null or point to the current thread's anchor.
+pub unsafe fn kcheck_kstack_fault(anchor_ptr: *const ThreadAnchor, fault_addr: usize) {
+    if anchor_ptr.is_null() {
+        return;
+    }
+    let base = anchor_ptr as usize;
+    let low = base - KSTACK_GUARD_SIZE_OR_ZERO;
+    if (low..base + core::mem::size_of::<ThreadAnchor>()).contains(&fault_addr) {
+        kstack_overflow(
+            anchor_ptr,
+            crate::kfn::scheduler::kcurrent_tid(),
+            fault_addr,
+        );
+    }
+}
+
+/// Make the guard region below `anchor_ptr`'s stack the active hardware-protected guard.
+///
+/// Called when the thread gets the CPU; a no-op without the `kstack-guard` feature.
+#[inline(always)]
+pub fn kguard_kstack(anchor_ptr: *const ThreadAnchor) {
+    #[cfg(feature = "kstack-guard")]
+    crate::kfn::arch::kprotect_region(
+        crate::ops::PROTECT_SLOT_KSTACK,
+        anchor_ptr as usize - KSTACK_GUARD_SIZE,
+        KSTACK_GUARD_SIZE,
+    );
+    #[cfg(not(feature = "kstack-guard"))]
+    let _ = anchor_ptr;
+}
+
 /// Compute the trap-frame address at the top of the thread's kernel stack.
 ///
 /// # Safety
@@ -135,3 +237,81 @@ pub unsafe fn ktrap_frame_addr(anchor_ptr: *const ThreadAnchor) -> usize {
 
     tf
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KSTACK_SIZE: usize = 16 * 1024;
+
+    #[cfg(not(feature = "memory"))]
+    #[test]
+    fn test_failed_allocation_returns_null() {
+        assert!(kalloc_kstack(KSTACK_SIZE, 64, 16).is_null());
+    }
+
+    #[cfg(feature = "memory")]
+    mod host {
+        extern crate std;
+
+        use super::*;
+        use crate::ops::{MemoryOps, MemoryStats};
+        use core::alloc::Layout;
+        use core::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::{Mutex, MutexGuard, Once};
+
+        static FAIL: AtomicBool = AtomicBool::new(false);
+
+        fn alloc(layout: Layout) -> *mut u8 {
+            if FAIL.load(Ordering::Relaxed) {
+                return core::ptr::null_mut();
+            }
+            unsafe { std::alloc::alloc(layout) }
+        }
+
+        /// Install host memory ops; tests share the failure switch, so they run one at a time.
+        fn setup() -> MutexGuard<'static, ()> {
+            static SERIAL: Mutex<()> = Mutex::new(());
+            static INIT: Once = Once::new();
+            let guard = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+            INIT.call_once(|| {
+                crate::register_memory(MemoryOps {
+                    init: |_, _| {},
+                    alloc,
+                    dealloc: |ptr, layout| unsafe { std::alloc::dealloc(ptr, layout) },
+                    realloc: |_, _, _| core::ptr::null_mut(),
+                    stats: MemoryStats::default,
+                })
+            });
+            guard
+        }
+
+        #[test]
+        fn test_failed_allocation_returns_null() {
+            let _serial = setup();
+            FAIL.store(true, Ordering::Relaxed);
+            let anchor = kalloc_kstack(KSTACK_SIZE, 64, 16);
+            FAIL.store(false, Ordering::Relaxed);
+            assert!(anchor.is_null());
+        }
+
+        #[test]
+        fn test_stack_leaves_room_for_the_guard() {
+            let _serial = setup();
+            let anchor = kalloc_kstack(KSTACK_SIZE, 64, 16);
+            assert!(!anchor.is_null());
+            let a = unsafe { *anchor };
+            let base = anchor as usize;
+            assert_eq!(a.kstack_base, base);
+            assert_eq!(a.canary, KSTACK_CANARY);
+
+            // The guard sits below the stack, at the start of the (stack-aligned) allocation.
+            let alloc = base - KSTACK_GUARD_SIZE_OR_ZERO;
+            assert!(alloc.is_multiple_of(KSTACK_SIZE));
+            assert_eq!(a.kernel_sp, base + KSTACK_SIZE);
+            assert!(a.trap_frame_addr >= base + size_of::<ThreadAnchor>());
+            assert!(a.trap_frame_addr + 64 <= a.kernel_sp);
+            unsafe { kfree_kstack(anchor) };
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Deterministic kernel clock.
+//!
+//! Time is derived from the retired-instruction counter rather than a wall clock, so the same
+//! program on the same input observes the same timestamps on every run (and under every
+//! simulator speed). One retired instruction counts as [`KTIME_NS_PER_INSTRET`] nanoseconds.
+//!
+//! Without the `arch` subsystem there is no instruction counter; every clock read then advances
+//! the clock by one tick instead, which keeps it monotonic and deterministic.
+//!
+//! Readings are journaled (`J_TIME`): recording a run retires extra instructions, so a replay
+//! would otherwise see a different clock and take different deadline and scheduling paths.
+
+/// Nominal duration of one retired instruction (i.e. a 1 GHz, 1-IPC core).
+pub const KTIME_NS_PER_INSTRET: u64 = 1;
+
+/// Monotonic nanoseconds since boot.
+#[inline]
+pub fn ktime_ns() -> u64 {
+    use super::journal::{kjournal_u64, J_TIME};
+
+    kjournal_u64(J_TIME, counter_ns())
+}
+
+fn counter_ns() -> u64 {
+    #[cfg(feature = "arch")]
+    {
+        crate::kfn::arch::kinstret().wrapping_mul(KTIME_NS_PER_INSTRET)
+    }
+    #[cfg(not(feature = "arch"))]
+    {
+        static TICKS: crate::utils::KernelLock<u64> = crate::utils::KernelLock::new(0);
+        TICKS.with_mut(|t| {
+            *t += 1;
+            *t * KTIME_NS_PER_INSTRET
+        })
+    }
+}
+
+/// Busy-wait until the clock reaches `deadline_ns`, for when nothing else can run meanwhile.
+///
+/// The wait itself is not journaled: a replay spins for however long its own counter takes, and
+/// the caller's next [`ktime_ns`] reading decides what it observes.
+pub fn kspin_until(deadline_ns: u64) {
+    while counter_ns() < deadline_ns {
+        core::hint::spin_loop();
+    }
+}
+
+/// Monotonic time since boot as a [`core::time::Duration`].
+#[inline]
+pub fn ktime() -> core::time::Duration {
+    core::time::Duration::from_nanos(ktime_ns())
+}
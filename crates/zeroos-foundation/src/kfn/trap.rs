@@ -18,14 +18,23 @@ cfg_if! {
         }
 
         #[inline]
-        pub fn kexception(code: usize, pc: usize, trap_value: usize) -> Option<usize> {
-            unsafe { (crate::KERNEL.trap.exception)(code, pc, trap_value) }
+        pub fn kexception(regs: *mut u8, code: usize, pc: usize, trap_value: usize) -> Option<usize> {
+            unsafe { (crate::KERNEL.trap.exception)(regs, code, pc, trap_value) }
         }
 
         #[inline]
         pub fn kinterrupt(code: usize) {
             unsafe { (crate::KERNEL.trap.interrupt)(code) }
         }
+
+        /// Run the OS return-to-user hook on `regs`.
+        ///
+        /// # Safety
+        /// `regs` must point to the trap frame of the trap being returned from.
+        #[inline]
+        pub unsafe fn kreturn_to_user(regs: *mut u8) {
+            (crate::KERNEL.trap.return_to_user)(regs)
+        }
     } else {
         #[inline]
         #[allow(dead_code)]
@@ -43,12 +52,25 @@ cfg_if! {
 
         #[inline]
         #[allow(dead_code)]
-        pub fn kexception(_code: usize, _pc: usize, _trap_value: usize) -> Option<usize> {
+        pub fn kexception(
+            _regs: *mut u8,
+            _code: usize,
+            _pc: usize,
+            _trap_value: usize,
+        ) -> Option<usize> {
             None
         }
 
         #[inline]
         #[allow(dead_code)]
         pub fn kinterrupt(_code: usize) {}
+
+        /// Stub implementation of `kreturn_to_user`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn kreturn_to_user(_regs: *mut u8) {}
     }
 }
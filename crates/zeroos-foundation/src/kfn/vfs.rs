@@ -2,6 +2,9 @@ use cfg_if::cfg_if;
 
 cfg_if! {
     if #[cfg(feature = "vfs")] {
+        /// `AT_FDCWD`: resolve relative paths against the working directory.
+        pub const AT_FDCWD: i32 = -100;
+
         #[inline]
         pub fn kinit() {
             unsafe { (crate::KERNEL.vfs.init)() }
@@ -21,7 +24,14 @@ cfg_if! {
         /// # Safety
         /// `path` must be a valid NUL-terminated string.
         pub unsafe fn kopen(path: *const u8, flags: i32, mode: u32) -> isize {
-            (crate::KERNEL.vfs.open)(path, flags, mode)
+            kopenat(AT_FDCWD, path, flags, mode)
+        }
+
+        #[inline]
+        /// # Safety
+        /// `path` must be a valid NUL-terminated string.
+        pub unsafe fn kopenat(dirfd: i32, path: *const u8, flags: i32, mode: u32) -> isize {
+            (crate::KERNEL.vfs.openat)(dirfd, path, flags, mode)
         }
 
         #[inline]
@@ -43,6 +53,289 @@ cfg_if! {
         pub fn kfstat(fd: i32, statbuf: *mut u8) -> isize {
             unsafe { (crate::KERNEL.vfs.fstat)(fd, statbuf) }
         }
+
+        #[inline]
+        /// # Safety
+        /// `path` must be a valid NUL-terminated string.
+        pub unsafe fn kfstatat(dirfd: i32, path: *const u8, statbuf: *mut u8, flags: i32) -> isize {
+            (crate::KERNEL.vfs.fstatat)(dirfd, path, statbuf, flags)
+        }
+
+        #[inline]
+        pub fn kgetdents64(fd: i32, buf: *mut u8, count: usize) -> isize {
+            unsafe { (crate::KERNEL.vfs.getdents64)(fd, buf, count) }
+        }
+
+        #[inline]
+        /// # Safety
+        /// `path` must be a valid NUL-terminated string.
+        pub unsafe fn kmkdirat(dirfd: i32, path: *const u8, mode: u32) -> isize {
+            (crate::KERNEL.vfs.mkdirat)(dirfd, path, mode)
+        }
+
+        #[inline]
+        /// # Safety
+        /// `path` must be a valid NUL-terminated string.
+        pub unsafe fn kunlinkat(dirfd: i32, path: *const u8, flags: i32) -> isize {
+            (crate::KERNEL.vfs.unlinkat)(dirfd, path, flags)
+        }
+
+        #[inline]
+        /// # Safety
+        /// `oldpath` and `newpath` must be valid NUL-terminated strings.
+        pub unsafe fn krenameat(
+            olddirfd: i32,
+            oldpath: *const u8,
+            newdirfd: i32,
+            newpath: *const u8,
+            flags: u32,
+        ) -> isize {
+            (crate::KERNEL.vfs.renameat)(olddirfd, oldpath, newdirfd, newpath, flags)
+        }
+
+        #[inline]
+        pub fn kgetcwd(buf: *mut u8, size: usize) -> isize {
+            unsafe { (crate::KERNEL.vfs.getcwd)(buf, size) }
+        }
+
+        #[inline]
+        /// # Safety
+        /// `path` must be a valid NUL-terminated string.
+        pub unsafe fn kchdir(path: *const u8) -> isize {
+            (crate::KERNEL.vfs.chdir)(path)
+        }
+
+        #[inline]
+        pub fn kfchdir(fd: i32) -> isize {
+            unsafe { (crate::KERNEL.vfs.fchdir)(fd) }
+        }
+
+        #[inline]
+        pub fn kpread(fd: i32, buf: *mut u8, count: usize, offset: isize) -> isize {
+            unsafe { (crate::KERNEL.vfs.pread)(fd, buf, count, offset) }
+        }
+
+        #[inline]
+        pub fn kpwrite(fd: i32, buf: *const u8, count: usize, offset: isize) -> isize {
+            unsafe { (crate::KERNEL.vfs.pwrite)(fd, buf, count, offset) }
+        }
+
+        #[inline]
+        /// # Safety
+        /// `offset` must be null or valid for reads and writes.
+        pub unsafe fn ksendfile(out_fd: i32, in_fd: i32, offset: *mut isize, count: usize) -> isize {
+            (crate::KERNEL.vfs.sendfile)(out_fd, in_fd, offset, count)
+        }
+
+        #[inline]
+        /// # Safety
+        /// `off_in` and `off_out` must each be null or valid for reads and writes.
+        pub unsafe fn kcopy_file_range(
+            fd_in: i32,
+            off_in: *mut isize,
+            fd_out: i32,
+            off_out: *mut isize,
+            len: usize,
+            flags: u32,
+        ) -> isize {
+            (crate::KERNEL.vfs.copy_file_range)(fd_in, off_in, fd_out, off_out, len, flags)
+        }
+
+        #[inline]
+        pub fn kpoll(fd: i32) -> i16 {
+            unsafe { (crate::KERNEL.vfs.poll)(fd) }
+        }
+
+        #[inline]
+        pub fn kpipe2(flags: i32, fds: &mut [i32; 2]) -> isize {
+            unsafe { (crate::KERNEL.vfs.pipe2)(flags, fds) }
+        }
+
+        #[inline]
+        pub fn keventfd2(initval: u32, flags: i32) -> isize {
+            unsafe { (crate::KERNEL.vfs.eventfd2)(initval, flags) }
+        }
+
+        #[inline]
+        pub fn kepoll_create1(flags: i32) -> isize {
+            unsafe { (crate::KERNEL.vfs.epoll_create1)(flags) }
+        }
+
+        /// # Safety
+        /// `event` must be null or point to a readable `struct epoll_event`.
+        #[inline]
+        pub unsafe fn kepoll_ctl(epfd: i32, op: i32, fd: i32, event: *const u8) -> isize {
+            (crate::KERNEL.vfs.epoll_ctl)(epfd, op, fd, event)
+        }
+
+        /// # Safety
+        /// `events` must be valid for writes of `maxevents` `struct epoll_event`s.
+        #[inline]
+        pub unsafe fn kepoll_scan(epfd: i32, events: *mut u8, maxevents: usize) -> isize {
+            (crate::KERNEL.vfs.epoll_scan)(epfd, events, maxevents)
+        }
+
+        #[inline]
+        pub fn ksocket(domain: i32, ty: i32, protocol: i32) -> isize {
+            unsafe { (crate::KERNEL.vfs.socket)(domain, ty, protocol) }
+        }
+
+        #[inline]
+        pub fn ksocketpair(domain: i32, ty: i32, protocol: i32, fds: &mut [i32; 2]) -> isize {
+            unsafe { (crate::KERNEL.vfs.socketpair)(domain, ty, protocol, fds) }
+        }
+
+        /// # Safety
+        /// `addr` must be valid for reads of `addrlen` bytes.
+        #[inline]
+        pub unsafe fn kbind(fd: i32, addr: *const u8, addrlen: usize) -> isize {
+            (crate::KERNEL.vfs.bind)(fd, addr, addrlen)
+        }
+
+        #[inline]
+        pub fn klisten(fd: i32, backlog: i32) -> isize {
+            unsafe { (crate::KERNEL.vfs.listen)(fd, backlog) }
+        }
+
+        /// # Safety
+        /// `addr`/`addrlen` must be null or valid as for [`ksockname`].
+        #[inline]
+        pub unsafe fn kaccept4(fd: i32, addr: *mut u8, addrlen: *mut u32, flags: i32) -> isize {
+            (crate::KERNEL.vfs.accept4)(fd, addr, addrlen, flags)
+        }
+
+        /// # Safety
+        /// `addr` must be valid for reads of `addrlen` bytes.
+        #[inline]
+        pub unsafe fn kconnect(fd: i32, addr: *const u8, addrlen: usize) -> isize {
+            (crate::KERNEL.vfs.connect)(fd, addr, addrlen)
+        }
+
+        #[inline]
+        pub fn ksendto(fd: i32, buf: *const u8, len: usize, flags: i32) -> isize {
+            unsafe { (crate::KERNEL.vfs.sendto)(fd, buf, len, flags) }
+        }
+
+        #[inline]
+        pub fn krecvfrom(fd: i32, buf: *mut u8, len: usize, flags: i32) -> isize {
+            unsafe { (crate::KERNEL.vfs.recvfrom)(fd, buf, len, flags) }
+        }
+
+        #[inline]
+        pub fn ksock_shutdown(fd: i32, how: i32) -> isize {
+            unsafe { (crate::KERNEL.vfs.sock_shutdown)(fd, how) }
+        }
+
+        /// # Safety
+        /// `addrlen` must be valid for reads and writes, and `addr` for writes of `*addrlen` bytes.
+        #[inline]
+        pub unsafe fn ksockname(fd: i32, addr: *mut u8, addrlen: *mut u32, peer: bool) -> isize {
+            (crate::KERNEL.vfs.sockname)(fd, addr, addrlen, peer)
+        }
+
+        /// # Safety
+        /// `optlen` must be valid for reads and writes, and `optval` for writes of `*optlen` bytes.
+        #[inline]
+        pub unsafe fn kgetsockopt(
+            fd: i32,
+            level: i32,
+            name: i32,
+            optval: *mut u8,
+            optlen: *mut u32,
+        ) -> isize {
+            (crate::KERNEL.vfs.getsockopt)(fd, level, name, optval, optlen)
+        }
+
+        #[inline]
+        pub fn ksetsockopt(fd: i32, level: i32, name: i32, optval: *const u8, optlen: usize) -> isize {
+            unsafe { (crate::KERNEL.vfs.setsockopt)(fd, level, name, optval, optlen) }
+        }
+
+        /// # Safety
+        /// `name` must be a valid NUL-terminated string.
+        #[inline]
+        pub unsafe fn kmemfd_create(name: *const u8, flags: u32) -> isize {
+            (crate::KERNEL.vfs.memfd_create)(name, flags)
+        }
+
+        #[inline]
+        pub fn kftruncate(fd: i32, len: isize) -> isize {
+            unsafe { (crate::KERNEL.vfs.ftruncate)(fd, len) }
+        }
+
+        #[inline]
+        pub fn kmmap_shared(fd: i32, offset: usize, len: usize, write: bool) -> isize {
+            unsafe { (crate::KERNEL.vfs.mmap_shared)(fd, offset, len, write) }
+        }
+
+        #[inline]
+        pub fn kmunmap_shared(addr: usize, len: usize) -> bool {
+            unsafe { (crate::KERNEL.vfs.munmap_shared)(addr, len) }
+        }
+
+        /// # Safety
+        /// `path` must be a valid NUL-terminated string and `buf` valid for `size` bytes.
+        #[inline]
+        pub unsafe fn kreadlinkat(dirfd: i32, path: *const u8, buf: *mut u8, size: usize) -> isize {
+            (crate::KERNEL.vfs.readlinkat)(dirfd, path, buf, size)
+        }
+
+        /// Readiness generation, bumped whenever some file may have become ready.
+        ///
+        /// Waiters sample it with [`kpoll_seq`], check their files, then [`kpoll_wait`] for a
+        /// change, so a notification between the check and the sleep is never lost.
+        static POLL_SEQ: core::sync::atomic::AtomicI32 = core::sync::atomic::AtomicI32::new(0);
+
+        #[inline]
+        pub fn kpoll_seq() -> i32 {
+            POLL_SEQ.load(core::sync::atomic::Ordering::Acquire)
+        }
+
+        /// Signal that a file's readiness changed and wake every [`kpoll_wait`]er.
+        pub fn kpoll_notify() {
+            POLL_SEQ.fetch_add(1, core::sync::atomic::Ordering::Release);
+            if cfg!(feature = "scheduler") {
+                crate::kfn::scheduler::kwake_on_addr(POLL_SEQ.as_ptr() as usize, usize::MAX);
+            }
+        }
+
+        /// Block the current thread until the readiness generation moves past `seq`.
+        ///
+        /// Returns `false` without sleeping when no other thread could ever notify: without a
+        /// scheduler, or when every other thread is blocked or gone. Callers must re-check
+        /// their condition either way.
+        pub fn kpoll_wait(seq: i32) -> bool {
+            if !cfg!(feature = "scheduler") {
+                return false;
+            }
+            let addr = POLL_SEQ.as_ptr() as usize;
+            let ret = crate::kfn::scheduler::kwait_on_addr(addr, seq);
+            if ret == 0 {
+                // Woken by a notification, or resumed with nobody else left to run.
+                kpoll_seq() != seq
+            } else if ret == -(crate::errno::EAGAIN as isize) {
+                // The generation already moved on.
+                true
+            } else {
+                // EDEADLK: this is the only thread. Any other failure says nothing about who
+                // could wake us, so let the caller re-check.
+                ret != -(crate::errno::EDEADLK as isize)
+            }
+        }
+
+        /// [`kpoll_wait`] that also gives up once the clock reaches `deadline_ns`.
+        ///
+        /// When nothing else could notify, this just waits out the deadline. Callers re-check
+        /// their condition and the deadline either way.
+        pub fn kpoll_wait_until(seq: i32, deadline_ns: u64) {
+            let addr = POLL_SEQ.as_ptr() as usize;
+            crate::kfn::scheduler::kwait_on_addr_until(addr, seq, deadline_ns);
+        }
+
+        #[inline]
+        pub fn kshutdown() {
+            unsafe { (crate::KERNEL.vfs.shutdown)() }
+        }
     } else {
         #[inline]
         #[allow(dead_code)]
@@ -68,6 +361,14 @@ cfg_if! {
             -1
         }
 
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `path` is not used in the stub implementation.
+        pub unsafe fn kopenat(_dirfd: i32, _path: *const u8, _flags: i32, _mode: u32) -> isize {
+            -1
+        }
+
         #[inline]
         #[allow(dead_code)]
         pub fn kclose(_fd: i32) -> isize {
@@ -91,5 +392,297 @@ cfg_if! {
         pub fn kfstat(_fd: i32, _statbuf: *mut u8) -> isize {
             -1
         }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `path` is not used in the stub implementation.
+        pub unsafe fn kfstatat(_dirfd: i32, _path: *const u8, _statbuf: *mut u8, _flags: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kgetdents64(_fd: i32, _buf: *mut u8, _count: usize) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `path` is not used in the stub implementation.
+        pub unsafe fn kmkdirat(_dirfd: i32, _path: *const u8, _mode: u32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `path` is not used in the stub implementation.
+        pub unsafe fn kunlinkat(_dirfd: i32, _path: *const u8, _flags: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// The paths are not used in the stub implementation.
+        pub unsafe fn krenameat(
+            _olddirfd: i32,
+            _oldpath: *const u8,
+            _newdirfd: i32,
+            _newpath: *const u8,
+            _flags: u32,
+        ) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kgetcwd(_buf: *mut u8, _size: usize) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `path` is not used in the stub implementation.
+        pub unsafe fn kchdir(_path: *const u8) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kfchdir(_fd: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kpread(_fd: i32, _buf: *mut u8, _count: usize, _offset: isize) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kpwrite(_fd: i32, _buf: *const u8, _count: usize, _offset: isize) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `offset` is not used in the stub implementation.
+        pub unsafe fn ksendfile(_out_fd: i32, _in_fd: i32, _offset: *mut isize, _count: usize) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// The offsets are not used in the stub implementation.
+        pub unsafe fn kcopy_file_range(
+            _fd_in: i32,
+            _off_in: *mut isize,
+            _fd_out: i32,
+            _off_out: *mut isize,
+            _len: usize,
+            _flags: u32,
+        ) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kpoll(_fd: i32) -> i16 {
+            0
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kpipe2(_flags: i32, _fds: &mut [i32; 2]) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn keventfd2(_initval: u32, _flags: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kepoll_create1(_flags: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `event` is not used in the stub implementation.
+        pub unsafe fn kepoll_ctl(_epfd: i32, _op: i32, _fd: i32, _event: *const u8) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `events` is not used in the stub implementation.
+        pub unsafe fn kepoll_scan(_epfd: i32, _events: *mut u8, _maxevents: usize) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn ksocket(_domain: i32, _ty: i32, _protocol: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn ksocketpair(_domain: i32, _ty: i32, _protocol: i32, _fds: &mut [i32; 2]) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `addr` is not used in the stub implementation.
+        pub unsafe fn kbind(_fd: i32, _addr: *const u8, _addrlen: usize) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn klisten(_fd: i32, _backlog: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `addr` and `addrlen` are not used in the stub implementation.
+        pub unsafe fn kaccept4(_fd: i32, _addr: *mut u8, _addrlen: *mut u32, _flags: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `addr` is not used in the stub implementation.
+        pub unsafe fn kconnect(_fd: i32, _addr: *const u8, _addrlen: usize) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn ksendto(_fd: i32, _buf: *const u8, _len: usize, _flags: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn krecvfrom(_fd: i32, _buf: *mut u8, _len: usize, _flags: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn ksock_shutdown(_fd: i32, _how: i32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `addr` and `addrlen` are not used in the stub implementation.
+        pub unsafe fn ksockname(_fd: i32, _addr: *mut u8, _addrlen: *mut u32, _peer: bool) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `optval` and `optlen` are not used in the stub implementation.
+        pub unsafe fn kgetsockopt(
+            _fd: i32,
+            _level: i32,
+            _name: i32,
+            _optval: *mut u8,
+            _optlen: *mut u32,
+        ) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn ksetsockopt(
+            _fd: i32,
+            _level: i32,
+            _name: i32,
+            _optval: *const u8,
+            _optlen: usize,
+        ) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `name` is not used in the stub implementation.
+        pub unsafe fn kmemfd_create(_name: *const u8, _flags: u32) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kftruncate(_fd: i32, _len: isize) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kmmap_shared(_fd: i32, _offset: usize, _len: usize, _write: bool) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kmunmap_shared(_addr: usize, _len: usize) -> bool {
+            false
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `path` and `buf` are not used in the stub implementation.
+        pub unsafe fn kreadlinkat(_dirfd: i32, _path: *const u8, _buf: *mut u8, _size: usize) -> isize {
+            -1
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kpoll_seq() -> i32 {
+            0
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kpoll_notify() {}
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kpoll_wait(_seq: i32) -> bool {
+            false
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kpoll_wait_until(_seq: i32, deadline_ns: u64) {
+            crate::kfn::time::kspin_until(deadline_ns);
+        }
+
+        #[inline]
+        pub fn kshutdown() {}
     }
 }
@@ -4,6 +4,7 @@ extern crate alloc;
 
 pub mod arch;
 pub mod entry;
+pub mod errno;
 pub mod kernel;
 pub mod kfn;
 pub mod ops;
@@ -95,4 +95,55 @@ pub struct ArchOps {
     /// # Safety
     /// `regs` must be a valid, aligned pointer.
     pub trap_frame_get_fault_addr: unsafe fn(regs: *const u8) -> usize,
+    /// Return the saved stack pointer from a trap frame.
+    /// # Safety
+    /// `regs` must be a valid, aligned pointer.
+    pub trap_frame_get_sp: unsafe fn(regs: *const u8) -> usize,
+    /// Return the saved frame pointer from a trap frame (where a backtrace of the trapped code
+    /// starts).
+    /// # Safety
+    /// `regs` must be a valid, aligned pointer.
+    pub trap_frame_get_fp: unsafe fn(regs: *const u8) -> usize,
+    /// Call `f` with the name and value of every register saved in a trap frame, in the
+    /// architecture's order (for crash reports).
+    /// # Safety
+    /// `regs` must be a valid, aligned pointer.
+    pub trap_frame_regs: unsafe fn(regs: *const u8, f: &mut dyn FnMut(&'static str, usize)),
+    /// Set the argument register at the given index (0-5) in a trap frame.
+    /// # Safety
+    /// `regs` must be a valid, aligned, and mutable pointer.
+    pub trap_frame_set_arg: unsafe fn(regs: *mut u8, idx: usize, val: usize),
+    /// Set the return-address register in a trap frame.
+    /// # Safety
+    /// `regs` must be a valid, aligned, and mutable pointer.
+    pub trap_frame_set_ra: unsafe fn(regs: *mut u8, ra: usize),
+
+    /// Return the address of the arch-specific signal return trampoline (issues `rt_sigreturn`).
+    ///
+    /// Signal handlers return into it when the guest did not supply its own restorer.
+    pub sigreturn_trampoline: fn() -> usize,
+    /// Return the size (bytes) of the user-visible machine context (`mcontext_t`).
+    pub mcontext_size: fn() -> usize,
+    /// Save the user registers of a trap frame into a `mcontext_t`.
+    /// # Safety
+    /// `regs` must be a valid trap frame; `mctx` must be writable for `mcontext_size()` bytes.
+    pub mcontext_save: unsafe fn(regs: *const u8, mctx: *mut u8),
+    /// Restore the user registers of a trap frame from a `mcontext_t`.
+    ///
+    /// Privileged state (status register etc.) is left untouched.
+    /// # Safety
+    /// `regs` must be a valid trap frame; `mctx` must be readable for `mcontext_size()` bytes.
+    pub mcontext_restore: unsafe fn(regs: *mut u8, mctx: *const u8),
+
+    /// Return the number of instructions retired by this hart (monotonic).
+    pub instret: fn() -> u64,
+
+    /// Make `[base, base + size)` inaccessible, replacing the region previously protected in
+    /// `slot` (see [`PROTECT_SLOT_KSTACK`], [`PROTECT_SLOT_USTACK`]). `size == 0` removes it.
+    pub protect_region: fn(slot: usize, base: usize, size: usize),
 }
+
+/// `protect_region` slot for the running thread's kernel stack guard.
+pub const PROTECT_SLOT_KSTACK: usize = 0;
+/// `protect_region` slot for the running thread's user stack guard.
+pub const PROTECT_SLOT_USTACK: usize = 1;
@@ -6,4 +6,26 @@ pub struct MemoryOps {
     pub alloc: fn(layout: Layout) -> *mut u8,
     pub dealloc: fn(ptr: *mut u8, layout: Layout),
     pub realloc: fn(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8,
+    pub stats: fn() -> MemoryStats,
+}
+
+/// Heap usage in bytes, as reported by the allocator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub total: usize,
+    pub free: usize,
+}
+
+/// Path the kernel image is reported under (`/proc/self/exe`, `/proc/self/maps`).
+pub const IMAGE_PATH: &str = "/zerokernel";
+
+/// A mapped address range, as listed in `/proc/self/maps`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub start: usize,
+    pub end: usize,
+    /// `rwxp`-style permission string, e.g. `*b"r-xp"`.
+    pub perms: [u8; 4],
+    /// Backing path or pseudo-name (`[stack]`); empty for anonymous memory.
+    pub name: &'static str,
 }
@@ -7,7 +7,7 @@ cfg_if! {
         pub(crate) mod memory;
     }
 }
-pub use memory::MemoryOps;
+pub use memory::{MemoryOps, MemoryRegion, MemoryStats, IMAGE_PATH};
 
 cfg_if! {
     if #[cfg(feature = "scheduler")] {
@@ -16,7 +16,7 @@ cfg_if! {
         pub(crate) mod scheduler;
     }
 }
-pub use scheduler::SchedulerOps;
+pub use scheduler::{SchedulerOps, ThreadInfo, ThreadStatus, THREAD_NAME_LEN};
 
 cfg_if! {
     if #[cfg(feature = "vfs")] {
@@ -25,7 +25,7 @@ cfg_if! {
         pub(crate) mod vfs;
     }
 }
-pub use vfs::VfsOps;
+pub use vfs::{VfsOps, MAX_FDS};
 
 cfg_if! {
     if #[cfg(feature = "random")] {
@@ -43,7 +43,7 @@ cfg_if! {
         pub(crate) mod arch;
     }
 }
-pub use arch::ArchOps;
+pub use arch::{ArchOps, PROTECT_SLOT_KSTACK, PROTECT_SLOT_USTACK};
 
 cfg_if! {
     if #[cfg(feature = "trap")] {
@@ -2,6 +2,47 @@
 //!
 //! Defines the interface for a thread scheduler.
 
+/// Maximum thread name length in bytes, including the trailing NUL (Linux `TASK_COMM_LEN`).
+pub const THREAD_NAME_LEN: usize = 16;
+
+/// Coarse thread state, as reported to kernel code outside the scheduler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadStatus {
+    Ready,
+    Running,
+    Blocked,
+    Exited,
+}
+
+/// Snapshot of one thread, returned by [`SchedulerOps::thread_info`].
+#[derive(Debug, Clone, Copy)]
+pub struct ThreadInfo {
+    pub tid: usize,
+    pub status: ThreadStatus,
+    /// NUL-padded thread name (see `prctl(PR_SET_NAME)`).
+    pub name: [u8; THREAD_NAME_LEN],
+    /// Exit code, meaningful once `status == Exited`.
+    pub exit_code: i32,
+    /// Number of syscalls issued by this thread.
+    pub syscalls: u64,
+    /// Number of times this thread gave up the CPU.
+    pub yields: u64,
+    /// Instructions retired while this thread was running.
+    pub instret: u64,
+}
+
+impl ThreadInfo {
+    /// Return the name up to (not including) the first NUL.
+    pub fn name(&self) -> &[u8] {
+        let len = self
+            .name
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(THREAD_NAME_LEN);
+        &self.name[..len]
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct SchedulerOps {
     /// Initialize the scheduler and create the boot thread.
@@ -17,6 +58,12 @@ pub struct SchedulerOps {
         clear_child_tid_ptr: usize,
     ) -> isize,
 
+    /// Spawn a kernel thread that starts at `entry(arg)` on its own kernel stack.
+    ///
+    /// Unlike `spawn_thread`, no trap frame is involved: the thread is entered directly by the
+    /// context switch and must end via `exit_current`. Returns the new TID or a negative errno.
+    pub spawn_kernel_thread: fn(entry: extern "C" fn(arg: usize) -> !, arg: usize) -> isize,
+
     /// Voluntarily yield the CPU to another thread.
     pub yield_now: fn() -> isize,
 
@@ -32,9 +79,25 @@ pub struct SchedulerOps {
     /// Put the current thread to sleep until the value at `addr` changes.
     pub wait_on_addr: fn(addr: usize, expected: i32) -> isize,
 
+    /// Like `wait_on_addr`, but give up with `-ETIMEDOUT` once the kernel clock reaches
+    /// `deadline_ns`.
+    pub wait_on_addr_until: fn(addr: usize, expected: i32, deadline_ns: u64) -> isize,
+
     /// Wake up to `count` threads waiting on `addr`.
     pub wake_on_addr: fn(addr: usize, count: usize) -> usize,
 
     /// Set a memory address to be cleared when the current thread exits.
     pub set_clear_on_exit_addr: fn(addr: usize) -> isize,
+
+    /// Block until thread `tid` exits and return its exit code (negative errno on failure).
+    pub join_thread: fn(tid: usize) -> isize,
+
+    /// Return a snapshot of the thread at `index` (`0..thread_count()`), for iteration.
+    pub thread_info: fn(index: usize) -> Option<ThreadInfo>,
+
+    /// Set the current thread's name (truncated to `THREAD_NAME_LEN - 1` bytes).
+    pub set_thread_name: fn(name: &[u8]),
+
+    /// Account one syscall to the current thread.
+    pub account_syscall: fn(),
 }
@@ -8,20 +8,29 @@ pub struct TrapOps {
     pub syscall:
         fn(a0: usize, a1: usize, a2: usize, a3: usize, a4: usize, a5: usize, nr: usize) -> isize,
 
-    /// Handle a CPU exception (fault).
+    /// Handle a CPU exception (fault) taken with trap frame `regs`.
     /// Returns `Some(new_pc)` to resume, or `None` to terminate.
-    pub exception: fn(code: usize, pc: usize, trap_value: usize) -> Option<usize>,
+    pub exception: fn(regs: *mut u8, code: usize, pc: usize, trap_value: usize) -> Option<usize>,
 
     /// Handle a hardware interrupt.
     pub interrupt: fn(code: usize),
+
+    /// Called with the trap frame right before returning to the interrupted guest code.
+    ///
+    /// OS personalities use it for work that must rewrite the resumed context (e.g. signal
+    /// delivery and `rt_sigreturn`).
+    /// # Safety
+    /// `regs` must point to the trap frame of the trap being returned from.
+    pub return_to_user: unsafe fn(regs: *mut u8),
 }
 
 impl Default for TrapOps {
     fn default() -> Self {
         Self {
             syscall: |_, _, _, _, _, _, _| -38, // ENOSYS
-            exception: |_, _, _| None,
+            exception: |_, _, _, _| None,
             interrupt: |_| {},
+            return_to_user: |_| {},
         }
     }
 }
@@ -1,11 +1,81 @@
+/// Size of the descriptor table (descriptors are `0..MAX_FDS`), reported as `RLIMIT_NOFILE`.
+pub const MAX_FDS: usize = 256;
+
 #[derive(Clone, Copy)]
 pub struct VfsOps {
     pub init: fn(),
     pub read: fn(fd: i32, buf: *mut u8, count: usize) -> isize,
     pub write: fn(fd: i32, buf: *const u8, count: usize) -> isize,
-    pub open: unsafe fn(path: *const u8, flags: i32, mode: u32) -> isize,
+    /// Open `path` relative to `dirfd` (`AT_FDCWD` for the working directory).
+    pub openat: unsafe fn(dirfd: i32, path: *const u8, flags: i32, mode: u32) -> isize,
     pub close: fn(fd: i32) -> isize,
     pub lseek: fn(fd: i32, offset: isize, whence: i32) -> isize,
     pub ioctl: fn(fd: i32, request: usize, arg: usize) -> isize,
     pub fstat: fn(fd: i32, statbuf: *mut u8) -> isize,
+    pub fstatat: unsafe fn(dirfd: i32, path: *const u8, statbuf: *mut u8, flags: i32) -> isize,
+    pub getdents64: fn(fd: i32, buf: *mut u8, count: usize) -> isize,
+    pub mkdirat: unsafe fn(dirfd: i32, path: *const u8, mode: u32) -> isize,
+    pub unlinkat: unsafe fn(dirfd: i32, path: *const u8, flags: i32) -> isize,
+    pub renameat: unsafe fn(
+        olddirfd: i32,
+        oldpath: *const u8,
+        newdirfd: i32,
+        newpath: *const u8,
+        flags: u32,
+    ) -> isize,
+    /// Copy the NUL-terminated working directory into `buf`; returns its length including the NUL.
+    pub getcwd: fn(buf: *mut u8, size: usize) -> isize,
+    pub chdir: unsafe fn(path: *const u8) -> isize,
+    pub fchdir: fn(fd: i32) -> isize,
+    /// Read at `offset` without moving the file position.
+    pub pread: fn(fd: i32, buf: *mut u8, count: usize, offset: isize) -> isize,
+    pub pwrite: fn(fd: i32, buf: *const u8, count: usize, offset: isize) -> isize,
+    /// Copy between descriptors in the kernel; a non-null `offset` replaces `in_fd`'s position.
+    pub sendfile: unsafe fn(out_fd: i32, in_fd: i32, offset: *mut isize, count: usize) -> isize,
+    pub copy_file_range: unsafe fn(
+        fd_in: i32,
+        off_in: *mut isize,
+        fd_out: i32,
+        off_out: *mut isize,
+        len: usize,
+        flags: u32,
+    ) -> isize,
+    /// Current readiness of `fd` as a `POLL*` mask (`POLLNVAL` if `fd` is not open).
+    pub poll: fn(fd: i32) -> i16,
+    /// Create a pipe; stores the read and write ends in `fds`.
+    pub pipe2: fn(flags: i32, fds: &mut [i32; 2]) -> isize,
+    pub eventfd2: fn(initval: u32, flags: i32) -> isize,
+    pub epoll_create1: fn(flags: i32) -> isize,
+    /// `event` points to a `struct epoll_event` (may be null for `EPOLL_CTL_DEL`).
+    pub epoll_ctl: unsafe fn(epfd: i32, op: i32, fd: i32, event: *const u8) -> isize,
+    /// Store up to `maxevents` ready `struct epoll_event`s without blocking.
+    pub epoll_scan: unsafe fn(epfd: i32, events: *mut u8, maxevents: usize) -> isize,
+    pub socket: fn(domain: i32, ty: i32, protocol: i32) -> isize,
+    pub socketpair: fn(domain: i32, ty: i32, protocol: i32, fds: &mut [i32; 2]) -> isize,
+    /// `addr` points to a `struct sockaddr` of `addrlen` bytes.
+    pub bind: unsafe fn(fd: i32, addr: *const u8, addrlen: usize) -> isize,
+    pub listen: fn(fd: i32, backlog: i32) -> isize,
+    /// Accept a connection; a non-null `addr`/`addrlen` receives the peer address.
+    pub accept4: unsafe fn(fd: i32, addr: *mut u8, addrlen: *mut u32, flags: i32) -> isize,
+    pub connect: unsafe fn(fd: i32, addr: *const u8, addrlen: usize) -> isize,
+    pub sendto: fn(fd: i32, buf: *const u8, len: usize, flags: i32) -> isize,
+    pub recvfrom: fn(fd: i32, buf: *mut u8, len: usize, flags: i32) -> isize,
+    /// `shutdown(2)` on a socket.
+    pub sock_shutdown: fn(fd: i32, how: i32) -> isize,
+    /// Store the local (or, with `peer`, remote) socket address like `getsockname(2)`.
+    pub sockname: unsafe fn(fd: i32, addr: *mut u8, addrlen: *mut u32, peer: bool) -> isize,
+    pub getsockopt:
+        unsafe fn(fd: i32, level: i32, name: i32, optval: *mut u8, optlen: *mut u32) -> isize,
+    pub setsockopt: fn(fd: i32, level: i32, name: i32, optval: *const u8, optlen: usize) -> isize,
+    /// Create an anonymous file; `name` is a NUL-terminated string.
+    pub memfd_create: unsafe fn(name: *const u8, flags: u32) -> isize,
+    pub ftruncate: fn(fd: i32, len: isize) -> isize,
+    /// Map `len` bytes of `fd` at `offset` shared with the file; returns the address.
+    pub mmap_shared: fn(fd: i32, offset: usize, len: usize, write: bool) -> isize,
+    /// Release shared file mappings in a range; `false` if it holds none (anonymous memory).
+    pub munmap_shared: fn(addr: usize, len: usize) -> bool,
+    /// Copy a symlink's target (not NUL-terminated) into `buf`; returns its length.
+    pub readlinkat: unsafe fn(dirfd: i32, path: *const u8, buf: *mut u8, size: usize) -> isize,
+    /// Flush and close every open descriptor (kernel shutdown).
+    pub shutdown: fn(),
 }
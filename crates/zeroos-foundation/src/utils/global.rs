@@ -1,30 +1,11 @@
-use core::cell::UnsafeCell;
+use super::KernelLock;
 
-pub struct GlobalCell<T>(UnsafeCell<T>);
-
-unsafe impl<T> Sync for GlobalCell<T> {}
-
-impl<T> GlobalCell<T> {
-    pub const fn new(value: T) -> Self {
-        Self(UnsafeCell::new(value))
-    }
-
-    #[inline(always)]
-    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
-        f(unsafe { &*self.0.get() })
-    }
-
-    #[inline(always)]
-    pub fn with_mut<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
-        f(unsafe { &mut *self.0.get() })
-    }
-}
-
-pub struct GlobalOption<T>(GlobalCell<Option<T>>);
+/// A [`KernelLock`]ed singleton that is installed at init time.
+pub struct GlobalOption<T>(KernelLock<Option<T>>);
 
 impl<T> GlobalOption<T> {
     pub const fn none() -> Self {
-        Self(GlobalCell::new(None))
+        Self(KernelLock::new(None))
     }
 
     #[inline(always)]
@@ -46,4 +27,13 @@ impl<T> GlobalOption<T> {
     pub fn with_some_mut<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
         self.0.with_mut(|slot| slot.as_mut().map(f))
     }
+
+    /// See [`KernelLock::force_unlock`].
+    ///
+    /// # Safety
+    /// As for [`KernelLock::force_unlock`].
+    #[inline(always)]
+    pub unsafe fn force_unlock(&self) {
+        self.0.force_unlock();
+    }
 }
@@ -0,0 +1,167 @@
+//! Lock for kernel singletons.
+//!
+//! What taking a [`KernelLock`] costs is chosen per build:
+//!
+//! - `lock-irq` masks machine interrupts for the critical section (preemption on one hart);
+//! - `lock-spin` spins on an atomic flag (several harts);
+//! - both together for preemptive multi-hart kernels;
+//! - neither (the default) for the cooperative single-hart kernel, where the lock compiles away.
+//!
+//! Locks are not re-entrant: a critical section must not reach code that takes the same lock.
+//! Once [`kshutdown`](crate::kfn::shutdown) or [`kcrash`](crate::kfn::crash) has started, waiters
+//! stop spinning and go ahead, so exiting from inside a critical section (a panic, or the last
+//! thread exiting in the scheduler) can still flush files and print reports.
+
+use core::cell::UnsafeCell;
+#[cfg(feature = "lock-spin")]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+pub struct KernelLock<T> {
+    #[cfg(feature = "lock-spin")]
+    locked: AtomicBool,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Sync for KernelLock<T> {}
+
+impl<T> KernelLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            #[cfg(feature = "lock-spin")]
+            locked: AtomicBool::new(false),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline(always)]
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let _guard = self.lock();
+        f(unsafe { &*self.data.get() })
+    }
+
+    #[inline(always)]
+    pub fn with_mut<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let _guard = self.lock();
+        f(unsafe { &mut *self.data.get() })
+    }
+
+    /// Release the lock from inside a critical section, leaving interrupts masked.
+    ///
+    /// A context switch happens with the lock held and the thread switched to releases it when
+    /// it leaves its own critical section; a thread that has never run is not in one, so the
+    /// switching thread releases the lock for it.
+    ///
+    /// # Safety
+    /// The caller must hold the lock and must not touch the data again until a thread holding
+    /// the lock switches back to it.
+    #[inline(always)]
+    pub unsafe fn force_unlock(&self) {
+        #[cfg(feature = "lock-spin")]
+        self.locked.store(false, Ordering::Release);
+    }
+
+    #[inline(always)]
+    fn lock(&self) -> Guard<'_, T> {
+        #[cfg(feature = "lock-irq")]
+        let irq = irq::save();
+        #[cfg(feature = "lock-spin")]
+        let owned = loop {
+            if self
+                .locked
+                .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                break true;
+            }
+            if crate::kfn::shutdown::kshutting_down() {
+                break false;
+            }
+            core::hint::spin_loop();
+        };
+        Guard {
+            lock: self,
+            #[cfg(feature = "lock-spin")]
+            owned,
+            #[cfg(feature = "lock-irq")]
+            irq,
+        }
+    }
+}
+
+struct Guard<'a, T> {
+    #[cfg_attr(not(feature = "lock-spin"), allow(dead_code))]
+    lock: &'a KernelLock<T>,
+    #[cfg(feature = "lock-spin")]
+    owned: bool,
+    #[cfg(feature = "lock-irq")]
+    irq: bool,
+}
+
+impl<T> Drop for Guard<'_, T> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        #[cfg(feature = "lock-spin")]
+        if self.owned {
+            self.lock.locked.store(false, Ordering::Release);
+        }
+        #[cfg(feature = "lock-irq")]
+        irq::restore(self.irq);
+    }
+}
+
+#[cfg(feature = "lock-irq")]
+mod irq {
+    use cfg_if::cfg_if;
+
+    cfg_if! {
+        if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
+            const MSTATUS_MIE: usize = 1 << 3;
+
+            /// Mask machine interrupts; returns whether they were enabled.
+            #[inline(always)]
+            pub(super) fn save() -> bool {
+                let mstatus: usize;
+                unsafe { core::arch::asm!("csrrci {}, mstatus, 8", out(reg) mstatus) };
+                mstatus & MSTATUS_MIE != 0
+            }
+
+            #[inline(always)]
+            pub(super) fn restore(enabled: bool) {
+                if enabled {
+                    unsafe { core::arch::asm!("csrsi mstatus, 8") };
+                }
+            }
+        } else {
+            // Host builds (unit tests) have no interrupts to mask.
+            #[inline(always)]
+            pub(super) fn save() -> bool {
+                false
+            }
+
+            #[inline(always)]
+            pub(super) fn restore(_enabled: bool) {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_mut_is_seen_by_with() {
+        static COUNTER: KernelLock<u32> = KernelLock::new(0);
+        COUNTER.with_mut(|c| *c += 2);
+        COUNTER.with_mut(|c| *c += 3);
+        assert_eq!(COUNTER.with(|c| *c), 5);
+    }
+
+    #[test]
+    fn test_lock_is_released_after_each_section() {
+        let lock = KernelLock::new(1);
+        for _ in 0..3 {
+            lock.with_mut(|v| *v *= 2);
+        }
+        assert_eq!(lock.with(|v| *v), 8);
+    }
+}
@@ -1,7 +1,9 @@
 pub mod global;
+pub mod lock;
 pub mod random;
 pub mod stack;
 
-pub use global::{GlobalCell, GlobalOption};
+pub use global::GlobalOption;
+pub use lock::KernelLock;
 pub use random::generate_random_bytes;
 pub use stack::DownwardStack;
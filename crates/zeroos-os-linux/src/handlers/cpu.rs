@@ -0,0 +1,336 @@
+//! CPU topology: affinity masks, `getcpu` and `riscv_hwprobe`.
+//!
+//! The guest sees a fixed number of virtual CPUs, set at build time with `ZEROOS_NCPUS`
+//! (decimal, default 1). All threads still run on the one hart; the count only drives what
+//! `available_parallelism`, `sysconf(_SC_NPROCESSORS_*)` and thread pools observe.
+//!
+//! `riscv_hwprobe` reports the extensions the kernel itself was compiled for, i.e. the target
+//! profile the guest was built with, so library dispatch is deterministic.
+
+use libc;
+
+/// Maximum virtual CPU count (one `unsigned long` of affinity mask).
+const MAX_CPUS: usize = usize::BITS as usize;
+
+const fn parse_ncpus(s: Option<&str>) -> usize {
+    let Some(s) = s else {
+        return 1;
+    };
+    let b = s.as_bytes();
+    let mut v = 0usize;
+    let mut i = 0;
+    while i < b.len() {
+        assert!(
+            b[i].is_ascii_digit(),
+            "ZEROOS_NCPUS must be a decimal integer"
+        );
+        v = v * 10 + (b[i] - b'0') as usize;
+        i += 1;
+    }
+    assert!(
+        v >= 1 && v <= MAX_CPUS,
+        "ZEROOS_NCPUS out of range (1..=usize::BITS)"
+    );
+    v
+}
+
+/// Number of virtual CPUs reported to the guest.
+pub const NCPUS: usize = parse_ncpus(option_env!("ZEROOS_NCPUS"));
+
+/// Size in bytes of the kernel's CPU mask (Linux rounds to `unsigned long`).
+const CPUMASK_SIZE: usize = core::mem::size_of::<usize>();
+
+const ALL_CPUS: usize = if NCPUS == MAX_CPUS {
+    usize::MAX
+} else {
+    (1 << NCPUS) - 1
+};
+
+fn task_exists(pid: usize) -> bool {
+    #[cfg(feature = "scheduler")]
+    {
+        pid == 0 || pid == 1 || foundation::kfn::scheduler::kthread_by_tid(pid).is_some()
+    }
+    #[cfg(not(feature = "scheduler"))]
+    {
+        pid == 0 || pid == 1
+    }
+}
+
+/// Handle sched_getaffinity syscall
+pub fn sys_sched_getaffinity(pid: usize, len: usize, mask: usize) -> isize {
+    if !task_exists(pid) {
+        return -(libc::ESRCH as isize);
+    }
+    if len < CPUMASK_SIZE || !len.is_multiple_of(core::mem::size_of::<usize>()) {
+        return -(libc::EINVAL as isize);
+    }
+    if mask == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    unsafe { (mask as *mut usize).write_unaligned(ALL_CPUS) };
+    CPUMASK_SIZE as isize
+}
+
+/// Handle sched_setaffinity syscall
+///
+/// Accepted as long as the mask names at least one virtual CPU; placement is not modelled.
+pub fn sys_sched_setaffinity(pid: usize, len: usize, mask: usize) -> isize {
+    if !task_exists(pid) {
+        return -(libc::ESRCH as isize);
+    }
+    if len == 0 || mask == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let mut bits = [0u8; CPUMASK_SIZE];
+    let n = len.min(CPUMASK_SIZE);
+    unsafe { core::ptr::copy_nonoverlapping(mask as *const u8, bits.as_mut_ptr(), n) };
+    if usize::from_le_bytes(bits) & ALL_CPUS == 0 {
+        return -(libc::EINVAL as isize);
+    }
+    0
+}
+
+/// Handle getcpu syscall
+///
+/// Threads are spread over the virtual CPUs by tid, so the answer is stable for a thread.
+pub fn sys_getcpu(cpu: usize, node: usize) -> isize {
+    #[cfg(feature = "scheduler")]
+    let tid = foundation::kfn::scheduler::kcurrent_tid();
+    #[cfg(not(feature = "scheduler"))]
+    let tid = 1usize;
+    if cpu != 0 {
+        // `NCPUS` is 1 unless configured otherwise.
+        #[allow(clippy::modulo_one)]
+        let id = (tid.saturating_sub(1) % NCPUS) as u32;
+        unsafe { (cpu as *mut u32).write_unaligned(id) };
+    }
+    if node != 0 {
+        unsafe { (node as *mut u32).write_unaligned(0) };
+    }
+    0
+}
+
+/// `struct riscv_hwprobe`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct HwProbe {
+    key: i64,
+    value: u64,
+}
+
+const KEY_MVENDORID: i64 = 0;
+const KEY_MARCHID: i64 = 1;
+const KEY_MIMPID: i64 = 2;
+const KEY_BASE_BEHAVIOR: i64 = 3;
+const KEY_IMA_EXT_0: i64 = 4;
+const KEY_CPUPERF_0: i64 = 5;
+const KEY_MISALIGNED_SCALAR_PERF: i64 = 9;
+
+const BASE_BEHAVIOR_IMA: u64 = 1 << 0;
+const MISALIGNED_UNKNOWN: u64 = 0;
+
+/// `RISCV_HWPROBE_IMA_*` / `RISCV_HWPROBE_EXT_*` bits for the extensions this build targets.
+const IMA_EXT_0: u64 = {
+    let mut bits = 0u64;
+    if cfg!(all(target_feature = "f", target_feature = "d")) {
+        bits |= 1 << 0;
+    }
+    if cfg!(target_feature = "c") {
+        bits |= 1 << 1;
+    }
+    if cfg!(target_feature = "v") {
+        bits |= 1 << 2;
+    }
+    if cfg!(target_feature = "zba") {
+        bits |= 1 << 3;
+    }
+    if cfg!(target_feature = "zbb") {
+        bits |= 1 << 4;
+    }
+    if cfg!(target_feature = "zbs") {
+        bits |= 1 << 5;
+    }
+    if cfg!(target_feature = "zbc") {
+        bits |= 1 << 7;
+    }
+    if cfg!(target_feature = "zbkb") {
+        bits |= 1 << 8;
+    }
+    if cfg!(target_feature = "zbkc") {
+        bits |= 1 << 9;
+    }
+    if cfg!(target_feature = "zbkx") {
+        bits |= 1 << 10;
+    }
+    if cfg!(target_feature = "zknd") {
+        bits |= 1 << 11;
+    }
+    if cfg!(target_feature = "zkne") {
+        bits |= 1 << 12;
+    }
+    if cfg!(target_feature = "zknh") {
+        bits |= 1 << 13;
+    }
+    if cfg!(target_feature = "zksed") {
+        bits |= 1 << 14;
+    }
+    if cfg!(target_feature = "zksh") {
+        bits |= 1 << 15;
+    }
+    if cfg!(target_feature = "zkt") {
+        bits |= 1 << 16;
+    }
+    bits
+};
+
+fn probe(key: i64) -> Option<u64> {
+    match key {
+        KEY_MVENDORID | KEY_MARCHID | KEY_MIMPID => Some(0),
+        // Base IMA requires the A extension; without it the base behavior is unknown.
+        KEY_BASE_BEHAVIOR if cfg!(all(target_feature = "m", target_feature = "a")) => {
+            Some(BASE_BEHAVIOR_IMA)
+        }
+        KEY_BASE_BEHAVIOR => Some(0),
+        KEY_IMA_EXT_0 => Some(IMA_EXT_0),
+        KEY_CPUPERF_0 | KEY_MISALIGNED_SCALAR_PERF => Some(MISALIGNED_UNKNOWN),
+        _ => None,
+    }
+}
+
+/// Handle riscv_hwprobe syscall
+///
+/// All virtual CPUs are identical, so the CPU set is only validated. Unknown keys are answered
+/// with key `-1`, as Linux does. `RISCV_HWPROBE_WHICH_CPUS` is not supported.
+pub fn sys_riscv_hwprobe(
+    pairs: usize,
+    pair_count: usize,
+    cpusetsize: usize,
+    cpus: usize,
+    flags: usize,
+) -> isize {
+    if flags != 0 {
+        return -(libc::EINVAL as isize);
+    }
+    if cpus != 0 && cpusetsize != 0 {
+        let mut bits = [0u8; CPUMASK_SIZE];
+        let n = cpusetsize.min(CPUMASK_SIZE);
+        unsafe { core::ptr::copy_nonoverlapping(cpus as *const u8, bits.as_mut_ptr(), n) };
+        if usize::from_le_bytes(bits) & ALL_CPUS == 0 {
+            return -(libc::EINVAL as isize);
+        }
+    }
+    if pair_count != 0 && pairs == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let pairs = pairs as *mut HwProbe;
+    for i in 0..pair_count {
+        unsafe {
+            let mut pair = pairs.add(i).read_unaligned();
+            match probe(pair.key) {
+                Some(value) => pair.value = value,
+                None => {
+                    pair.key = -1;
+                    pair.value = 0;
+                }
+            }
+            pairs.add(i).write_unaligned(pair);
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ncpus_defaults_to_one_and_parses_decimal() {
+        assert_eq!(parse_ncpus(None), 1);
+        assert_eq!(parse_ncpus(Some("4")), 4);
+        assert_eq!(parse_ncpus(Some("064")), 64);
+    }
+
+    #[test]
+    #[should_panic(expected = "decimal integer")]
+    fn test_ncpus_rejects_non_digits() {
+        parse_ncpus(Some("4 "));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn test_ncpus_rejects_zero() {
+        parse_ncpus(Some("0"));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn test_ncpus_rejects_more_than_a_mask_holds() {
+        parse_ncpus(Some("65"));
+    }
+
+    fn pair(key: i64) -> HwProbe {
+        HwProbe {
+            key,
+            value: u64::MAX,
+        }
+    }
+
+    #[test]
+    fn test_hwprobe_answers_known_keys_and_marks_unknown_ones() {
+        let mut pairs = [
+            pair(KEY_MVENDORID),
+            pair(KEY_BASE_BEHAVIOR),
+            pair(KEY_IMA_EXT_0),
+            pair(KEY_MISALIGNED_SCALAR_PERF),
+            pair(6),
+        ];
+        let ret = sys_riscv_hwprobe(pairs.as_mut_ptr() as usize, pairs.len(), 0, 0, 0);
+        assert_eq!(ret, 0);
+
+        let base = if cfg!(all(target_feature = "m", target_feature = "a")) {
+            BASE_BEHAVIOR_IMA
+        } else {
+            0
+        };
+        let answers: [(i64, u64); 5] = pairs.map(|p| (p.key, p.value));
+        assert_eq!(
+            answers,
+            [
+                (KEY_MVENDORID, 0),
+                (KEY_BASE_BEHAVIOR, base),
+                (KEY_IMA_EXT_0, IMA_EXT_0),
+                (KEY_MISALIGNED_SCALAR_PERF, MISALIGNED_UNKNOWN),
+                (-1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hwprobe_validates_flags_and_cpus() {
+        let mut pairs = [pair(KEY_MVENDORID)];
+        let ptr = pairs.as_mut_ptr() as usize;
+        assert_eq!(sys_riscv_hwprobe(ptr, 1, 0, 0, 1), -(libc::EINVAL as isize));
+
+        // A CPU set naming no virtual CPU.
+        let none = !ALL_CPUS;
+        let cpus = &none as *const usize as usize;
+        assert_eq!(
+            sys_riscv_hwprobe(ptr, 1, CPUMASK_SIZE, cpus, 0),
+            -(libc::EINVAL as isize)
+        );
+        let all = ALL_CPUS;
+        let cpus = &all as *const usize as usize;
+        assert_eq!(sys_riscv_hwprobe(ptr, 1, CPUMASK_SIZE, cpus, 0), 0);
+
+        assert_eq!(sys_riscv_hwprobe(0, 1, 0, 0, 0), -(libc::EFAULT as isize));
+    }
+
+    #[test]
+    fn test_affinity_covers_every_virtual_cpu() {
+        let mut mask = 0usize;
+        let ret = sys_sched_getaffinity(0, CPUMASK_SIZE, &mut mask as *mut usize as usize);
+        assert_eq!(ret, CPUMASK_SIZE as isize);
+        assert_eq!(mask.count_ones() as usize, NCPUS);
+    }
+}
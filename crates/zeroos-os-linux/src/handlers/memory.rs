@@ -1,14 +1,213 @@
 use core::alloc::Layout;
 
 use foundation::kfn;
+use foundation::ops::{MemoryRegion, PROTECT_SLOT_USTACK};
+use foundation::utils::KernelLock;
 use libc;
 
 const PAGE_SIZE: usize = 4096;
 
+/// Maximum number of disjoint `PROT_NONE` ranges tracked.
+const MAX_GUARDS: usize = 64;
+
+/// `PROT_NONE` ranges (`[start, end)`), i.e. the stack guards libc and Rust std set up.
+///
+/// Memory is never actually unmapped here; these ranges only tell the signal layer where a fault
+/// means "stack overflow" and which range to hardware-protect for the running thread.
+struct GuardTable {
+    ranges: [(usize, usize); MAX_GUARDS],
+    len: usize,
+}
+
+impl GuardTable {
+    fn remove(&mut self, start: usize, end: usize) {
+        let mut i = 0;
+        while i < self.len {
+            let (s, e) = self.ranges[i];
+            if e <= start || s >= end {
+                i += 1;
+                continue;
+            }
+            if s < start && e > end {
+                // Split; if the table is full the upper part is dropped (guards only shrink).
+                self.ranges[i] = (s, start);
+                self.push(end, e);
+                i += 1;
+            } else if s < start {
+                self.ranges[i] = (s, start);
+                i += 1;
+            } else if e > end {
+                self.ranges[i] = (end, e);
+                i += 1;
+            } else {
+                self.len -= 1;
+                self.ranges[i] = self.ranges[self.len];
+            }
+        }
+    }
+
+    fn push(&mut self, start: usize, end: usize) {
+        if self.len < MAX_GUARDS {
+            self.ranges[self.len] = (start, end);
+            self.len += 1;
+        }
+    }
+
+    fn insert(&mut self, start: usize, end: usize) {
+        self.remove(start, end);
+        self.push(start, end);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.ranges[..self.len].iter().copied()
+    }
+}
+
+static GUARDS: KernelLock<GuardTable> = KernelLock::new(GuardTable {
+    ranges: [(0, 0); MAX_GUARDS],
+    len: 0,
+});
+
+/// Return the guard range containing `addr`, if any.
+pub(crate) fn guard_containing(addr: usize) -> Option<(usize, usize)> {
+    GUARDS.with(|g| g.iter().find(|&(s, e)| (s..e).contains(&addr)))
+}
+
+/// Return the closest guard range ending at or below `sp` (the guard of the stack `sp` is on).
+pub(crate) fn guard_below(sp: usize) -> Option<(usize, usize)> {
+    GUARDS.with(|g| g.iter().filter(|&(_, e)| e <= sp).max_by_key(|&(_, e)| e))
+}
+
+/// Guard range currently hardware-protected for the running thread (`(0, 0)` = none).
+static ACTIVE_GUARD: KernelLock<(usize, usize)> = KernelLock::new((0, 0));
+
+/// Hardware-protect the guard of the stack `sp` is on (no-op without PMP support).
+pub(crate) fn protect_stack_guard(sp: usize) {
+    let want = guard_below(sp).unwrap_or((0, 0));
+    ACTIVE_GUARD.with_mut(|active| {
+        if *active != want {
+            *active = want;
+            kfn::arch::kprotect_region(PROTECT_SLOT_USTACK, want.0, want.1 - want.0);
+        }
+    });
+}
+
+/// Lift the hardware protection before the kernel touches or re-purposes guard memory.
+fn unprotect_stack_guard() {
+    ACTIVE_GUARD.with_mut(|active| {
+        if *active != (0, 0) {
+            *active = (0, 0);
+            kfn::arch::kprotect_region(PROTECT_SLOT_USTACK, 0, 0);
+        }
+    });
+}
+
+fn set_guard(addr: usize, size: usize, prot: usize) {
+    unprotect_stack_guard();
+    GUARDS.with_mut(|g| {
+        if prot == libc::PROT_NONE as usize {
+            g.insert(addr, addr + size);
+        } else {
+            g.remove(addr, addr + size);
+        }
+    });
+}
+
+/// `rwx` part of a `/proc/self/maps` permission string.
+fn region_perms(prot: usize) -> [u8; 3] {
+    let bit = |flag: i32, c: u8| if prot & flag as usize != 0 { c } else { b'-' };
+    [
+        bit(libc::PROT_READ, b'r'),
+        bit(libc::PROT_WRITE, b'w'),
+        bit(libc::PROT_EXEC, b'x'),
+    ]
+}
+
+/// Record a successful mapping for `/proc/self/maps` and pass `ret` through.
+fn record_mapping(ret: isize, size: usize, prot: usize, shared: bool) -> isize {
+    if !(-4095..0).contains(&ret) {
+        let [r, w, x] = region_perms(prot);
+        kfn::memory::kmap_region(MemoryRegion {
+            start: ret as usize,
+            end: ret as usize + size,
+            perms: [r, w, x, if shared { b's' } else { b'p' }],
+            name: "",
+        });
+    }
+    ret
+}
+
 pub fn sys_brk(_brk: usize) -> isize {
     -(libc::ENOMEM as isize)
 }
 
+/// Allocate `size` bytes of zeroed, page-aligned memory.
+fn alloc_pages(size: usize) -> Result<*mut u8, isize> {
+    let layout = Layout::from_size_align(size, PAGE_SIZE).map_err(|_| -(libc::EINVAL as isize))?;
+    let ptr = kfn::memory::kmalloc(layout);
+    if ptr.is_null() {
+        return Err(-(libc::ENOMEM as isize));
+    }
+    unsafe {
+        core::ptr::write_bytes(ptr, 0, size);
+    }
+    Ok(ptr)
+}
+
+/// Map `fd` at `offset`.
+///
+/// `MAP_SHARED` hands out the file's own storage (files whose `FileOps` implement `mmap`).
+/// `MAP_PRIVATE` is copy-on-read: the file is read into fresh memory at `mmap` time, so later
+/// writes to either side are not seen by the other.
+#[cfg(feature = "vfs")]
+fn map_file(fd: i32, len: usize, size: usize, prot: usize, shared: bool, offset: usize) -> isize {
+    if shared {
+        return kfn::vfs::kmmap_shared(fd, offset, size, prot & libc::PROT_WRITE as usize != 0);
+    }
+    let ptr = match alloc_pages(size) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let mut done = 0;
+    while done < len {
+        let n = kfn::vfs::kpread(
+            fd,
+            unsafe { ptr.add(done) },
+            len - done,
+            (offset + done) as isize,
+        );
+        if n < 0 {
+            kfn::memory::kfree(ptr, Layout::from_size_align(size, PAGE_SIZE).unwrap());
+            // Directories and streams cannot be mapped.
+            return match -n as i32 {
+                libc::EISDIR | libc::ESPIPE | libc::EINVAL => -(libc::ENODEV as isize),
+                libc::EBADF if kfn::vfs::kpoll(fd) & libc::POLLNVAL == 0 => {
+                    -(libc::EACCES as isize)
+                }
+                _ => n,
+            };
+        }
+        if n == 0 {
+            break;
+        }
+        done += n as usize;
+    }
+    set_guard(ptr as usize, size, prot);
+    ptr as isize
+}
+
+#[cfg(not(feature = "vfs"))]
+fn map_file(
+    _fd: i32,
+    _len: usize,
+    _size: usize,
+    _prot: usize,
+    _shared: bool,
+    _offset: usize,
+) -> isize {
+    -(libc::EBADF as isize)
+}
+
 pub fn sys_mmap(
     addr: usize,
     len: usize,
@@ -25,17 +224,20 @@ pub fn sys_mmap(
         return -(libc::EINVAL as isize);
     }
 
-    let allowed_flags = (libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_STACK) as usize;
+    let allowed_flags = (libc::MAP_PRIVATE
+        | libc::MAP_SHARED
+        | libc::MAP_ANONYMOUS
+        | libc::MAP_STACK
+        | libc::MAP_NORESERVE
+        | libc::MAP_POPULATE) as usize;
     if (flags & !allowed_flags) != 0 {
         return -(libc::EINVAL as isize);
     }
-    if (flags & libc::MAP_PRIVATE as usize) == 0 || (flags & libc::MAP_ANONYMOUS as usize) == 0 {
+    let shared = flags & libc::MAP_SHARED as usize != 0;
+    if shared == (flags & libc::MAP_PRIVATE as usize != 0) {
         return -(libc::EINVAL as isize);
     }
-    if addr != 0 || offset != 0 {
-        return -(libc::EINVAL as isize);
-    }
-    if fd != usize::MAX && fd != 0 {
+    if addr != 0 {
         return -(libc::EINVAL as isize);
     }
 
@@ -44,18 +246,28 @@ pub fn sys_mmap(
         Some(s) => s,
         None => return -(libc::EINVAL as isize),
     };
-    let layout = match Layout::from_size_align(size, PAGE_SIZE) {
-        Ok(l) => l,
-        Err(_) => return -(libc::EINVAL as isize),
-    };
-    let ptr = kfn::memory::kmalloc(layout);
-    if ptr.is_null() {
-        return -(libc::ENOMEM as isize);
+
+    if flags & libc::MAP_ANONYMOUS as usize == 0 {
+        if !offset.is_multiple_of(PAGE_SIZE) {
+            return -(libc::EINVAL as isize);
+        }
+        let ret = map_file(fd as i32, len, size, prot, shared, offset);
+        return record_mapping(ret, size, prot, shared);
     }
-    unsafe {
-        core::ptr::write_bytes(ptr, 0, size);
+    // With a single address space, shared anonymous memory is just anonymous memory.
+    if offset != 0 {
+        return -(libc::EINVAL as isize);
     }
-    ptr as isize
+    if fd != usize::MAX && fd != 0 {
+        return -(libc::EINVAL as isize);
+    }
+
+    let ptr = match alloc_pages(size) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    set_guard(ptr as usize, size, prot);
+    record_mapping(ptr as isize, size, prot, shared)
 }
 
 pub fn sys_munmap(addr: usize, len: usize) -> isize {
@@ -74,6 +286,14 @@ pub fn sys_munmap(addr: usize, len: usize) -> isize {
         Ok(l) => l,
         Err(_) => return -(libc::EINVAL as isize),
     };
+    unprotect_stack_guard();
+    GUARDS.with_mut(|g| g.remove(addr, addr + size));
+    kfn::memory::kunmap_region(addr, addr + size);
+    // File storage is released by the VFS, not the allocator.
+    #[cfg(feature = "vfs")]
+    if kfn::vfs::kmunmap_shared(addr, size) {
+        return 0;
+    }
     kfn::memory::kfree(addr as *mut u8, layout);
     0
 }
@@ -89,5 +309,8 @@ pub fn sys_mprotect(addr: usize, len: usize, prot: usize) -> isize {
     if (prot & !allowed_prot) != 0 {
         return -(libc::EINVAL as isize);
     }
+    let size = len.div_ceil(PAGE_SIZE) * PAGE_SIZE;
+    set_guard(addr, size, prot);
+    kfn::memory::kset_region_perms(addr, addr + size, region_perms(prot));
     0
 }
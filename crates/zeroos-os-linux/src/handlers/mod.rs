@@ -5,16 +5,42 @@ use cfg_if::cfg_if;
 use foundation::kfn;
 use libc;
 
+pub mod cpu;
 #[cfg(feature = "memory")]
 pub mod memory;
+#[cfg(feature = "vfs")]
+pub mod poll;
 #[cfg(feature = "random")]
 pub mod random;
 pub mod signal;
+#[cfg(feature = "vfs")]
+pub mod socket;
+pub mod sysinfo;
 #[cfg(feature = "scheduler")]
 pub mod thread;
+pub mod time;
 #[cfg(feature = "vfs")]
 pub mod vfs;
 
+/// Run an input transfer into `buf` through the record/replay journal (see
+/// `kfn::journal::kjournal_read`); just runs `read` without the `journal` feature.
+#[allow(dead_code)]
+#[inline(always)]
+pub(crate) fn journaled_input(
+    buf: *mut u8,
+    count: usize,
+    rerun: bool,
+    read: impl FnOnce() -> isize,
+) -> isize {
+    #[cfg(feature = "journal")]
+    return unsafe { kfn::journal::kjournal_read(buf, count, rerun, read) };
+    #[cfg(not(feature = "journal"))]
+    {
+        let _ = (buf, count, rerun);
+        read()
+    }
+}
+
 #[inline]
 pub fn sys_unsupported() -> isize {
     -(libc::ENOSYS as isize)
@@ -0,0 +1,273 @@
+//! Readiness syscalls: `ppoll`, `pselect6` and the `epoll` family.
+//!
+//! Each descriptor reports its readiness through `FileOps::poll`. When nothing is ready the
+//! caller sleeps on the kernel readiness generation (see `kfn::vfs::kpoll_wait`), so an infinite
+//! wait blocks the thread until some file changes state; when no other thread could ever change
+//! one, it fails with `EAGAIN`, as a blocking pipe read does. A finite wait sleeps the same way
+//! with the deadline as a timeout (see `kfn::vfs::kpoll_wait_until`), and a zero timeout only
+//! scans once, which also works without a scheduler.
+//!
+//! The temporary signal mask only decides which pending signals interrupt the wait (`EINTR`);
+//! it is not installed for the duration of the call.
+
+use foundation::kfn;
+use foundation::ops::MAX_FDS;
+use libc;
+
+use super::signal::{interrupted, SIGSET_SIZE};
+
+/// `FD_SETSIZE`: descriptors addressable by a `pselect6` set.
+const FD_SETSIZE: usize = 1024;
+const FD_SET_WORDS: usize = FD_SETSIZE / 64;
+
+/// Events reported whether or not they were asked for.
+const ALWAYS: i16 = libc::POLLERR | libc::POLLHUP | libc::POLLNVAL;
+
+/// Read a `struct timespec` timeout; `None` (null pointer) means wait forever.
+fn read_timeout(ts: usize) -> Result<Option<u64>, isize> {
+    if ts == 0 {
+        return Ok(None);
+    }
+    let ts = unsafe { (ts as *const libc::timespec).read_unaligned() };
+    if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+        return Err(-(libc::EINVAL as isize));
+    }
+    Ok(Some(
+        (ts.tv_sec as u64)
+            .saturating_mul(1_000_000_000)
+            .saturating_add(ts.tv_nsec as u64),
+    ))
+}
+
+fn read_sigmask(set: usize, sigsetsize: usize) -> Result<Option<u64>, isize> {
+    if set == 0 {
+        return Ok(None);
+    }
+    if sigsetsize != SIGSET_SIZE {
+        return Err(-(libc::EINVAL as isize));
+    }
+    Ok(Some(unsafe { (set as *const u64).read_unaligned() }))
+}
+
+/// Re-run `scan` until it reports ready descriptors (or an error), the timeout expires, or an
+/// unmasked signal is pending.
+fn wait_ready(
+    timeout_ns: Option<u64>,
+    sigmask: Option<u64>,
+    mut scan: impl FnMut() -> isize,
+) -> isize {
+    let deadline = timeout_ns.map(|t| kfn::time::ktime_ns().saturating_add(t));
+    loop {
+        let seq = kfn::vfs::kpoll_seq();
+        let ready = scan();
+        if ready != 0 {
+            return ready;
+        }
+        if deadline.is_some_and(|d| kfn::time::ktime_ns() >= d) {
+            return 0;
+        }
+        if interrupted(sigmask) {
+            return -(libc::EINTR as isize);
+        }
+        match deadline {
+            // Only a notification can make a descriptor ready.
+            None => {
+                if !kfn::vfs::kpoll_wait(seq) {
+                    return -(libc::EAGAIN as isize);
+                }
+            }
+            Some(deadline) => kfn::vfs::kpoll_wait_until(seq, deadline),
+        }
+    }
+}
+
+/// Handle ppoll syscall
+pub fn sys_ppoll(fds: usize, nfds: usize, tmo: usize, sigmask: usize, sigsetsize: usize) -> isize {
+    if nfds > MAX_FDS {
+        return -(libc::EINVAL as isize);
+    }
+    if nfds != 0 && fds == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let timeout = match read_timeout(tmo) {
+        Ok(t) => t,
+        Err(e) => return e,
+    };
+    let mask = match read_sigmask(sigmask, sigsetsize) {
+        Ok(m) => m,
+        Err(e) => return e,
+    };
+    let fds = fds as *mut libc::pollfd;
+    wait_ready(timeout, mask, || {
+        let mut ready = 0;
+        for i in 0..nfds {
+            let mut p = unsafe { fds.add(i).read_unaligned() };
+            p.revents = if p.fd < 0 {
+                0
+            } else {
+                kfn::vfs::kpoll(p.fd) & (p.events | ALWAYS)
+            };
+            if p.revents != 0 {
+                ready += 1;
+            }
+            unsafe { fds.add(i).write_unaligned(p) };
+        }
+        ready
+    })
+}
+
+fn read_set(set: usize, words: usize) -> [u64; FD_SET_WORDS] {
+    let mut out = [0u64; FD_SET_WORDS];
+    if set != 0 {
+        for (i, w) in out.iter_mut().take(words).enumerate() {
+            *w = unsafe { (set as *const u64).add(i).read_unaligned() };
+        }
+    }
+    out
+}
+
+fn write_set(set: usize, words: usize, bits: &[u64; FD_SET_WORDS]) {
+    if set != 0 {
+        for (i, &w) in bits.iter().take(words).enumerate() {
+            unsafe { (set as *mut u64).add(i).write_unaligned(w) };
+        }
+    }
+}
+
+/// Handle pselect6 syscall
+///
+/// `sig` points at `{ const sigset_t *ss; size_t ss_len; }`, as in Linux.
+pub fn sys_pselect6(
+    nfds: usize,
+    readfds: usize,
+    writefds: usize,
+    exceptfds: usize,
+    timeout: usize,
+    sig: usize,
+) -> isize {
+    if nfds > FD_SETSIZE {
+        return -(libc::EINVAL as isize);
+    }
+    let timeout = match read_timeout(timeout) {
+        Ok(t) => t,
+        Err(e) => return e,
+    };
+    let mask = if sig == 0 {
+        Ok(None)
+    } else {
+        let [ss, ss_len] = unsafe { (sig as *const [usize; 2]).read_unaligned() };
+        read_sigmask(ss, ss_len)
+    };
+    let mask = match mask {
+        Ok(m) => m,
+        Err(e) => return e,
+    };
+
+    let words = nfds.div_ceil(64);
+    let want = [
+        read_set(readfds, words),
+        read_set(writefds, words),
+        read_set(exceptfds, words),
+    ];
+    let events = [
+        libc::POLLIN | libc::POLLRDNORM | libc::POLLHUP | libc::POLLERR,
+        libc::POLLOUT | libc::POLLWRNORM | libc::POLLERR,
+        libc::POLLPRI,
+    ];
+    let mut got = [[0u64; FD_SET_WORDS]; 3];
+    let ret = wait_ready(timeout, mask, || {
+        got = [[0u64; FD_SET_WORDS]; 3];
+        let mut ready = 0;
+        for fd in 0..nfds {
+            let (word, bit) = (fd / 64, 1u64 << (fd % 64));
+            if want.iter().all(|set| set[word] & bit == 0) {
+                continue;
+            }
+            let revents = kfn::vfs::kpoll(fd as i32);
+            if revents & libc::POLLNVAL != 0 {
+                return -(libc::EBADF as isize);
+            }
+            for k in 0..3 {
+                if want[k][word] & bit != 0 && revents & events[k] != 0 {
+                    got[k][word] |= bit;
+                    ready += 1;
+                }
+            }
+        }
+        ready
+    });
+    if ret >= 0 {
+        write_set(readfds, words, &got[0]);
+        write_set(writefds, words, &got[1]);
+        write_set(exceptfds, words, &got[2]);
+    }
+    ret
+}
+
+/// Handle epoll_create1 syscall
+pub fn sys_epoll_create1(flags: usize) -> isize {
+    kfn::vfs::kepoll_create1(flags as i32)
+}
+
+/// Handle epoll_ctl syscall
+pub fn sys_epoll_ctl(epfd: usize, op: usize, fd: usize, event: usize) -> isize {
+    if event == 0 && op as i32 != libc::EPOLL_CTL_DEL {
+        return -(libc::EFAULT as isize);
+    }
+    unsafe { kfn::vfs::kepoll_ctl(epfd as i32, op as i32, fd as i32, event as *const u8) }
+}
+
+fn epoll_wait(
+    epfd: usize,
+    events: usize,
+    maxevents: usize,
+    timeout: Option<u64>,
+    sigmask: usize,
+    sigsetsize: usize,
+) -> isize {
+    let maxevents = maxevents as i32;
+    if maxevents <= 0 {
+        return -(libc::EINVAL as isize);
+    }
+    if events == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let mask = match read_sigmask(sigmask, sigsetsize) {
+        Ok(m) => m,
+        Err(e) => return e,
+    };
+    wait_ready(timeout, mask, || unsafe {
+        kfn::vfs::kepoll_scan(epfd as i32, events as *mut u8, maxevents as usize)
+    })
+}
+
+/// Handle epoll_pwait syscall
+///
+/// `timeout` is in milliseconds; a negative value waits forever.
+pub fn sys_epoll_pwait(
+    epfd: usize,
+    events: usize,
+    maxevents: usize,
+    timeout: usize,
+    sigmask: usize,
+    sigsetsize: usize,
+) -> isize {
+    let timeout = timeout as i32;
+    let timeout = (timeout >= 0).then(|| timeout as u64 * 1_000_000);
+    epoll_wait(epfd, events, maxevents, timeout, sigmask, sigsetsize)
+}
+
+/// Handle epoll_pwait2 syscall
+pub fn sys_epoll_pwait2(
+    epfd: usize,
+    events: usize,
+    maxevents: usize,
+    timeout: usize,
+    sigmask: usize,
+    sigsetsize: usize,
+) -> isize {
+    match read_timeout(timeout) {
+        Ok(t) => epoll_wait(epfd, events, maxevents, t, sigmask, sigsetsize),
+        Err(e) => e,
+    }
+}
@@ -8,5 +8,11 @@ pub fn sys_getrandom(buf: usize, buflen: usize, _flags: usize) -> isize {
     if buf == 0 {
         return -(libc::EFAULT as isize);
     }
-    unsafe { kfn::random::krandom(buf as *mut u8, buflen) }
+    let ret = unsafe { kfn::random::krandom(buf as *mut u8, buflen) };
+    #[cfg(feature = "journal")]
+    if ret > 0 {
+        let out = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, ret as usize) };
+        kfn::journal::kjournal_bytes(kfn::journal::J_RANDOM, out);
+    }
+    ret
 }
@@ -1,59 +1,708 @@
 //! Signal handling for ZeroOS
 //!
-//! Implements lightweight signal support for zkVM environment.
-//! Only handles SIGABRT for panic detection, other signals return ENOSYS.
+//! Handlers are process-wide; pending and blocked masks are per thread. Signals are delivered on
+//! the way back to guest code (`TrapOps::return_to_user`): a frame holding `siginfo_t` and
+//! `ucontext_t` is pushed on the user stack and the handler is entered with `ra` pointing at the
+//! arch `rt_sigreturn` trampoline. Synchronous faults reported through `TrapOps::exception` are
+//! raised as SIGSEGV, SIGBUS or SIGILL. A signal that terminates the process ends the run with
+//! a crash report (`kfn::crash`).
+//!
+//! Limitations: a thread blocked in the kernel is not interrupted (it sees the signal when its
+//! syscall returns), there is no job control (stop signals are ignored), and FP state is not saved.
 
 use libc;
 
-extern "C" {
-    /// Platform-specific abort handler (provided by jolt-platform or similar).
-    ///
-    /// # Arguments
-    /// * `sig` - The signal number that caused the abort (e.g., SIGABRT=6).
-    ///   Exit code will be computed as `128 + sig` per Linux convention.
-    fn __platform_abort(sig: i32) -> !;
+use foundation::kfn::arch as karch;
+use foundation::kfn::crash::{kcrash, CrashCause, CrashReport};
+use foundation::utils::KernelLock;
+
+/// Number of signals (`_NSIG`); valid numbers are `1..=NSIG`.
+const NSIG: usize = 64;
+/// Kernel `sigset_t` size in bytes.
+pub(crate) const SIGSET_SIZE: usize = 8;
+/// Threads tracked at once (matches the cooperative scheduler's thread table).
+const MAX_SIGNAL_THREADS: usize = 64;
+
+const SIG_DFL: usize = 0;
+const SIG_IGN: usize = 1;
+
+// `si_code` values.
+const SI_TKILL: i32 = -6;
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+const ILL_ILLOPC: i32 = 1;
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+const SEGV_MAPERR: i32 = 1;
+const SEGV_ACCERR: i32 = 2;
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+const BUS_ADRALN: i32 = 1;
+
+/// Offset of the `siginfo_t` union (`si_pid`, `si_addr`, ...).
+const SI_UNION_OFFSET: usize = if core::mem::size_of::<usize>() == 8 {
+    16
+} else {
+    12
+};
+
+#[inline(always)]
+const fn sigbit(sig: usize) -> u64 {
+    1 << (sig - 1)
 }
 
-/// Handle rt_sigaction syscall
+/// Signals that can be neither caught nor blocked.
+const UNBLOCKABLE: u64 = sigbit(libc::SIGKILL as usize) | sigbit(libc::SIGSTOP as usize);
+
+/// Kernel `struct sigaction` (asm-generic layout; RISC-V has no `sa_restorer`).
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct KSigAction {
+    handler: usize,
+    flags: usize,
+    mask: u64,
+}
+
+impl KSigAction {
+    const DEFAULT: Self = Self {
+        handler: SIG_DFL,
+        flags: 0,
+        mask: 0,
+    };
+}
+
+#[derive(Clone, Copy)]
+struct ThreadSignals {
+    /// Owning thread (0 = free slot).
+    tid: usize,
+    pending: u64,
+    blocked: u64,
+    /// Details of the pending synchronous fault, if any (`fault_sig == 0` otherwise).
+    fault_sig: usize,
+    fault_code: i32,
+    fault_addr: usize,
+    /// `rt_sigreturn` was called; restore the frame on the way out.
+    sigreturn: bool,
+    /// Alternate signal stack (`sigaltstack`); `alt_size == 0` = disabled.
+    alt_sp: usize,
+    alt_size: usize,
+    /// The thread is running a handler on the alternate stack.
+    on_alt: bool,
+}
+
+impl ThreadSignals {
+    const EMPTY: Self = Self {
+        tid: 0,
+        pending: 0,
+        blocked: 0,
+        fault_sig: 0,
+        fault_code: 0,
+        fault_addr: 0,
+        sigreturn: false,
+        alt_sp: 0,
+        alt_size: 0,
+        on_alt: false,
+    };
+
+    #[inline]
+    fn on_altstack(&self, sp: usize) -> bool {
+        self.alt_size != 0
+            && (self.alt_sp..self.alt_sp + self.alt_size).contains(&sp.wrapping_sub(1))
+    }
+}
+
+struct SignalState {
+    actions: [KSigAction; NSIG],
+    threads: [ThreadSignals; MAX_SIGNAL_THREADS],
+}
+
+static SIGNALS: KernelLock<SignalState> = KernelLock::new(SignalState {
+    actions: [KSigAction::DEFAULT; NSIG],
+    threads: [ThreadSignals::EMPTY; MAX_SIGNAL_THREADS],
+});
+
+/// Linux `ucontext_t` up to (excluding) `uc_mcontext`.
+#[repr(C)]
+struct UContextHead {
+    uc_flags: usize,
+    uc_link: usize,
+    ss_sp: usize,
+    ss_flags: i32,
+    ss_size: usize,
+    uc_sigmask: u64,
+    /// Rest of the 1024-bit userspace `sigset_t`.
+    unused: [u8; 120],
+}
+
+/// Start of a signal frame on the user stack; `uc_mcontext` follows at [`MCONTEXT_OFFSET`].
+#[repr(C, align(16))]
+struct SigFrameHead {
+    info: [u8; 128],
+    uc: UContextHead,
+}
+
+const MCONTEXT_OFFSET: usize = core::mem::size_of::<SigFrameHead>();
+
+#[inline]
+fn current_tid() -> usize {
+    #[cfg(feature = "scheduler")]
+    {
+        foundation::kfn::scheduler::kcurrent_tid()
+    }
+    #[cfg(not(feature = "scheduler"))]
+    {
+        1
+    }
+}
+
+#[inline]
+fn thread_alive(tid: usize) -> bool {
+    #[cfg(feature = "scheduler")]
+    {
+        foundation::kfn::scheduler::kthread_by_tid(tid)
+            .is_some_and(|t| t.status != foundation::ops::ThreadStatus::Exited)
+    }
+    #[cfg(not(feature = "scheduler"))]
+    {
+        tid == current_tid()
+    }
+}
+
+impl SignalState {
+    /// Signal state of `tid`, allocating (or recycling a dead thread's) slot on first use.
+    fn thread(&mut self, tid: usize) -> Option<&mut ThreadSignals> {
+        let idx = self.threads.iter().position(|t| t.tid == tid).or_else(|| {
+            self.threads
+                .iter()
+                .position(|t| t.tid == 0 || !thread_alive(t.tid))
+        })?;
+        let slot = &mut self.threads[idx];
+        if slot.tid != tid {
+            *slot = ThreadSignals {
+                tid,
+                ..ThreadSignals::EMPTY
+            };
+        }
+        Some(slot)
+    }
+
+    /// Whether `sig` would currently be discarded on delivery.
+    fn ignored(&self, sig: usize) -> bool {
+        match self.actions[sig - 1].handler {
+            SIG_IGN => true,
+            SIG_DFL => default_ignored(sig),
+            _ => false,
+        }
+    }
+}
+
+fn signal_name(sig: usize) -> &'static str {
+    const NAMES: [&str; 31] = [
+        "SIGHUP",
+        "SIGINT",
+        "SIGQUIT",
+        "SIGILL",
+        "SIGTRAP",
+        "SIGABRT",
+        "SIGBUS",
+        "SIGFPE",
+        "SIGKILL",
+        "SIGUSR1",
+        "SIGSEGV",
+        "SIGUSR2",
+        "SIGPIPE",
+        "SIGALRM",
+        "SIGTERM",
+        "SIGSTKFLT",
+        "SIGCHLD",
+        "SIGCONT",
+        "SIGSTOP",
+        "SIGTSTP",
+        "SIGTTIN",
+        "SIGTTOU",
+        "SIGURG",
+        "SIGXCPU",
+        "SIGXFSZ",
+        "SIGVTALRM",
+        "SIGPROF",
+        "SIGWINCH",
+        "SIGIO",
+        "SIGPWR",
+        "SIGSYS",
+    ];
+    NAMES.get(sig.wrapping_sub(1)).copied().unwrap_or("SIGRT")
+}
+
+/// A signal that kills the process, carried out of the `SIGNALS` critical section so that
+/// [`terminate`] runs with the lock released.
+struct Fatal {
+    sig: usize,
+    /// Faulting address when the signal comes from an exception.
+    fault: Option<usize>,
+}
+
+/// Terminate the process on a fatal signal (exit status `128 + sig`) with a crash report.
 ///
-/// For zkVM, we don't need full signal handler tables.
-/// We accept all sigaction calls but ignore them (return success).
-pub fn sys_rt_sigaction(_signum: usize, _act: usize, _oldact: usize, _sigsetsize: usize) -> isize {
-    // Accept but ignore - we'll handle signals directly
+/// `regs` is the trap frame of the killed context. Must not be called with `SIGNALS` held: the
+/// shutdown sequence reaches code that may take it again.
+fn terminate(Fatal { sig, fault }: Fatal, regs: *const u8) -> ! {
+    let name = signal_name(sig);
+    let report = |cause, message| -> ! {
+        kcrash(&CrashReport {
+            cause,
+            message,
+            exit_code: 128 + sig as i32,
+            regs,
+        })
+    };
+    match fault {
+        Some(addr) => report(CrashCause::Exception, format_args!("{name} at {addr:#x}")),
+        None => report(CrashCause::Signal, format_args!("killed by {name}")),
+    }
+}
+
+fn default_ignored(sig: usize) -> bool {
+    matches!(
+        sig as i32,
+        libc::SIGCHLD
+            | libc::SIGCONT
+            | libc::SIGURG
+            | libc::SIGWINCH
+            // No job control: stop signals are ignored.
+            | libc::SIGSTOP
+            | libc::SIGTSTP
+            | libc::SIGTTIN
+            | libc::SIGTTOU
+    )
+}
+
+#[inline]
+fn valid_sig(sig: usize) -> bool {
+    (1..=NSIG).contains(&sig)
+}
+
+/// Queue `sig` for thread `tid`.
+fn send(tid: usize, sig: usize) -> isize {
+    if sig != 0 && !valid_sig(sig) {
+        return -(libc::EINVAL as isize);
+    }
+    if !thread_alive(tid) {
+        return -(libc::ESRCH as isize);
+    }
+    if sig == 0 {
+        return 0;
+    }
+    SIGNALS.with_mut(|s| {
+        if s.ignored(sig) {
+            return 0;
+        }
+        match s.thread(tid) {
+            Some(t) => {
+                t.pending |= sigbit(sig);
+                0
+            }
+            None => -(libc::EAGAIN as isize),
+        }
+    })
+}
+
+/// Raise `SIGPIPE` on the calling thread when a write fails with `EPIPE` (the reader is gone);
+/// returns `ret` unchanged. Like any signal it is delivered on the way back to user mode.
+#[cfg(feature = "vfs")]
+pub(crate) fn sigpipe_on_epipe(ret: isize) -> isize {
+    if ret == -(libc::EPIPE as isize) {
+        send(current_tid(), libc::SIGPIPE as usize);
+    }
+    ret
+}
+
+/// Copy the caller's signal mask to a newly created thread.
+#[cfg(feature = "scheduler")]
+pub(crate) fn inherit(child_tid: usize) {
+    let tid = current_tid();
+    SIGNALS.with_mut(|s| {
+        let blocked = s.thread(tid).map_or(0, |t| t.blocked);
+        if let Some(child) = s.thread(child_tid) {
+            child.blocked = blocked;
+        }
+    });
+}
+
+/// Handle rt_sigaction syscall
+pub fn sys_rt_sigaction(signum: usize, act: usize, oldact: usize, sigsetsize: usize) -> isize {
+    if sigsetsize != SIGSET_SIZE || !valid_sig(signum) {
+        return -(libc::EINVAL as isize);
+    }
+    if act != 0 && UNBLOCKABLE & sigbit(signum) != 0 {
+        return -(libc::EINVAL as isize);
+    }
+
+    SIGNALS.with_mut(|s| {
+        if oldact != 0 {
+            unsafe { (oldact as *mut KSigAction).write_unaligned(s.actions[signum - 1]) };
+        }
+        if act != 0 {
+            let mut new = unsafe { (act as *const KSigAction).read_unaligned() };
+            new.mask &= !UNBLOCKABLE;
+            s.actions[signum - 1] = new;
+            // POSIX: setting a pending signal to be ignored discards it.
+            if s.ignored(signum) {
+                for t in s.threads.iter_mut() {
+                    t.pending &= !sigbit(signum);
+                }
+            }
+        }
+    });
     0
 }
 
 /// Handle rt_sigprocmask syscall
-///
-/// Signal masks aren't needed in zkVM - accept but ignore.
-pub fn sys_rt_sigprocmask(_how: usize, _set: usize, _oldset: usize, _sigsetsize: usize) -> isize {
-    // Accept but ignore
+pub fn sys_rt_sigprocmask(how: usize, set: usize, oldset: usize, sigsetsize: usize) -> isize {
+    if sigsetsize != SIGSET_SIZE {
+        return -(libc::EINVAL as isize);
+    }
+    let tid = current_tid();
+    SIGNALS.with_mut(|s| {
+        let Some(t) = s.thread(tid) else {
+            return -(libc::EAGAIN as isize);
+        };
+        let old = t.blocked;
+        if set != 0 {
+            let set = unsafe { (set as *const u64).read_unaligned() };
+            t.blocked = match how as i32 {
+                libc::SIG_BLOCK => old | set,
+                libc::SIG_UNBLOCK => old & !set,
+                libc::SIG_SETMASK => set,
+                _ => return -(libc::EINVAL as isize),
+            } & !UNBLOCKABLE;
+        }
+        if oldset != 0 {
+            unsafe { (oldset as *mut u64).write_unaligned(old) };
+        }
+        0
+    })
+}
+
+/// Whether the current thread has a pending signal that `mask` (default: its own blocked set)
+/// does not block; blocking syscalls then stop waiting and return `EINTR`.
+pub(crate) fn interrupted(mask: Option<u64>) -> bool {
+    let tid = current_tid();
+    SIGNALS.with_mut(|s| {
+        s.thread(tid)
+            .is_some_and(|t| t.pending & !mask.map_or(t.blocked, |m| m & !UNBLOCKABLE) != 0)
+    })
+}
+
+/// Handle rt_sigpending syscall
+pub fn sys_rt_sigpending(set: usize, sigsetsize: usize) -> isize {
+    if sigsetsize != SIGSET_SIZE {
+        return -(libc::EINVAL as isize);
+    }
+    if set == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let tid = current_tid();
+    let pending = SIGNALS.with_mut(|s| s.thread(tid).map_or(0, |t| t.pending));
+    unsafe { (set as *mut u64).write_unaligned(pending) };
     0
 }
 
-/// Handle tkill syscall
+/// Handle rt_sigreturn syscall
 ///
-/// Send signal to a thread. For zkVM, we only handle SIGABRT for panic detection.
-pub fn sys_tkill(_tid: usize, sig: usize) -> isize {
-    const SIGABRT: usize = 6;
+/// The frame is restored by [`return_to_user`], which has access to the trap frame; the value
+/// returned here is overwritten by the restored context.
+pub fn sys_rt_sigreturn() -> isize {
+    let tid = current_tid();
+    SIGNALS.with_mut(|s| {
+        if let Some(t) = s.thread(tid) {
+            t.sigreturn = true;
+        }
+    });
+    0
+}
 
-    match sig {
-        SIGABRT => {
-            // This is a panic/abort - call platform-specific handler
-            // Exit code will be 128 + 6 = 134 per Linux convention
-            unsafe { __platform_abort(sig as i32) }
+/// Handle sigaltstack syscall
+pub fn sys_sigaltstack(ss: usize, old_ss: usize) -> isize {
+    let tid = current_tid();
+    SIGNALS.with_mut(|s| {
+        let Some(t) = s.thread(tid) else {
+            return -(libc::EAGAIN as isize);
+        };
+        let on_stack = t.on_alt;
+        if old_ss != 0 {
+            let flags = if on_stack {
+                libc::SS_ONSTACK
+            } else if t.alt_size == 0 {
+                libc::SS_DISABLE
+            } else {
+                0
+            };
+            let old = libc::stack_t {
+                ss_sp: t.alt_sp as *mut libc::c_void,
+                ss_flags: flags,
+                ss_size: t.alt_size,
+            };
+            unsafe { (old_ss as *mut libc::stack_t).write_unaligned(old) };
         }
-        _ => {
-            // Other signals not implemented
-            -(libc::ENOSYS as isize)
+        if ss != 0 {
+            if on_stack {
+                return -(libc::EPERM as isize);
+            }
+            let new = unsafe { (ss as *const libc::stack_t).read_unaligned() };
+            if new.ss_flags & libc::SS_DISABLE != 0 {
+                t.alt_sp = 0;
+                t.alt_size = 0;
+            } else if new.ss_flags != 0 {
+                return -(libc::EINVAL as isize);
+            } else if new.ss_size < libc::MINSIGSTKSZ {
+                return -(libc::ENOMEM as isize);
+            } else {
+                t.alt_sp = new.ss_sp as usize;
+                t.alt_size = new.ss_size;
+            }
         }
-    }
+        0
+    })
+}
+
+/// Handle tkill syscall
+pub fn sys_tkill(tid: usize, sig: usize) -> isize {
+    send(tid, sig)
 }
 
 /// Handle tgkill syscall
 ///
-/// Similar to tkill but with thread group ID.
+/// There is a single thread group, so this behaves like tkill.
 pub fn sys_tgkill(_tgid: usize, tid: usize, sig: usize) -> isize {
-    // For single-threaded zkVM, tgkill behaves like tkill
     sys_tkill(tid, sig)
 }
+
+/// Handle kill syscall
+///
+/// The only process is ours (pid 1); process-directed signals go to the calling thread.
+pub fn sys_kill(pid: usize, sig: usize) -> isize {
+    match pid as isize {
+        0 | 1 => send(current_tid(), sig),
+        _ => -(libc::ESRCH as isize),
+    }
+}
+
+/// Map an exception cause to `(signal, si_code, address is pc)`.
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+fn fault_signal(code: usize) -> Option<(usize, i32, bool)> {
+    let sig = match code {
+        // Instruction address misaligned / access fault.
+        0 => (libc::SIGBUS as usize, BUS_ADRALN, true),
+        1 => (libc::SIGSEGV as usize, SEGV_ACCERR, true),
+        // Illegal instruction.
+        2 => (libc::SIGILL as usize, ILL_ILLOPC, true),
+        // Load/store address misaligned.
+        4 | 6 => (libc::SIGBUS as usize, BUS_ADRALN, false),
+        // Load/store access fault.
+        5 | 7 => (libc::SIGSEGV as usize, SEGV_ACCERR, false),
+        // Instruction/load/store page fault.
+        12 => (libc::SIGSEGV as usize, SEGV_MAPERR, true),
+        13 | 15 => (libc::SIGSEGV as usize, SEGV_MAPERR, false),
+        _ => return None,
+    };
+    Some(sig)
+}
+
+#[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+fn fault_signal(_code: usize) -> Option<(usize, i32, bool)> {
+    None
+}
+
+/// Queue a synchronous fault signal for `tid`.
+///
+/// Like Linux's forced signals, a fault that is blocked, ignored or unhandled terminates the
+/// process (exit status `128 + sig`).
+fn force_fault(
+    s: &mut SignalState,
+    tid: usize,
+    sig: usize,
+    si_code: i32,
+    addr: usize,
+) -> Result<(), Fatal> {
+    let fatal = Fatal {
+        sig,
+        fault: Some(addr),
+    };
+    let handler = s.actions[sig - 1].handler;
+    let Some(t) = s.thread(tid) else {
+        return Err(fatal);
+    };
+    if handler == SIG_DFL || handler == SIG_IGN || t.blocked & sigbit(sig) != 0 {
+        return Err(fatal);
+    }
+    t.pending |= sigbit(sig);
+    t.fault_sig = sig;
+    t.fault_code = si_code;
+    t.fault_addr = addr;
+    Ok(())
+}
+
+/// `TrapOps::exception`: raise the matching signal for a synchronous fault.
+///
+/// Returns `None` only for causes that have no signal mapping.
+pub fn exception(regs: *mut u8, code: usize, pc: usize, trap_value: usize) -> Option<usize> {
+    let (sig, si_code, at_pc) = fault_signal(code)?;
+    let tid = current_tid();
+    let addr = if at_pc { pc } else { trap_value };
+    if let Err(fatal) = SIGNALS.with_mut(|s| force_fault(s, tid, sig, si_code, addr)) {
+        terminate(fatal, regs)
+    }
+    Some(pc)
+}
+
+/// `TrapOps::return_to_user`: finish `rt_sigreturn` and deliver one pending signal.
+///
+/// # Safety
+/// `regs` must point to the trap frame of the trap being returned from.
+pub unsafe fn return_to_user(regs: *mut u8) {
+    let tid = current_tid();
+    let delivered = SIGNALS.with_mut(|s| {
+        let Some(t) = s.thread(tid) else {
+            return Ok(());
+        };
+        if core::mem::take(&mut t.sigreturn) {
+            restore_frame(regs, t);
+        }
+
+        // Without hardware guards an overflow is only noticed once sp itself is in a guard.
+        #[cfg(feature = "memory")]
+        {
+            let sp = karch::ktrap_frame_get_sp(regs);
+            if super::memory::guard_containing(sp).is_some() {
+                force_fault(s, tid, libc::SIGSEGV as usize, SEGV_ACCERR, sp)?;
+            }
+        }
+
+        deliver(regs, s, tid)
+    });
+    if let Err(fatal) = delivered {
+        terminate(fatal, regs)
+    }
+
+    #[cfg(feature = "memory")]
+    super::memory::protect_stack_guard(karch::ktrap_frame_get_sp(regs));
+}
+
+/// Deliver the lowest pending unblocked signal of `tid`, if any.
+unsafe fn deliver(regs: *mut u8, s: &mut SignalState, tid: usize) -> Result<(), Fatal> {
+    loop {
+        let Some(t) = s.thread(tid) else {
+            return Ok(());
+        };
+        let deliverable = t.pending & !t.blocked;
+        if deliverable == 0 {
+            return Ok(());
+        }
+        let sig = deliverable.trailing_zeros() as usize + 1;
+        t.pending &= !sigbit(sig);
+        let fault = if t.fault_sig == sig {
+            t.fault_sig = 0;
+            Some((t.fault_code, t.fault_addr))
+        } else {
+            None
+        };
+
+        let action = s.actions[sig - 1];
+        match action.handler {
+            SIG_IGN => continue,
+            SIG_DFL if default_ignored(sig) => continue,
+            SIG_DFL => {
+                return Err(Fatal {
+                    sig,
+                    fault: fault.map(|(_, addr)| addr),
+                })
+            }
+            _ => {}
+        }
+
+        if action.flags & libc::SA_RESETHAND as usize != 0 {
+            s.actions[sig - 1] = KSigAction::DEFAULT;
+        }
+        let Some(t) = s.thread(tid) else {
+            return Ok(());
+        };
+        return push_frame(regs, t, sig, &action, fault);
+    }
+}
+
+unsafe fn push_frame(
+    regs: *mut u8,
+    t: &mut ThreadSignals,
+    sig: usize,
+    action: &KSigAction,
+    fault: Option<(i32, usize)>,
+) -> Result<(), Fatal> {
+    let user_sp = karch::ktrap_frame_get_sp(regs);
+    let alt_flags = if t.alt_size == 0 {
+        libc::SS_DISABLE
+    } else if t.on_altstack(user_sp) {
+        libc::SS_ONSTACK
+    } else {
+        0
+    };
+    let use_alt = action.flags & libc::SA_ONSTACK as usize != 0 && alt_flags == 0;
+    let top = if use_alt {
+        t.alt_sp + t.alt_size
+    } else {
+        user_sp
+    };
+    let frame_size = MCONTEXT_OFFSET + karch::kmcontext_size();
+    let sp = (top - frame_size) & !0xF;
+
+    // The frame would land in a stack guard (typically: overflow without an alternate stack).
+    #[cfg(feature = "memory")]
+    if super::memory::guard_containing(sp).is_some()
+        || super::memory::guard_containing(top - 1).is_some()
+    {
+        return Err(Fatal {
+            sig,
+            fault: Some(sp),
+        });
+    }
+
+    let frame = sp as *mut SigFrameHead;
+
+    core::ptr::write_bytes(frame as *mut u8, 0, MCONTEXT_OFFSET);
+    let info = core::ptr::addr_of_mut!((*frame).info) as *mut u8;
+    (info as *mut i32).write(sig as i32);
+    match fault {
+        Some((code, addr)) => {
+            (info.add(8) as *mut i32).write(code);
+            (info.add(SI_UNION_OFFSET) as *mut usize).write(addr);
+        }
+        None => {
+            (info.add(8) as *mut i32).write(SI_TKILL);
+            (info.add(SI_UNION_OFFSET) as *mut i32).write(1); // si_pid
+        }
+    }
+    (*frame).uc.ss_sp = t.alt_sp;
+    (*frame).uc.ss_flags = alt_flags;
+    (*frame).uc.ss_size = t.alt_size;
+    (*frame).uc.uc_sigmask = t.blocked;
+    karch::kmcontext_save(regs, (sp + MCONTEXT_OFFSET) as *mut u8);
+
+    karch::ktrap_frame_set_pc(regs, action.handler);
+    karch::ktrap_frame_set_sp(regs, sp);
+    karch::ktrap_frame_set_ra(regs, karch::ksigreturn_trampoline());
+    karch::ktrap_frame_set_arg(regs, 0, sig);
+    karch::ktrap_frame_set_arg(regs, 1, info as usize);
+    karch::ktrap_frame_set_arg(regs, 2, core::ptr::addr_of!((*frame).uc) as usize);
+
+    t.on_alt |= use_alt;
+    t.blocked |= action.mask;
+    if action.flags & libc::SA_NODEFER as usize == 0 {
+        t.blocked |= sigbit(sig);
+    }
+    t.blocked &= !UNBLOCKABLE;
+    Ok(())
+}
+
+unsafe fn restore_frame(regs: *mut u8, t: &mut ThreadSignals) {
+    let sp = karch::ktrap_frame_get_sp(regs);
+    let frame = sp as *const SigFrameHead;
+    t.blocked = (*frame).uc.uc_sigmask & !UNBLOCKABLE;
+    karch::kmcontext_restore(regs, (sp + MCONTEXT_OFFSET) as *const u8);
+    t.on_alt = t.on_altstack(karch::ktrap_frame_get_sp(regs));
+}
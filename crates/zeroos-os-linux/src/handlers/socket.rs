@@ -0,0 +1,135 @@
+//! Socket syscalls on the in-guest loopback stack (`AF_UNIX` and `127.0.0.1` streams).
+//!
+//! There is no network device: connections only reach listeners inside the guest, see the VFS
+//! socket objects. Addresses are passed through as raw `struct sockaddr` bytes.
+
+use foundation::kfn;
+use libc;
+
+/// Handle socket syscall
+pub fn sys_socket(domain: usize, ty: usize, protocol: usize) -> isize {
+    kfn::vfs::ksocket(domain as i32, ty as i32, protocol as i32)
+}
+
+/// Handle socketpair syscall
+pub fn sys_socketpair(domain: usize, ty: usize, protocol: usize, sv: usize) -> isize {
+    if sv == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let mut pair = [0i32; 2];
+    let r = kfn::vfs::ksocketpair(domain as i32, ty as i32, protocol as i32, &mut pair);
+    if r == 0 {
+        unsafe { (sv as *mut [i32; 2]).write_unaligned(pair) };
+    }
+    r
+}
+
+/// Handle bind syscall
+pub fn sys_bind(fd: usize, addr: usize, addrlen: usize) -> isize {
+    unsafe { kfn::vfs::kbind(fd as i32, addr as *const u8, addrlen as u32 as usize) }
+}
+
+/// Handle listen syscall
+pub fn sys_listen(fd: usize, backlog: usize) -> isize {
+    kfn::vfs::klisten(fd as i32, backlog as i32)
+}
+
+/// Handle accept4 syscall
+pub fn sys_accept4(fd: usize, addr: usize, addrlen: usize, flags: usize) -> isize {
+    unsafe {
+        kfn::vfs::kaccept4(
+            fd as i32,
+            addr as *mut u8,
+            addrlen as *mut u32,
+            flags as i32,
+        )
+    }
+}
+
+/// Handle accept syscall
+pub fn sys_accept(fd: usize, addr: usize, addrlen: usize) -> isize {
+    sys_accept4(fd, addr, addrlen, 0)
+}
+
+/// Handle connect syscall
+pub fn sys_connect(fd: usize, addr: usize, addrlen: usize) -> isize {
+    unsafe { kfn::vfs::kconnect(fd as i32, addr as *const u8, addrlen as u32 as usize) }
+}
+
+/// Handle sendto syscall
+///
+/// Stream sockets are connected, so the destination address is ignored. Sending on a
+/// connection the peer shut down raises `SIGPIPE` unless `MSG_NOSIGNAL` is set.
+pub fn sys_sendto(
+    fd: usize,
+    buf: usize,
+    len: usize,
+    flags: usize,
+    _dest_addr: usize,
+    _addrlen: usize,
+) -> isize {
+    let r = kfn::vfs::ksendto(fd as i32, buf as *const u8, len, flags as i32);
+    if flags as i32 & libc::MSG_NOSIGNAL != 0 {
+        return r;
+    }
+    super::signal::sigpipe_on_epipe(r)
+}
+
+/// Handle recvfrom syscall
+///
+/// Stream sockets report no source address (`*addrlen` is set to 0), as for TCP.
+pub fn sys_recvfrom(
+    fd: usize,
+    buf: usize,
+    len: usize,
+    flags: usize,
+    src_addr: usize,
+    addrlen: usize,
+) -> isize {
+    let r = super::journaled_input(buf as *mut u8, len, true, || {
+        kfn::vfs::krecvfrom(fd as i32, buf as *mut u8, len, flags as i32)
+    });
+    if r >= 0 && src_addr != 0 && addrlen != 0 {
+        unsafe { (addrlen as *mut u32).write_unaligned(0) };
+    }
+    r
+}
+
+/// Handle shutdown syscall
+pub fn sys_shutdown(fd: usize, how: usize) -> isize {
+    kfn::vfs::ksock_shutdown(fd as i32, how as i32)
+}
+
+/// Handle getsockname syscall
+pub fn sys_getsockname(fd: usize, addr: usize, addrlen: usize) -> isize {
+    unsafe { kfn::vfs::ksockname(fd as i32, addr as *mut u8, addrlen as *mut u32, false) }
+}
+
+/// Handle getpeername syscall
+pub fn sys_getpeername(fd: usize, addr: usize, addrlen: usize) -> isize {
+    unsafe { kfn::vfs::ksockname(fd as i32, addr as *mut u8, addrlen as *mut u32, true) }
+}
+
+/// Handle getsockopt syscall
+pub fn sys_getsockopt(fd: usize, level: usize, name: usize, optval: usize, optlen: usize) -> isize {
+    unsafe {
+        kfn::vfs::kgetsockopt(
+            fd as i32,
+            level as i32,
+            name as i32,
+            optval as *mut u8,
+            optlen as *mut u32,
+        )
+    }
+}
+
+/// Handle setsockopt syscall
+pub fn sys_setsockopt(fd: usize, level: usize, name: usize, optval: usize, optlen: usize) -> isize {
+    kfn::vfs::ksetsockopt(
+        fd as i32,
+        level as i32,
+        name as i32,
+        optval as *const u8,
+        optlen,
+    )
+}
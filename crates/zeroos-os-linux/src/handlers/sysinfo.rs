@@ -0,0 +1,189 @@
+//! Process identity and system information.
+//!
+//! Every value is deterministic. The `uname` strings can be overridden at build time with
+//! `ZEROOS_UNAME_NODENAME` and `ZEROOS_UNAME_RELEASE`; resource limits are derived from the
+//! linker's stack reservation and the allocator's heap.
+
+use foundation::ops::MAX_FDS;
+use foundation::utils::KernelLock;
+use libc;
+
+const fn env_or(value: Option<&'static str>, default: &'static str) -> &'static str {
+    match value {
+        Some(v) => v,
+        None => default,
+    }
+}
+
+const SYSNAME: &str = "Linux";
+const NODENAME: &str = env_or(option_env!("ZEROOS_UNAME_NODENAME"), "zeroos");
+/// Kernel release; Linux-shaped so libc/std version probes parse it.
+const RELEASE: &str = env_or(option_env!("ZEROOS_UNAME_RELEASE"), "6.1.0-zeroos");
+const VERSION: &str = concat!("#1 ZeroOS ", env!("CARGO_PKG_VERSION"));
+#[cfg(target_arch = "riscv32")]
+const MACHINE: &str = "riscv32";
+#[cfg(not(target_arch = "riscv32"))]
+const MACHINE: &str = "riscv64";
+const DOMAINNAME: &str = "(none)";
+
+/// The single process runs as root.
+const UID: isize = 0;
+const GID: isize = 0;
+
+extern "C" {
+    static __stack_top: u8;
+    static __stack_bottom: u8;
+}
+
+fn copy_field(dst: &mut [libc::c_char], src: &str) {
+    let n = src.len().min(dst.len() - 1);
+    for (d, &s) in dst.iter_mut().zip(&src.as_bytes()[..n]) {
+        *d = s as libc::c_char;
+    }
+    dst[n] = 0;
+}
+
+/// Handle uname syscall
+pub fn sys_uname(buf: usize) -> isize {
+    if buf == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let mut uts: libc::utsname = unsafe { core::mem::zeroed() };
+    copy_field(&mut uts.sysname, SYSNAME);
+    copy_field(&mut uts.nodename, NODENAME);
+    copy_field(&mut uts.release, RELEASE);
+    copy_field(&mut uts.version, VERSION);
+    copy_field(&mut uts.machine, MACHINE);
+    copy_field(&mut uts.domainname, DOMAINNAME);
+    unsafe { (buf as *mut libc::utsname).write_unaligned(uts) };
+    0
+}
+
+pub fn sys_getuid() -> isize {
+    UID
+}
+
+pub fn sys_geteuid() -> isize {
+    UID
+}
+
+pub fn sys_getgid() -> isize {
+    GID
+}
+
+pub fn sys_getegid() -> isize {
+    GID
+}
+
+/// The process has no parent inside the guest.
+pub fn sys_getppid() -> isize {
+    0
+}
+
+fn heap_stats() -> (usize, usize) {
+    #[cfg(feature = "memory")]
+    {
+        let stats = foundation::kfn::memory::kstats();
+        (stats.total, stats.free)
+    }
+    #[cfg(not(feature = "memory"))]
+    {
+        (0, 0)
+    }
+}
+
+fn thread_count() -> usize {
+    #[cfg(feature = "scheduler")]
+    {
+        foundation::kfn::scheduler::kthread_count()
+    }
+    #[cfg(not(feature = "scheduler"))]
+    {
+        1
+    }
+}
+
+/// Handle sysinfo syscall
+pub fn sys_sysinfo(info: usize) -> isize {
+    if info == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let (total, free) = heap_stats();
+    let mut si: libc::sysinfo = unsafe { core::mem::zeroed() };
+    si.uptime = foundation::kfn::time::ktime().as_secs() as _;
+    si.totalram = total as libc::c_ulong;
+    si.freeram = free as libc::c_ulong;
+    si.procs = thread_count().min(u16::MAX as usize) as libc::c_ushort;
+    si.mem_unit = 1;
+    unsafe { (info as *mut libc::sysinfo).write_unaligned(si) };
+    0
+}
+
+/// Number of resource limits in the Linux ABI (`RLIM_NLIMITS`).
+const RLIM_NLIMITS: usize = 16;
+
+/// Limits changed with `setrlimit`/`prlimit64` (`None` = default).
+static OVERRIDES: KernelLock<[Option<libc::rlimit64>; RLIM_NLIMITS]> =
+    KernelLock::new([None; RLIM_NLIMITS]);
+
+fn default_limit(resource: usize) -> u64 {
+    let (heap, _) = heap_stats();
+    match resource as _ {
+        libc::RLIMIT_STACK => {
+            (core::ptr::addr_of!(__stack_top) as usize
+                - core::ptr::addr_of!(__stack_bottom) as usize) as u64
+        }
+        libc::RLIMIT_DATA | libc::RLIMIT_AS if heap != 0 => heap as u64,
+        libc::RLIMIT_NOFILE => MAX_FDS as u64,
+        libc::RLIMIT_CORE => 0,
+        _ => libc::RLIM64_INFINITY,
+    }
+}
+
+fn get_limit(resource: usize) -> libc::rlimit64 {
+    OVERRIDES.with(|o| o[resource]).unwrap_or_else(|| {
+        let v = default_limit(resource);
+        libc::rlimit64 {
+            rlim_cur: v,
+            rlim_max: v,
+        }
+    })
+}
+
+fn set_limit(resource: usize, new: libc::rlimit64) -> isize {
+    if new.rlim_cur > new.rlim_max {
+        return -(libc::EINVAL as isize);
+    }
+    OVERRIDES.with_mut(|o| o[resource] = Some(new));
+    0
+}
+
+/// Handle getrlimit syscall
+pub fn sys_getrlimit(resource: usize, rlim: usize) -> isize {
+    sys_prlimit64(0, resource, 0, rlim)
+}
+
+/// Handle setrlimit syscall
+pub fn sys_setrlimit(resource: usize, rlim: usize) -> isize {
+    sys_prlimit64(0, resource, rlim, 0)
+}
+
+/// Handle prlimit64 syscall
+pub fn sys_prlimit64(pid: usize, resource: usize, new_limit: usize, old_limit: usize) -> isize {
+    // The only process is pid 1 (see `getpid`).
+    if pid != 0 && pid != 1 {
+        return -(libc::ESRCH as isize);
+    }
+    if resource >= RLIM_NLIMITS {
+        return -(libc::EINVAL as isize);
+    }
+    if old_limit != 0 {
+        let old = get_limit(resource);
+        unsafe { (old_limit as *mut libc::rlimit64).write_unaligned(old) };
+    }
+    if new_limit != 0 {
+        let new = unsafe { (new_limit as *const libc::rlimit64).read_unaligned() };
+        return set_limit(resource, new);
+    }
+    0
+}
@@ -72,21 +72,26 @@ pub fn sys_clone(
         0
     };
 
-    kfn::scheduler::kspawn_thread(
+    let tid = kfn::scheduler::kspawn_thread(
         stack,
         tls_val,
         parent_tid_ptr,
         child_tid_ptr,
         clear_child_tid_ptr,
-    )
+    );
+    if tid > 0 {
+        super::signal::inherit(tid as usize);
+    }
+    tid
 }
 
 pub fn sys_exit(status: usize) -> isize {
     kfn::scheduler::kexit_current(status as i32)
 }
 
+/// Exit the whole process: every thread ends together with the machine, whichever thread calls.
 pub fn sys_exit_group(status: usize) -> isize {
-    kfn::scheduler::kexit_current(status as i32)
+    kfn::kexit(status as i32)
 }
 
 pub fn sys_futex(addr: usize, op: usize, val: usize) -> isize {
@@ -126,3 +131,44 @@ pub fn sys_set_tid_address(tidptr: usize) -> isize {
     }
     kfn::scheduler::kset_clear_on_exit_addr(tidptr)
 }
+
+pub fn sys_prctl(option: usize, arg2: usize, _arg3: usize, _arg4: usize, _arg5: usize) -> isize {
+    use foundation::ops::THREAD_NAME_LEN;
+
+    match option as i32 {
+        libc::PR_SET_NAME => {
+            if arg2 == 0 {
+                return -(libc::EFAULT as isize);
+            }
+            // The name is NUL-terminated; Linux silently truncates to TASK_COMM_LEN - 1.
+            let mut name = [0u8; THREAD_NAME_LEN];
+            let src = arg2 as *const u8;
+            for (i, b) in name.iter_mut().take(THREAD_NAME_LEN - 1).enumerate() {
+                *b = unsafe { src.add(i).read() };
+                if *b == 0 {
+                    break;
+                }
+            }
+            kfn::scheduler::kset_thread_name(&name);
+            0
+        }
+        libc::PR_GET_NAME => {
+            if arg2 == 0 {
+                return -(libc::EFAULT as isize);
+            }
+            let tid = kfn::scheduler::kcurrent_tid();
+            let Some(info) = kfn::scheduler::kthread_by_tid(tid) else {
+                return -(libc::ESRCH as isize);
+            };
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    info.name.as_ptr(),
+                    arg2 as *mut u8,
+                    THREAD_NAME_LEN,
+                );
+            }
+            0
+        }
+        _ => -(libc::EINVAL as isize),
+    }
+}
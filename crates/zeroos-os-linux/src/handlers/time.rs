@@ -0,0 +1,54 @@
+//! Clocks, all backed by the deterministic kernel clock (`kfn::time`).
+//!
+//! Every clock counts from boot, so `CLOCK_REALTIME` starts at the epoch and the CPU-time clocks
+//! match the monotonic one. Readings go through the record/replay journal with the clock itself.
+
+use foundation::kfn;
+use libc;
+
+const NSEC_PER_SEC: u64 = 1_000_000_000;
+
+fn known_clock(clockid: usize) -> bool {
+    matches!(
+        clockid as libc::clockid_t,
+        libc::CLOCK_REALTIME
+            | libc::CLOCK_MONOTONIC
+            | libc::CLOCK_PROCESS_CPUTIME_ID
+            | libc::CLOCK_THREAD_CPUTIME_ID
+            | libc::CLOCK_MONOTONIC_RAW
+            | libc::CLOCK_REALTIME_COARSE
+            | libc::CLOCK_MONOTONIC_COARSE
+            | libc::CLOCK_BOOTTIME
+    )
+}
+
+fn write_timespec(tp: usize, ns: u64) {
+    let ts = libc::timespec {
+        tv_sec: (ns / NSEC_PER_SEC) as _,
+        tv_nsec: (ns % NSEC_PER_SEC) as _,
+    };
+    unsafe { (tp as *mut libc::timespec).write_unaligned(ts) };
+}
+
+/// Handle clock_gettime syscall
+pub fn sys_clock_gettime(clockid: usize, tp: usize) -> isize {
+    if !known_clock(clockid) {
+        return -(libc::EINVAL as isize);
+    }
+    if tp == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    write_timespec(tp, kfn::time::ktime_ns());
+    0
+}
+
+/// Handle clock_getres syscall
+pub fn sys_clock_getres(clockid: usize, res: usize) -> isize {
+    if !known_clock(clockid) {
+        return -(libc::EINVAL as isize);
+    }
+    if res != 0 {
+        write_timespec(res, kfn::time::KTIME_NS_PER_INSTRET);
+    }
+    0
+}
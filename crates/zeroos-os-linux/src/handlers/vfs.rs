@@ -1,11 +1,13 @@
 use foundation::kfn;
 use libc;
 
-pub fn sys_openat(_dirfd: usize, path: usize, flags: usize, mode: usize) -> isize {
+use super::signal::sigpipe_on_epipe;
+
+pub fn sys_openat(dirfd: usize, path: usize, flags: usize, mode: usize) -> isize {
     if path == 0 {
         return -(libc::EFAULT as isize);
     }
-    unsafe { kfn::vfs::kopen(path as *const u8, flags as i32, mode as u32) }
+    unsafe { kfn::vfs::kopenat(dirfd as i32, path as *const u8, flags as i32, mode as u32) }
 }
 
 pub fn sys_close(fd: usize) -> isize {
@@ -19,7 +21,23 @@ pub fn sys_read(fd: usize, buf: usize, count: usize) -> isize {
     if buf == 0 {
         return -(libc::EFAULT as isize);
     }
-    kfn::vfs::kread(fd as i32, buf as *mut u8, count)
+    journaled_read(fd as i32, buf as *mut u8, count)
+}
+
+/// Read from `fd` through the record/replay journal.
+///
+/// When replaying, stdin is not touched: the recorded result and bytes are returned instead.
+#[inline(always)]
+fn journaled_read(fd: i32, buf: *mut u8, count: usize) -> isize {
+    super::journaled_input(buf, count, fd != 0, || kfn::vfs::kread(fd, buf, count))
+}
+
+/// [`journaled_read`] at `offset`, leaving the file offset alone.
+#[inline(always)]
+fn journaled_pread(fd: i32, buf: *mut u8, count: usize, offset: isize) -> isize {
+    super::journaled_input(buf, count, true, || {
+        kfn::vfs::kpread(fd, buf, count, offset)
+    })
 }
 
 pub fn sys_write(fd: usize, buf: usize, count: usize) -> isize {
@@ -29,7 +47,7 @@ pub fn sys_write(fd: usize, buf: usize, count: usize) -> isize {
     if buf == 0 {
         return -(libc::EFAULT as isize);
     }
-    kfn::vfs::kwrite(fd as i32, buf as *const u8, count)
+    sigpipe_on_epipe(kfn::vfs::kwrite(fd as i32, buf as *const u8, count))
 }
 
 #[repr(C)]
@@ -38,20 +56,26 @@ struct IoVec {
     iov_len: usize,
 }
 
-pub fn sys_readv(fd: usize, iov: usize, iovcnt: usize) -> isize {
+fn iovecs<'a>(iov: usize, iovcnt: usize) -> Result<&'a [IoVec], isize> {
     if iovcnt == 0 {
-        return -(libc::EINVAL as isize);
+        return Err(-(libc::EINVAL as isize));
     }
     if iov == 0 {
-        return -(libc::EFAULT as isize);
+        return Err(-(libc::EFAULT as isize));
     }
     if iovcnt > (libc::UIO_MAXIOV as usize) {
-        return -(libc::EINVAL as isize);
+        return Err(-(libc::EINVAL as isize));
     }
     if !iov.is_multiple_of(core::mem::align_of::<IoVec>()) {
-        return -(libc::EINVAL as isize);
+        return Err(-(libc::EINVAL as isize));
     }
-    let iovecs = unsafe { core::slice::from_raw_parts(iov as *const IoVec, iovcnt) };
+    Ok(unsafe { core::slice::from_raw_parts(iov as *const IoVec, iovcnt) })
+}
+
+/// Run `op` over each buffer in turn, stopping at the first error or short transfer.
+///
+/// `op` gets the buffer and the bytes transferred so far; partial progress wins over an error.
+fn for_each_iovec(iovecs: &[IoVec], mut op: impl FnMut(&IoVec, usize) -> isize) -> isize {
     let mut total = 0isize;
     for v in iovecs {
         if v.iov_len == 0 {
@@ -64,7 +88,7 @@ pub fn sys_readv(fd: usize, iov: usize, iovcnt: usize) -> isize {
                 -(libc::EFAULT as isize)
             };
         }
-        let r = kfn::vfs::kread(fd as i32, v.iov_base, v.iov_len);
+        let r = op(v, total as usize);
         if r < 0 {
             return if total > 0 { total } else { r };
         }
@@ -76,42 +100,134 @@ pub fn sys_readv(fd: usize, iov: usize, iovcnt: usize) -> isize {
     total
 }
 
+pub fn sys_readv(fd: usize, iov: usize, iovcnt: usize) -> isize {
+    let iovecs = match iovecs(iov, iovcnt) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+    for_each_iovec(iovecs, |v, _| {
+        journaled_read(fd as i32, v.iov_base, v.iov_len)
+    })
+}
+
 pub fn sys_writev(fd: usize, iov: usize, iovcnt: usize) -> isize {
-    if iovcnt == 0 {
-        return -(libc::EINVAL as isize);
+    let iovecs = match iovecs(iov, iovcnt) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+    sigpipe_on_epipe(for_each_iovec(iovecs, |v, _| {
+        kfn::vfs::kwrite(fd as i32, v.iov_base as *const u8, v.iov_len)
+    }))
+}
+
+pub fn sys_pread64(fd: usize, buf: usize, count: usize, offset: usize) -> isize {
+    if count == 0 {
+        return 0;
     }
-    if iov == 0 {
+    if buf == 0 {
         return -(libc::EFAULT as isize);
     }
-    if iovcnt > (libc::UIO_MAXIOV as usize) {
-        return -(libc::EINVAL as isize);
+    journaled_pread(fd as i32, buf as *mut u8, count, offset as isize)
+}
+
+pub fn sys_pwrite64(fd: usize, buf: usize, count: usize, offset: usize) -> isize {
+    if count == 0 {
+        return 0;
     }
-    if !iov.is_multiple_of(core::mem::align_of::<IoVec>()) {
-        return -(libc::EINVAL as isize);
+    if buf == 0 {
+        return -(libc::EFAULT as isize);
     }
-    let iovecs = unsafe { core::slice::from_raw_parts(iov as *const IoVec, iovcnt) };
-    let mut total = 0isize;
-    for v in iovecs {
-        if v.iov_len == 0 {
-            continue;
-        }
-        if v.iov_base.is_null() {
-            return if total > 0 {
-                total
-            } else {
-                -(libc::EFAULT as isize)
-            };
-        }
-        let r = kfn::vfs::kwrite(fd as i32, v.iov_base as *const u8, v.iov_len);
-        if r < 0 {
-            return if total > 0 { total } else { r };
-        }
-        total += r;
-        if (r as usize) < v.iov_len {
-            break;
-        }
+    kfn::vfs::kpwrite(fd as i32, buf as *const u8, count, offset as isize)
+}
+
+/// Handle preadv syscall
+///
+/// The offset arrives split in two registers; on 64-bit targets `pos_l` holds all of it.
+pub fn sys_preadv(fd: usize, iov: usize, iovcnt: usize, pos_l: usize, _pos_h: usize) -> isize {
+    let iovecs = match iovecs(iov, iovcnt) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+    let offset = pos_l as isize;
+    for_each_iovec(iovecs, |v, done| {
+        journaled_pread(fd as i32, v.iov_base, v.iov_len, offset + done as isize)
+    })
+}
+
+/// Handle pwritev syscall (offset split as for `preadv`)
+pub fn sys_pwritev(fd: usize, iov: usize, iovcnt: usize, pos_l: usize, _pos_h: usize) -> isize {
+    let iovecs = match iovecs(iov, iovcnt) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+    let offset = pos_l as isize;
+    for_each_iovec(iovecs, |v, done| {
+        kfn::vfs::kpwrite(
+            fd as i32,
+            v.iov_base as *const u8,
+            v.iov_len,
+            offset + done as isize,
+        )
+    })
+}
+
+/// Handle sendfile syscall
+///
+/// Data moves through a kernel buffer; a non-null `offset` is used instead of (and does not
+/// move) `in_fd`'s position, and is updated with the bytes sent.
+pub fn sys_sendfile(out_fd: usize, in_fd: usize, offset: usize, count: usize) -> isize {
+    sigpipe_on_epipe(unsafe {
+        kfn::vfs::ksendfile(out_fd as i32, in_fd as i32, offset as *mut isize, count)
+    })
+}
+
+/// Handle copy_file_range syscall
+pub fn sys_copy_file_range(
+    fd_in: usize,
+    off_in: usize,
+    fd_out: usize,
+    off_out: usize,
+    len: usize,
+    flags: usize,
+) -> isize {
+    unsafe {
+        kfn::vfs::kcopy_file_range(
+            fd_in as i32,
+            off_in as *mut isize,
+            fd_out as i32,
+            off_out as *mut isize,
+            len,
+            flags as u32,
+        )
     }
-    total
+}
+
+/// Handle pipe2 syscall
+pub fn sys_pipe2(fds: usize, flags: usize) -> isize {
+    if fds == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let mut pair = [0i32; 2];
+    let r = kfn::vfs::kpipe2(flags as i32, &mut pair);
+    if r == 0 {
+        unsafe { (fds as *mut [i32; 2]).write_unaligned(pair) };
+    }
+    r
+}
+
+/// Handle memfd_create syscall
+pub fn sys_memfd_create(name: usize, flags: usize) -> isize {
+    unsafe { kfn::vfs::kmemfd_create(name as *const u8, flags as u32) }
+}
+
+/// Handle ftruncate syscall
+pub fn sys_ftruncate(fd: usize, length: usize) -> isize {
+    kfn::vfs::kftruncate(fd as i32, length as isize)
+}
+
+/// Handle eventfd2 syscall
+pub fn sys_eventfd2(initval: usize, flags: usize) -> isize {
+    kfn::vfs::keventfd2(initval as u32, flags as i32)
 }
 
 pub fn sys_lseek(fd: usize, offset: usize, whence: usize) -> isize {
@@ -128,3 +244,120 @@ pub fn sys_fstat(fd: usize, statbuf: usize) -> isize {
     }
     kfn::vfs::kfstat(fd as i32, statbuf as *mut u8)
 }
+
+pub fn sys_newfstatat(dirfd: usize, path: usize, statbuf: usize, flags: usize) -> isize {
+    if path == 0 || statbuf == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    unsafe {
+        kfn::vfs::kfstatat(
+            dirfd as i32,
+            path as *const u8,
+            statbuf as *mut u8,
+            flags as i32,
+        )
+    }
+}
+
+/// Handle faccessat syscall
+///
+/// The process runs as root, so any existing node is accessible, except that execute access
+/// needs at least one `x` bit, as for root on Linux.
+pub fn sys_faccessat(dirfd: usize, path: usize, mode: usize) -> isize {
+    if path == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    if mode & !((libc::R_OK | libc::W_OK | libc::X_OK) as usize) != 0 {
+        return -(libc::EINVAL as isize);
+    }
+    let mut st: libc::stat = unsafe { core::mem::zeroed() };
+    let r = unsafe {
+        kfn::vfs::kfstatat(
+            dirfd as i32,
+            path as *const u8,
+            &mut st as *mut libc::stat as *mut u8,
+            0,
+        )
+    };
+    if r < 0 {
+        return r;
+    }
+    if mode & libc::X_OK as usize != 0
+        && st.st_mode & libc::S_IFMT != libc::S_IFDIR
+        && st.st_mode & 0o111 == 0
+    {
+        return -(libc::EACCES as isize);
+    }
+    0
+}
+
+pub fn sys_getdents64(fd: usize, dirp: usize, count: usize) -> isize {
+    if dirp == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    kfn::vfs::kgetdents64(fd as i32, dirp as *mut u8, count)
+}
+
+/// Handle readlinkat syscall
+pub fn sys_readlinkat(dirfd: usize, path: usize, buf: usize, size: usize) -> isize {
+    if path == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    unsafe { kfn::vfs::kreadlinkat(dirfd as i32, path as *const u8, buf as *mut u8, size) }
+}
+
+pub fn sys_mkdirat(dirfd: usize, path: usize, mode: usize) -> isize {
+    if path == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    unsafe { kfn::vfs::kmkdirat(dirfd as i32, path as *const u8, mode as u32) }
+}
+
+pub fn sys_unlinkat(dirfd: usize, path: usize, flags: usize) -> isize {
+    if path == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    unsafe { kfn::vfs::kunlinkat(dirfd as i32, path as *const u8, flags as i32) }
+}
+
+pub fn sys_renameat2(
+    olddirfd: usize,
+    oldpath: usize,
+    newdirfd: usize,
+    newpath: usize,
+    flags: usize,
+) -> isize {
+    if oldpath == 0 || newpath == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    unsafe {
+        kfn::vfs::krenameat(
+            olddirfd as i32,
+            oldpath as *const u8,
+            newdirfd as i32,
+            newpath as *const u8,
+            flags as u32,
+        )
+    }
+}
+
+/// Handle getcwd syscall
+///
+/// Like the raw Linux syscall, returns the length of the path including its NUL.
+pub fn sys_getcwd(buf: usize, size: usize) -> isize {
+    if buf == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    kfn::vfs::kgetcwd(buf as *mut u8, size)
+}
+
+pub fn sys_chdir(path: usize) -> isize {
+    if path == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    unsafe { kfn::vfs::kchdir(path as *const u8) }
+}
+
+pub fn sys_fchdir(fd: usize) -> isize {
+    kfn::vfs::kfchdir(fd as i32)
+}
@@ -1,4 +1,6 @@
 #![no_std]
+// `sys_registry!` recurses once per syscall entry.
+#![recursion_limit = "256"]
 pub mod handlers;
 pub mod syscall;
 
@@ -11,6 +11,9 @@ use crate::handlers;
 /// We pick a conservative bound to keep the table simple while staying small (~8 KiB on riscv64).
 const NR_SYSCALLS: usize = 1024;
 
+/// `__NR_riscv_hwprobe` (not exported by `libc`).
+const SYS_riscv_hwprobe: c_long = 258;
+
 type SysHandler = fn(a0: usize, a1: usize, a2: usize, a3: usize, a4: usize, a5: usize) -> isize;
 
 #[inline(always)]
@@ -25,6 +28,36 @@ fn sys_unsupported_handler(
     handlers::sys_unsupported()
 }
 
+/// Note `ret` of syscall `nr` for crash reports if it failed as unsupported.
+///
+/// With `strict-syscalls`, an unsupported syscall ends the run instead (exit status
+/// `128 + SIGSYS`, as when seccomp kills a process).
+#[inline(always)]
+fn check_unsupported(nr: usize, ret: isize, regs: *const u8) -> isize {
+    if ret == -(ENOSYS as isize) {
+        unsupported(nr, regs);
+    }
+    ret
+}
+
+#[cold]
+fn unsupported(nr: usize, regs: *const u8) {
+    let name = syscall_name(nr);
+    foundation::kfn::crash::knote_unsupported_syscall(nr, name);
+    #[cfg(feature = "strict-syscalls")]
+    {
+        use foundation::kfn::crash::{kcrash, CrashCause, CrashReport};
+        kcrash(&CrashReport {
+            cause: CrashCause::UnsupportedSyscall,
+            message: format_args!("{name} (nr {nr}) is not implemented"),
+            exit_code: 128 + SIGSYS,
+            regs,
+        });
+    }
+    #[cfg(not(feature = "strict-syscalls"))]
+    let _ = regs;
+}
+
 macro_rules! sys_registry {
     (@call 0, $handler:path, $_a0:ident, $_a1:ident, $_a2:ident, $_a3:ident, $_a4:ident, $_a5:ident) => { $handler() };
     (@call 1, $handler:path, $_a0:ident, $_a1:ident, $_a2:ident, $_a3:ident, $_a4:ident, $_a5:ident) => { $handler($_a0) };
@@ -141,8 +174,34 @@ sys_registry! {
     (SYS_exit_group, handlers::sys_exit_group, 1),
     (SYS_rt_sigaction, handlers::signal::sys_rt_sigaction, 4),
     (SYS_rt_sigprocmask, handlers::signal::sys_rt_sigprocmask, 4),
+    (SYS_rt_sigpending, handlers::signal::sys_rt_sigpending, 2),
+    (SYS_rt_sigreturn, handlers::signal::sys_rt_sigreturn, 0),
+    (SYS_sigaltstack, handlers::signal::sys_sigaltstack, 2),
     (SYS_tkill, handlers::signal::sys_tkill, 2),
     (SYS_tgkill, handlers::signal::sys_tgkill, 3),
+    (SYS_kill, handlers::signal::sys_kill, 2),
+
+    // Process identity and system information.
+    (SYS_uname, handlers::sysinfo::sys_uname, 1),
+    (SYS_getuid, handlers::sysinfo::sys_getuid, 0),
+    (SYS_geteuid, handlers::sysinfo::sys_geteuid, 0),
+    (SYS_getgid, handlers::sysinfo::sys_getgid, 0),
+    (SYS_getegid, handlers::sysinfo::sys_getegid, 0),
+    (SYS_getppid, handlers::sysinfo::sys_getppid, 0),
+    (SYS_sysinfo, handlers::sysinfo::sys_sysinfo, 1),
+    (SYS_getrlimit, handlers::sysinfo::sys_getrlimit, 2),
+    (SYS_setrlimit, handlers::sysinfo::sys_setrlimit, 2),
+    (SYS_prlimit64, handlers::sysinfo::sys_prlimit64, 4),
+
+    // Clocks.
+    (SYS_clock_gettime, handlers::time::sys_clock_gettime, 2),
+    (SYS_clock_getres, handlers::time::sys_clock_getres, 2),
+
+    // CPU topology.
+    (SYS_sched_getaffinity, handlers::cpu::sys_sched_getaffinity, 3),
+    (SYS_sched_setaffinity, handlers::cpu::sys_sched_setaffinity, 3),
+    (SYS_getcpu, handlers::cpu::sys_getcpu, 2),
+    (SYS_riscv_hwprobe, handlers::cpu::sys_riscv_hwprobe, 5),
 
     // Scheduler/sys-thread syscalls.
     #[cfg(feature = "scheduler")]
@@ -153,6 +212,7 @@ sys_registry! {
         (SYS_getpid, handlers::thread::sys_getpid, 0),
         (SYS_gettid, handlers::thread::sys_gettid, 0),
         (SYS_set_tid_address, handlers::thread::sys_set_tid_address, 1),
+        (SYS_prctl, handlers::thread::sys_prctl, 5),
     }
 
     // Memory syscalls.
@@ -173,9 +233,49 @@ sys_registry! {
         (SYS_write, handlers::vfs::sys_write, 3),
         (SYS_readv, handlers::vfs::sys_readv, 3),
         (SYS_writev, handlers::vfs::sys_writev, 3),
+        (SYS_pread64, handlers::vfs::sys_pread64, 4),
+        (SYS_pwrite64, handlers::vfs::sys_pwrite64, 4),
+        (SYS_preadv, handlers::vfs::sys_preadv, 5),
+        (SYS_pwritev, handlers::vfs::sys_pwritev, 5),
+        (SYS_sendfile, handlers::vfs::sys_sendfile, 4),
+        (SYS_copy_file_range, handlers::vfs::sys_copy_file_range, 6),
         (SYS_lseek, handlers::vfs::sys_lseek, 3),
         (SYS_ioctl, handlers::vfs::sys_ioctl, 3),
         (SYS_fstat, handlers::vfs::sys_fstat, 2),
+        (SYS_newfstatat, handlers::vfs::sys_newfstatat, 4),
+        (SYS_faccessat, handlers::vfs::sys_faccessat, 3),
+        (SYS_readlinkat, handlers::vfs::sys_readlinkat, 4),
+        (SYS_getdents64, handlers::vfs::sys_getdents64, 3),
+        (SYS_mkdirat, handlers::vfs::sys_mkdirat, 3),
+        (SYS_unlinkat, handlers::vfs::sys_unlinkat, 3),
+        (SYS_renameat2, handlers::vfs::sys_renameat2, 5),
+        (SYS_getcwd, handlers::vfs::sys_getcwd, 2),
+        (SYS_chdir, handlers::vfs::sys_chdir, 1),
+        (SYS_fchdir, handlers::vfs::sys_fchdir, 1),
+        (SYS_pipe2, handlers::vfs::sys_pipe2, 2),
+        (SYS_ppoll, handlers::poll::sys_ppoll, 5),
+        (SYS_pselect6, handlers::poll::sys_pselect6, 6),
+        (SYS_epoll_create1, handlers::poll::sys_epoll_create1, 1),
+        (SYS_epoll_ctl, handlers::poll::sys_epoll_ctl, 4),
+        (SYS_epoll_pwait, handlers::poll::sys_epoll_pwait, 6),
+        (SYS_epoll_pwait2, handlers::poll::sys_epoll_pwait2, 6),
+        (SYS_eventfd2, handlers::vfs::sys_eventfd2, 2),
+        (SYS_memfd_create, handlers::vfs::sys_memfd_create, 2),
+        (SYS_ftruncate, handlers::vfs::sys_ftruncate, 2),
+        (SYS_socket, handlers::socket::sys_socket, 3),
+        (SYS_socketpair, handlers::socket::sys_socketpair, 4),
+        (SYS_bind, handlers::socket::sys_bind, 3),
+        (SYS_listen, handlers::socket::sys_listen, 2),
+        (SYS_accept, handlers::socket::sys_accept, 3),
+        (SYS_accept4, handlers::socket::sys_accept4, 4),
+        (SYS_connect, handlers::socket::sys_connect, 3),
+        (SYS_sendto, handlers::socket::sys_sendto, 6),
+        (SYS_recvfrom, handlers::socket::sys_recvfrom, 6),
+        (SYS_shutdown, handlers::socket::sys_shutdown, 2),
+        (SYS_getsockname, handlers::socket::sys_getsockname, 3),
+        (SYS_getpeername, handlers::socket::sys_getpeername, 3),
+        (SYS_getsockopt, handlers::socket::sys_getsockopt, 5),
+        (SYS_setsockopt, handlers::socket::sys_setsockopt, 5),
     }
 
     // Random syscalls.
@@ -418,6 +518,7 @@ pub fn syscall_name(nr: usize) -> &'static str {
         SYS_syslog => "SYS_syslog",
         SYS_getrandom => "SYS_getrandom",
         SYS_getcpu => "SYS_getcpu",
+        SYS_riscv_hwprobe => "SYS_riscv_hwprobe",
 
         // Capabilities
         SYS_capget => "SYS_capget",
@@ -584,12 +685,19 @@ pub unsafe fn dispatch_syscall<Frame: SyscallFrame>(regs: *mut Frame) {
     let a4 = regs_ref.arg(4);
     let a5 = regs_ref.arg(5);
 
+    #[cfg(feature = "scheduler")]
+    foundation::kfn::scheduler::kaccount_syscall();
+
     let ret = if nr < NR_SYSCALLS {
         (HANDLERS[nr])(a0, a1, a2, a3, a4, a5)
     } else {
         sys_unsupported_handler(a0, a1, a2, a3, a4, a5)
     };
-    unsafe { (*regs).set_ret(ret) }
+    let ret = check_unsupported(nr, ret, regs as *const u8);
+    unsafe {
+        (*regs).set_ret(ret);
+        handlers::signal::return_to_user(regs as *mut u8);
+    }
 }
 
 pub fn linux_handle(
@@ -601,15 +709,33 @@ pub fn linux_handle(
     a5: usize,
     nr: usize,
 ) -> isize {
-    if nr < NR_SYSCALLS {
+    #[cfg(feature = "scheduler")]
+    foundation::kfn::scheduler::kaccount_syscall();
+
+    let ret = if nr < NR_SYSCALLS {
         (HANDLERS[nr])(a0, a1, a2, a3, a4, a5)
     } else {
         sys_unsupported_handler(a0, a1, a2, a3, a4, a5)
+    };
+    check_unsupported(nr, ret, current_regs())
+}
+
+/// Trap frame of the syscall being handled, if the kernel can find it.
+#[inline(always)]
+fn current_regs() -> *const u8 {
+    #[cfg(feature = "scheduler")]
+    {
+        foundation::kfn::arch::kcurrent_trap_frame()
+    }
+    #[cfg(not(feature = "scheduler"))]
+    {
+        core::ptr::null()
     }
 }
 
 pub const TRAP_OPS: foundation::ops::TrapOps = foundation::ops::TrapOps {
     syscall: linux_handle,
-    exception: |_, _, _| None,
+    exception: handlers::signal::exception,
     interrupt: |_| {},
+    return_to_user: handlers::signal::return_to_user,
 };
@@ -1,4 +1,4 @@
-use spin::Mutex;
+use foundation::utils::KernelLock;
 
 #[repr(C)]
 pub struct ChaChaState {
@@ -104,7 +104,7 @@ impl ChaChaState {
     }
 }
 
-static GLOBAL_RNG: Mutex<ChaChaState> = Mutex::new(ChaChaState::new());
+static GLOBAL_RNG: KernelLock<ChaChaState> = KernelLock::new(ChaChaState::new());
 
 /// # Safety
 /// - `buf` must be non-null and valid for writes of `len` bytes.
@@ -114,14 +114,12 @@ pub unsafe fn fill_bytes(buf: *mut u8, len: usize) -> isize {
         return -1;
     }
 
-    let mut rng = GLOBAL_RNG.lock();
     let slice = core::slice::from_raw_parts_mut(buf, len);
-    rng.fill_bytes(slice);
+    GLOBAL_RNG.with_mut(|rng| rng.fill_bytes(slice));
 
     len as isize
 }
 
 pub fn init(seed: u64) {
-    let mut rng = GLOBAL_RNG.lock();
-    *rng = ChaChaState::with_seed(seed);
+    GLOBAL_RNG.with_mut(|rng| *rng = ChaChaState::with_seed(seed));
 }
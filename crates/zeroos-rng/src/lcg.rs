@@ -1,4 +1,4 @@
-use spin::Mutex;
+use foundation::utils::KernelLock;
 
 #[repr(C)]
 pub struct LcgState {
@@ -46,7 +46,7 @@ impl LcgState {
     }
 }
 
-static GLOBAL_RNG: Mutex<LcgState> = Mutex::new(LcgState::new());
+static GLOBAL_RNG: KernelLock<LcgState> = KernelLock::new(LcgState::new());
 
 /// # Safety
 /// - `buf` must be non-null and valid for writes of `len` bytes.
@@ -56,14 +56,12 @@ pub unsafe fn fill_bytes(buf: *mut u8, len: usize) -> isize {
         return -1;
     }
 
-    let mut rng = GLOBAL_RNG.lock();
     let slice = core::slice::from_raw_parts_mut(buf, len);
-    rng.fill_bytes(slice);
+    GLOBAL_RNG.with_mut(|rng| rng.fill_bytes(slice));
 
     len as isize
 }
 
 pub fn init(seed: u64) {
-    let mut rng = GLOBAL_RNG.lock();
-    *rng = LcgState::with_seed(seed);
+    GLOBAL_RNG.with_mut(|rng| *rng = LcgState::with_seed(seed));
 }
@@ -0,0 +1,445 @@
+//! Minimal single-threaded async executor.
+//!
+//! [`block_on`] drives a future to completion on the calling thread, together with every task
+//! started through [`spawn_local`]. Only woken tasks are re-polled.
+//!
+//! When every task is pending, the executor parks until something wakes it:
+//! - with the `thread` feature: it blocks on a kernel wait queue, so wakers fired from other
+//!   threads resume it, with the earliest timer deadline (if any) as its timeout;
+//! - otherwise, with pending timers: it spins the kernel clock up to the earliest deadline;
+//! - otherwise: nothing can wake it from outside, so it busy-polls every task.
+
+extern crate alloc as alloc_crate;
+
+use alloc_crate::boxed::Box;
+use alloc_crate::collections::VecDeque;
+use alloc_crate::sync::Arc;
+use alloc_crate::task::Wake;
+use alloc_crate::vec::Vec;
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+
+use foundation::kfn::time::ktime_ns;
+
+type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+/// Bumped on every wake so a parked executor can tell it missed nothing.
+static NOTIFY: AtomicU32 = AtomicU32::new(0);
+
+struct TaskWaker {
+    woken: AtomicBool,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+        NOTIFY.fetch_add(1, Ordering::Release);
+        #[cfg(feature = "thread")]
+        foundation::kfn::scheduler::kwake_on_addr(NOTIFY.as_ptr() as usize, 1);
+    }
+}
+
+struct Task {
+    future: LocalFuture,
+    flag: Arc<TaskWaker>,
+    waker: Waker,
+}
+
+impl Task {
+    fn new(future: LocalFuture) -> Self {
+        let flag = Arc::new(TaskWaker {
+            // New tasks are polled at least once.
+            woken: AtomicBool::new(true),
+        });
+        let waker = Waker::from(flag.clone());
+        Self {
+            future,
+            flag,
+            waker,
+        }
+    }
+}
+
+struct Timer {
+    id: u64,
+    deadline: u64,
+    waker: Waker,
+}
+
+struct Executor {
+    tasks: Vec<Task>,
+    spawned: VecDeque<LocalFuture>,
+    timers: Vec<Timer>,
+    next_timer: u64,
+}
+
+/// Executor state, shared by the kernel threads of the single hart.
+///
+/// Tasks hold `!Send` futures, so this cannot be a `KernelLock`. Kernel threads only switch at
+/// yields and waits, never inside [`with_executor`]; `busy` turns an overlapping access (a
+/// re-entrant call, or a second hart) into a panic instead of a data race.
+struct ExecutorCell {
+    busy: AtomicBool,
+    executor: UnsafeCell<Executor>,
+}
+
+// SAFETY: accesses never overlap (checked by `busy`), and all threads run on one hart.
+unsafe impl Sync for ExecutorCell {}
+
+static EXECUTOR: ExecutorCell = ExecutorCell {
+    busy: AtomicBool::new(false),
+    executor: UnsafeCell::new(Executor {
+        tasks: Vec::new(),
+        spawned: VecDeque::new(),
+        timers: Vec::new(),
+        next_timer: 0,
+    }),
+};
+
+fn with_executor<R>(f: impl FnOnce(&mut Executor) -> R) -> R {
+    if EXECUTOR.busy.swap(true, Ordering::Acquire) {
+        panic!("executor accessed re-entrantly or from two harts");
+    }
+    let ret = f(unsafe { &mut *EXECUTOR.executor.get() });
+    EXECUTOR.busy.store(false, Ordering::Release);
+    ret
+}
+
+/// Start `future` as a background task of the running executor.
+///
+/// The task makes progress only while some [`block_on`] call is running; tasks still pending
+/// when that call returns are kept for the next one.
+pub fn spawn_local<F>(future: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    with_executor(|e| e.spawned.push_back(Box::pin(future)));
+}
+
+/// Run `future` (and all spawned tasks) until `future` completes.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = core::pin::pin!(future);
+    let main = Arc::new(TaskWaker {
+        woken: AtomicBool::new(true),
+    });
+    let main_waker = Waker::from(main.clone());
+
+    loop {
+        let seq = NOTIFY.load(Ordering::Acquire);
+
+        fire_timers();
+
+        if main.woken.swap(false, Ordering::AcqRel) {
+            if let Poll::Ready(out) = future.as_mut().poll(&mut Context::from_waker(&main_waker)) {
+                return out;
+            }
+        }
+
+        let progressed = poll_tasks();
+        if progressed || main.woken.load(Ordering::Acquire) {
+            continue;
+        }
+
+        park(seq, &main);
+    }
+}
+
+/// Poll every woken task once; returns whether any task was polled.
+fn poll_tasks() -> bool {
+    // Tasks are taken out of the executor while polled so they can spawn/register timers.
+    let mut tasks = with_executor(|e| {
+        let mut tasks = core::mem::take(&mut e.tasks);
+        tasks.extend(e.spawned.drain(..).map(Task::new));
+        tasks
+    });
+
+    let mut progressed = false;
+    tasks.retain_mut(|task| {
+        if !task.flag.woken.swap(false, Ordering::AcqRel) {
+            return true;
+        }
+        progressed = true;
+        let mut cx = Context::from_waker(&task.waker);
+        task.future.as_mut().poll(&mut cx).is_pending()
+    });
+
+    with_executor(|e| {
+        tasks.append(&mut e.tasks);
+        e.tasks = tasks;
+    });
+    progressed
+}
+
+fn fire_timers() {
+    let now = ktime_ns();
+    let due: Vec<Waker> = with_executor(|e| {
+        let mut due = Vec::new();
+        e.timers.retain(|t| {
+            if t.deadline <= now {
+                due.push(t.waker.clone());
+                false
+            } else {
+                true
+            }
+        });
+        due
+    });
+    for waker in due {
+        waker.wake();
+    }
+}
+
+/// Wait until something may have changed since `seq` was observed.
+fn park(seq: u32, main: &Arc<TaskWaker>) {
+    let next_deadline = with_executor(|e| e.timers.iter().map(|t| t.deadline).min());
+
+    #[cfg(feature = "thread")]
+    {
+        if NOTIFY.load(Ordering::Acquire) != seq {
+            return;
+        }
+        let addr = NOTIFY.as_ptr() as usize;
+        if let Some(deadline) = next_deadline {
+            // Timing out just means the next timer is due.
+            foundation::kfn::scheduler::kwait_on_addr_until(addr, seq as i32, deadline);
+        } else {
+            let ret = foundation::kfn::scheduler::kwait_on_addr(addr, seq as i32);
+            // No other thread can ever wake us, so fall back to busy-polling.
+            if ret == -(foundation::errno::EDEADLK as isize) {
+                busy_poll(main);
+            }
+        }
+    }
+    #[cfg(not(feature = "thread"))]
+    {
+        let _ = seq;
+        match next_deadline {
+            Some(deadline) => foundation::kfn::time::kspin_until(deadline),
+            None => busy_poll(main),
+        }
+    }
+}
+
+/// Mark every task as woken so the next round re-polls all of them.
+fn busy_poll(main: &Arc<TaskWaker>) {
+    main.woken.store(true, Ordering::Release);
+    with_executor(|e| {
+        for task in &e.tasks {
+            task.flag.woken.store(true, Ordering::Release);
+        }
+    });
+    core::hint::spin_loop();
+}
+
+/// Future returned by [`sleep`] and [`sleep_until`].
+///
+/// Its timer is removed once it completes or is dropped, so an abandoned sleep (such as the
+/// one inside a [`timeout`] whose future finished first) does not keep the executor spinning
+/// until the deadline.
+pub struct Sleep {
+    deadline: u64,
+    /// Id of the registered timer.
+    timer: Option<u64>,
+}
+
+impl Sleep {
+    /// Register the timer, or point it at `waker` if the task was polled with another one.
+    fn register(&mut self, waker: &Waker) {
+        let deadline = self.deadline;
+        let timer = &mut self.timer;
+        with_executor(|e| {
+            if let Some(t) = timer.and_then(|id| e.timers.iter_mut().find(|t| t.id == id)) {
+                if !t.waker.will_wake(waker) {
+                    t.waker = waker.clone();
+                }
+                return;
+            }
+            let id = e.next_timer;
+            e.next_timer += 1;
+            e.timers.push(Timer {
+                id,
+                deadline,
+                waker: waker.clone(),
+            });
+            *timer = Some(id);
+        });
+    }
+
+    fn cancel(&mut self) {
+        if let Some(id) = self.timer.take() {
+            with_executor(|e| e.timers.retain(|t| t.id != id));
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if ktime_ns() >= self.deadline {
+            self.cancel();
+            return Poll::Ready(());
+        }
+        self.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Complete once `duration` has elapsed on the kernel clock.
+pub fn sleep(duration: Duration) -> Sleep {
+    let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+    sleep_until(ktime_ns().saturating_add(nanos))
+}
+
+/// Complete once the kernel clock reaches `deadline_ns`.
+pub fn sleep_until(deadline_ns: u64) -> Sleep {
+    Sleep {
+        deadline: deadline_ns,
+        timer: None,
+    }
+}
+
+/// Error returned by [`timeout`] when the deadline passed first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed;
+
+/// Future returned by [`timeout`].
+pub struct Timeout<F> {
+    future: Pin<Box<F>>,
+    sleep: Sleep,
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(out) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(out));
+        }
+        match Pin::new(&mut self.sleep).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(Elapsed)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Run `future`, giving up with [`Elapsed`] after `duration`.
+pub fn timeout<F: Future>(duration: Duration, future: F) -> Timeout<F> {
+    Timeout {
+        future: Box::pin(future),
+        sleep: sleep(duration),
+    }
+}
+
+/// Yield once to the executor, letting other ready tasks run.
+pub async fn yield_now() {
+    let mut yielded = false;
+    core::future::poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use alloc_crate::rc::Rc;
+    use core::cell::RefCell;
+
+    /// The executor is a global; run one test on it at a time.
+    fn serial() -> std::sync::MutexGuard<'static, ()> {
+        static SERIAL: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        #[cfg(feature = "thread")]
+        crate::test_support::init();
+        SERIAL.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    #[test]
+    fn test_block_on_runs_spawned_chain() {
+        let _serial = serial();
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let outer = log.clone();
+        spawn_local(async move {
+            outer.borrow_mut().push(1);
+            let inner = outer.clone();
+            spawn_local(async move {
+                yield_now().await;
+                inner.borrow_mut().push(3);
+            });
+            outer.borrow_mut().push(2);
+        });
+        let done = log.clone();
+        let len = block_on(async move {
+            while done.borrow().len() < 3 {
+                yield_now().await;
+            }
+            done.borrow().len()
+        });
+        assert_eq!(len, 3);
+        assert_eq!(*log.borrow(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_sleeps_finish_in_deadline_order() {
+        let _serial = serial();
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        for micros in [30, 10, 20] {
+            let log = log.clone();
+            spawn_local(async move {
+                sleep(Duration::from_micros(micros)).await;
+                log.borrow_mut().push(micros);
+            });
+        }
+        let done = log.clone();
+        block_on(async move {
+            while done.borrow().len() < 3 {
+                sleep(Duration::from_micros(1)).await;
+            }
+        });
+        assert_eq!(*log.borrow(), [10, 20, 30]);
+        assert!(with_executor(|e| e.timers.is_empty()));
+    }
+
+    #[test]
+    fn test_timeout_expires_or_completes() {
+        let _serial = serial();
+
+        let pending = core::future::pending::<()>();
+        assert_eq!(
+            block_on(timeout(Duration::from_micros(5), pending)),
+            Err(Elapsed)
+        );
+
+        let ready = async {
+            yield_now().await;
+            7
+        };
+        assert_eq!(block_on(timeout(Duration::from_secs(60), ready)), Ok(7));
+        // The abandoned sleep took its timer with it.
+        assert!(with_executor(|e| e.timers.is_empty()));
+    }
+}
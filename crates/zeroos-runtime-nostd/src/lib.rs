@@ -11,6 +11,21 @@ cfg_if! {
     }
 }
 
+cfg_if! {
+    if #[cfg(feature = "thread")] {
+        pub mod thread;
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "executor")] {
+        pub mod executor;
+    }
+}
+
+#[cfg(all(test, feature = "thread"))]
+mod test_support;
+
 cfg_if! {
     if #[cfg(target_arch = "riscv64")] {
         pub mod riscv64;
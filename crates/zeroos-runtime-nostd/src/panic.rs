@@ -5,14 +5,17 @@
 //! - Sets platform-specific panic bits/flags
 //! - Calls platform exit with appropriate exit code
 //!
-//! ## Backtrace Support
+//! ## Crash Record
 //!
-//! When the `backtrace` feature is enabled, the panic handler will print
-//! stack frame addresses. See the [`crate::backtrace`] module for details.
+//! Before aborting, the handler prints a structured crash record (see
+//! `foundation::kfn::crash`), including stack frame addresses when a backtrace
+//! mode is enabled.
 
 use core::fmt::Write;
 use core::panic::PanicInfo;
 
+use foundation::kfn::crash::{CrashCause, CrashReport};
+
 use crate::io::StdoutWriter;
 
 /// Standard signal number for abort (SIGABRT)
@@ -45,7 +48,7 @@ extern "C" {
 ///
 /// This handler:
 /// 1. Prints panic information to stdout (via __platform_stdout_write)
-/// 2. Optionally prints stack backtrace (with `backtrace` feature)
+/// 2. Prints the crash record (with backtrace frames, unless backtraces are off)
 /// 3. Calls `__platform_abort()` to set panic state and exit
 ///
 /// The platform must provide both `__platform_stdout_write()` and `__platform_abort()`.
@@ -67,12 +70,12 @@ fn panic(info: &PanicInfo) -> ! {
         let _ = writeln!(w, "PANIC: panicked: {}", info.message());
     }
 
-    // Print stack backtrace (polymorphic: no-op if zeroos_backtrace="off")
-    // Safety: Called from panic handler with valid stack state
-    unsafe {
-        use crate::BacktraceCapture;
-        crate::Backtrace::print_backtrace();
-    }
+    foundation::kfn::crash::kcrash_report(&CrashReport {
+        cause: CrashCause::Panic,
+        message: format_args!("{}", info),
+        exit_code: 128 + SIGABRT,
+        regs: core::ptr::null(),
+    });
 
     // Call platform-specific abort handler with SIGABRT
     // This will exit with code 128 + 6 = 134
@@ -0,0 +1,98 @@
+//! Host stand-in for the kernel scheduler, so thread and executor tests run under `cargo test`.
+//!
+//! Kernel threads are std threads. Address waits re-check the value and yield instead of
+//! sleeping, which callers must already tolerate as spurious wakeups.
+
+extern crate std;
+
+use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::collections::HashSet;
+use std::sync::{Condvar, Mutex, Once};
+
+use foundation::errno::{EAGAIN, ETIMEDOUT};
+use foundation::kfn::time::ktime_ns;
+use foundation::ops::SchedulerOps;
+
+static NEXT_TID: AtomicUsize = AtomicUsize::new(2);
+static EXITED: Mutex<Option<HashSet<usize>>> = Mutex::new(None);
+static EXIT: Condvar = Condvar::new();
+
+std::thread_local! {
+    static TID: core::cell::Cell<usize> = const { core::cell::Cell::new(1) };
+}
+
+fn spawn_kernel_thread(entry: extern "C" fn(arg: usize) -> !, arg: usize) -> isize {
+    let tid = NEXT_TID.fetch_add(1, Ordering::Relaxed);
+    std::thread::spawn(move || {
+        TID.with(|t| t.set(tid));
+        entry(arg)
+    });
+    tid as isize
+}
+
+fn exit_current(_code: i32) -> isize {
+    let tid = TID.with(|t| t.get());
+    EXITED
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashSet::new)
+        .insert(tid);
+    EXIT.notify_all();
+    // A kernel thread never returns from its exit.
+    loop {
+        std::thread::park();
+    }
+}
+
+fn join_thread(tid: usize) -> isize {
+    let mut exited = EXITED.lock().unwrap();
+    while !exited.as_ref().is_some_and(|e| e.contains(&tid)) {
+        exited = EXIT.wait(exited).unwrap();
+    }
+    0
+}
+
+fn wait_on_addr(addr: usize, expected: i32) -> isize {
+    let value = unsafe { &*(addr as *const AtomicI32) };
+    if value.load(Ordering::Acquire) != expected {
+        return -(EAGAIN as isize);
+    }
+    std::thread::yield_now();
+    0
+}
+
+fn wait_on_addr_until(addr: usize, expected: i32, deadline_ns: u64) -> isize {
+    if ktime_ns() >= deadline_ns {
+        return -(ETIMEDOUT as isize);
+    }
+    wait_on_addr(addr, expected)
+}
+
+fn yield_now() -> isize {
+    std::thread::yield_now();
+    0
+}
+
+/// Install the host scheduler (once per test binary).
+pub(crate) fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        foundation::register_scheduler(SchedulerOps {
+            init: || 0,
+            spawn_thread: |_, _, _, _, _| -1,
+            spawn_kernel_thread,
+            yield_now,
+            exit_current,
+            current_tid: || TID.with(|t| t.get()),
+            thread_count: || NEXT_TID.load(Ordering::Relaxed) - 1,
+            wait_on_addr,
+            wait_on_addr_until,
+            wake_on_addr: |_, _| 0,
+            set_clear_on_exit_addr: |_| 0,
+            join_thread,
+            thread_info: |_| None,
+            set_thread_name: |_| {},
+            account_syscall: || {},
+        })
+    });
+}
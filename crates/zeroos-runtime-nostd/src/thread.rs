@@ -0,0 +1,316 @@
+//! Native kernel threads for no_std guests.
+//!
+//! Threads are cooperative: a thread runs until it yields, blocks on a [`Mutex`]/[`Condvar`],
+//! joins another thread or exits. There is no preemption, so a busy loop that never yields
+//! starves every other thread.
+//!
+//! A panic in any thread aborts the whole program (no unwinding in no_std).
+
+extern crate alloc as alloc_crate;
+
+use alloc_crate::boxed::Box;
+use alloc_crate::sync::Arc;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+
+use foundation::kfn::scheduler as ksched;
+
+type ThreadMain = Box<dyn FnOnce() + Send + 'static>;
+
+struct Packet<T> {
+    result: UnsafeCell<Option<T>>,
+}
+
+// The result is written by the child before it exits and read by the joiner only after
+// `kjoin_thread` observed that exit.
+unsafe impl<T: Send> Sync for Packet<T> {}
+
+extern "C" fn thread_start(arg: usize) -> ! {
+    let main = unsafe { Box::from_raw(arg as *mut ThreadMain) };
+    main();
+    loop {
+        ksched::kexit_current(0);
+    }
+}
+
+/// Owned permission to join a spawned thread.
+pub struct JoinHandle<T> {
+    tid: usize,
+    packet: Arc<Packet<T>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Thread ID of the spawned thread.
+    pub fn tid(&self) -> usize {
+        self.tid
+    }
+
+    /// Block until the thread finishes and return its result.
+    pub fn join(self) -> T {
+        let ret = ksched::kjoin_thread(self.tid);
+        if ret < 0 {
+            panic!("join of thread {} failed: {}", self.tid, ret);
+        }
+        unsafe { (*self.packet.result.get()).take() }.expect("joined thread produced no result")
+    }
+}
+
+/// Spawn a new kernel thread running `f`.
+///
+/// # Panics
+/// Panics if the scheduler cannot create another thread.
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let packet = Arc::new(Packet {
+        result: UnsafeCell::new(None),
+    });
+    let their_packet = packet.clone();
+    let main: ThreadMain = Box::new(move || {
+        let value = f();
+        unsafe { *their_packet.result.get() = Some(value) };
+    });
+    let arg = Box::into_raw(Box::new(main));
+
+    let tid = ksched::kspawn_kernel_thread(thread_start, arg as usize);
+    if tid < 0 {
+        drop(unsafe { Box::from_raw(arg) });
+        panic!("spawn failed: {}", tid);
+    }
+    JoinHandle {
+        tid: tid as usize,
+        packet,
+    }
+}
+
+/// Give the CPU to another ready thread, if any.
+#[inline]
+pub fn yield_now() {
+    ksched::ksched_yield();
+}
+
+/// Thread ID of the calling thread.
+#[inline]
+pub fn current_tid() -> usize {
+    ksched::kcurrent_tid()
+}
+
+const UNLOCKED: i32 = 0;
+const LOCKED: i32 = 1;
+const CONTENDED: i32 = 2;
+
+/// A mutual exclusion lock built on the scheduler's address wait queues.
+pub struct Mutex<T: ?Sized> {
+    state: AtomicI32,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: AtomicI32::new(UNLOCKED),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Acquire the lock, blocking the current thread until it is available.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        if self
+            .state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            while self.state.swap(CONTENDED, Ordering::Acquire) != UNLOCKED {
+                ksched::kwait_on_addr(self.state.as_ptr() as usize, CONTENDED);
+            }
+        }
+        MutexGuard {
+            mutex: self,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Acquire the lock if it is free.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| MutexGuard {
+                mutex: self,
+                _not_send: PhantomData,
+            })
+    }
+
+    fn unlock(&self) {
+        if self.state.swap(UNLOCKED, Ordering::Release) == CONTENDED {
+            ksched::kwake_on_addr(self.state.as_ptr() as usize, 1);
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// RAII guard returned by [`Mutex::lock`]; releases the lock on drop.
+pub struct MutexGuard<'a, T: ?Sized> {
+    mutex: &'a Mutex<T>,
+    // The lock must be released by the thread that took it.
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for MutexGuard<'_, T> {}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock();
+    }
+}
+
+/// A condition variable for use with [`Mutex`].
+pub struct Condvar {
+    seq: AtomicU32,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+        }
+    }
+
+    /// Release `guard`'s lock, block until notified, then re-acquire it.
+    ///
+    /// Spurious wakeups are possible; callers should re-check their condition in a loop.
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let seq = self.seq.load(Ordering::Relaxed);
+        let mutex = guard.mutex;
+        drop(guard);
+        ksched::kwait_on_addr(self.seq.as_ptr() as usize, seq as i32);
+        mutex.lock()
+    }
+
+    /// Block until `condition` returns false.
+    pub fn wait_while<'a, T: ?Sized, F>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: F,
+    ) -> MutexGuard<'a, T>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    pub fn notify_one(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        ksched::kwake_on_addr(self.seq.as_ptr() as usize, 1);
+    }
+
+    pub fn notify_all(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        ksched::kwake_on_addr(self.seq.as_ptr() as usize, usize::MAX);
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_join_returns_value() {
+        crate::test_support::init();
+        let handle = spawn(|| 6 * 7);
+        assert_ne!(handle.tid(), current_tid());
+        assert_eq!(handle.join(), 42);
+    }
+
+    #[test]
+    fn test_mutex_excludes_across_yields() {
+        crate::test_support::init();
+        static COUNTER: Mutex<(u32, bool)> = Mutex::new((0, false));
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                spawn(|| {
+                    for _ in 0..100 {
+                        let mut guard = COUNTER.lock();
+                        assert!(!guard.1, "two holders at once");
+                        guard.1 = true;
+                        let seen = guard.0;
+                        yield_now();
+                        guard.0 = seen + 1;
+                        guard.1 = false;
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join();
+        }
+        assert_eq!(COUNTER.lock().0, 400);
+    }
+
+    #[test]
+    fn test_condvar_wakes_waiter() {
+        crate::test_support::init();
+        let state = Arc::new((Mutex::new(false), Condvar::new()));
+
+        let waiter = {
+            let state = state.clone();
+            spawn(move || {
+                let (ready, cv) = &*state;
+                *cv.wait_while(ready.lock(), |ready| !*ready)
+            })
+        };
+        yield_now();
+        *state.0.lock() = true;
+        state.1.notify_one();
+        assert!(waiter.join());
+    }
+}
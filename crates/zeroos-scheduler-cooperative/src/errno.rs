@@ -0,0 +1,3 @@
+//! Linux errno values returned by the scheduler.
+
+pub use foundation::errno::{EAGAIN, EDEADLK, EPERM, ESRCH, ETIMEDOUT};
@@ -0,0 +1,127 @@
+//! Seeded schedule exploration (test-only policy).
+//!
+//! Instead of strict round-robin, every scheduling point (yield, futex wake) picks the next
+//! ready thread pseudo-randomly from a seeded generator. Running a guest under many seeds
+//! explores different interleavings; a failing seed replays the exact same schedule.
+//!
+//! The seed is taken from `ZEROOS_SCHED_SEED` at build time (decimal or `0x`-prefixed hex) and
+//! can be overridden at runtime with [`set_seed`] before threads are spawned. There is no
+//! preemption in this scheduler, so yields and wakes are the only decision points.
+
+use foundation::utils::KernelLock;
+
+const FALLBACK_SEED: u64 = 0x5EED_0000_0000_0001;
+
+/// Seed used when [`set_seed`] has not been called.
+pub const DEFAULT_SEED: u64 = parse_seed(option_env!("ZEROOS_SCHED_SEED"));
+
+const fn parse_seed(s: Option<&str>) -> u64 {
+    let Some(s) = s else {
+        return FALLBACK_SEED;
+    };
+    let b = s.as_bytes();
+    let (mut i, radix) = if b.len() > 2 && b[0] == b'0' && (b[1] == b'x' || b[1] == b'X') {
+        (2, 16)
+    } else {
+        (0, 10)
+    };
+    let mut v: u64 = 0;
+    while i < b.len() {
+        let d = match b[i] {
+            c @ b'0'..=b'9' => (c - b'0') as u64,
+            c @ b'a'..=b'f' if radix == 16 => (c - b'a' + 10) as u64,
+            c @ b'A'..=b'F' if radix == 16 => (c - b'A' + 10) as u64,
+            b'_' => {
+                i += 1;
+                continue;
+            }
+            _ => panic!("ZEROOS_SCHED_SEED must be a decimal or 0x-prefixed hex integer"),
+        };
+        v = v.wrapping_mul(radix).wrapping_add(d);
+        i += 1;
+    }
+    v
+}
+
+struct Explorer {
+    seed: u64,
+    state: u64,
+    decisions: u64,
+}
+
+impl Explorer {
+    const fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: seed,
+            decisions: 0,
+        }
+    }
+
+    // SplitMix64: tiny, stateless-seedable, and good enough to spread interleavings.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+static EXPLORER: KernelLock<Explorer> = KernelLock::new(Explorer::new(DEFAULT_SEED));
+
+/// Reset the generator to `seed`. Call before spawning threads for a reproducible run.
+pub fn set_seed(seed: u64) {
+    EXPLORER.with_mut(|e| *e = Explorer::new(seed));
+}
+
+/// Return the seed of the current run.
+pub fn seed() -> u64 {
+    EXPLORER.with(|e| e.seed)
+}
+
+/// Return the number of scheduling decisions taken so far.
+pub fn decisions() -> u64 {
+    EXPLORER.with(|e| e.decisions)
+}
+
+/// Pick an index in `0..n` (`n` must be non-zero).
+pub(crate) fn pick(n: usize) -> usize {
+    debug_assert!(n != 0);
+    EXPLORER.with_mut(|e| {
+        e.decisions += 1;
+        (e.next_u64() % n as u64) as usize
+    })
+}
+
+/// Print the seed so a failing run can be replayed with `ZEROOS_SCHED_SEED=<seed>`.
+pub fn report() {
+    foundation::kprintln!(
+        "[SCHED] schedule exploration: seed=0x{:016x} decisions={} (replay with ZEROOS_SCHED_SEED=0x{:x})",
+        seed(),
+        decisions(),
+        seed()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_seed() {
+        assert_eq!(parse_seed(None), FALLBACK_SEED);
+        assert_eq!(parse_seed(Some("1234")), 1234);
+        assert_eq!(parse_seed(Some("0xdead_BEEF")), 0xDEAD_BEEF);
+    }
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = Explorer::new(42);
+        let mut b = Explorer::new(42);
+        for _ in 0..16 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Explorer::new(1).next_u64(), Explorer::new(2).next_u64());
+    }
+}
@@ -2,6 +2,9 @@
 
 extern crate alloc;
 
+mod errno;
+#[cfg(feature = "explore")]
+pub mod explore;
 pub mod ops;
 pub mod scheduler;
 pub mod thread;
@@ -1,9 +1,20 @@
 use crate::scheduler::Scheduler;
+use foundation::ops::ThreadInfo;
 
 // Standard EPERM (Operation not permitted) value for ABI compatibility.
-use libc::EPERM;
+use crate::errno::{EPERM, ESRCH, ETIMEDOUT};
 
 pub fn init() -> usize {
+    // A failing run prints its seed on the way out.
+    #[cfg(feature = "explore")]
+    foundation::kfn::shutdown::kshutdown_register(
+        foundation::kfn::shutdown::ShutdownStage::Stats,
+        |code| {
+            if code != 0 {
+                crate::explore::report();
+            }
+        },
+    );
     Scheduler::init()
 }
 
@@ -41,6 +52,11 @@ pub fn spawn_thread(
     .unwrap_or(-EPERM as isize)
 }
 
+pub fn spawn_kernel_thread(entry: extern "C" fn(arg: usize) -> !, arg: usize) -> isize {
+    Scheduler::with_mut(|scheduler| scheduler.spawn_kernel_thread(entry, arg))
+        .unwrap_or(-EPERM as isize)
+}
+
 pub fn yield_now() -> isize {
     Scheduler::with_mut(|scheduler| scheduler.yield_now());
     0
@@ -65,6 +81,12 @@ pub fn wait_on_addr(addr: usize, val: i32) -> isize {
     Scheduler::with_mut(|scheduler| scheduler.wait_on_addr(addr, val)).unwrap_or(0)
 }
 
+#[inline(always)]
+pub fn wait_on_addr_until(addr: usize, val: i32, deadline_ns: u64) -> isize {
+    Scheduler::with_mut(|scheduler| scheduler.wait_on_addr_until(addr, val, deadline_ns))
+        .unwrap_or(-ETIMEDOUT as isize)
+}
+
 #[inline(always)]
 pub fn wake_on_addr(addr: usize, count: usize) -> usize {
     Scheduler::with_mut(|scheduler| scheduler.wake_on_addr(addr, count)).unwrap_or(0)
@@ -84,14 +106,45 @@ pub fn set_tid_address(tidptr: usize) -> isize {
     .unwrap_or(0)
 }
 
+pub fn join_thread(tid: usize) -> isize {
+    Scheduler::with_mut(|scheduler| scheduler.join_thread(tid)).unwrap_or(-ESRCH as isize)
+}
+
+pub fn thread_info(index: usize) -> Option<ThreadInfo> {
+    Scheduler::with_mut(|scheduler| scheduler.thread_info(index)).flatten()
+}
+
+pub fn set_thread_name(name: &[u8]) {
+    Scheduler::with_mut(|scheduler| {
+        if let Some(tcb) = scheduler.current_thread() {
+            unsafe { (*tcb.as_ptr()).set_name(name) };
+        }
+    });
+}
+
+#[inline(always)]
+pub fn account_syscall() {
+    Scheduler::with_mut(|scheduler| {
+        if let Some(tcb) = scheduler.current_thread() {
+            unsafe { (*tcb.as_ptr()).syscalls += 1 };
+        }
+    });
+}
+
 pub const SCHEDULER_OPS: foundation::ops::SchedulerOps = foundation::ops::SchedulerOps {
     init,
     spawn_thread,
+    spawn_kernel_thread,
     yield_now,
     exit_current,
     current_tid,
     thread_count,
     wait_on_addr,
+    wait_on_addr_until,
     wake_on_addr,
     set_clear_on_exit_addr: set_tid_address,
+    join_thread,
+    thread_info,
+    set_thread_name,
+    account_syscall,
 };
@@ -3,13 +3,16 @@ use alloc::boxed::Box;
 use core::ptr::NonNull;
 use foundation::utils::GlobalOption;
 
-use libc::{EAGAIN, EDEADLK, EPERM};
+use crate::errno::{EAGAIN, EDEADLK, EPERM, ESRCH, ETIMEDOUT};
+use foundation::ops::{ThreadInfo, THREAD_NAME_LEN};
 
 use alloc::alloc::Layout;
 use foundation::kfn::arch as karch;
+use foundation::kfn::thread::{self as kthread, ThreadAnchor};
 
 pub const MAX_THREADS: usize = 64;
 
+/// Stays locked across context switches; the thread switched to unlocks it (see `yield_now`).
 static SCHEDULER: GlobalOption<Scheduler> = GlobalOption::none();
 
 pub struct Scheduler {
@@ -19,6 +22,9 @@ pub struct Scheduler {
     pub(crate) next_tid: Tid,
 }
 
+// The table owns its TCBs, which are only touched with `SCHEDULER` locked.
+unsafe impl Send for Scheduler {}
+
 impl Default for Scheduler {
     fn default() -> Self {
         Self::new()
@@ -66,11 +72,21 @@ impl Scheduler {
                         state: ThreadState::Running,
                         saved_pc: 0,
                         futex_wait_addr: 0,
+                        wake_at: u64::MAX,
                         clear_child_tid: 0,
                         kstack_base: anchor_ptr as usize,
                         kstack_size: crate::thread::KSTACK_SIZE,
+                        name: [0; THREAD_NAME_LEN],
+                        exit_code: 0,
+                        syscalls: 0,
+                        yields: 0,
+                        instret: 0,
+                        switched_in_at: karch::kinstret(),
+                        started: true,
+                        detached: false,
                     },
                 );
+                (*boot_ptr).set_name(b"main");
             }
 
             let mut boot = unsafe { Box::from_raw(boot_ptr) };
@@ -109,6 +125,7 @@ impl Scheduler {
             unsafe {
                 (*anchor_ptr).task_ptr = ptr.as_ptr() as usize;
             }
+            kthread::kguard_kstack(anchor_ptr);
         });
 
         anchor_ptr as usize
@@ -131,6 +148,67 @@ impl Scheduler {
         self.thread_count
     }
 
+    fn index_of(&self, tid: Tid) -> Option<usize> {
+        (0..self.thread_count)
+            .find(|&i| self.threads[i].is_some_and(|tcb| unsafe { (*tcb.as_ptr()).tid == tid }))
+    }
+
+    /// Remove the exited thread at `index` from the table and free it.
+    fn reap(&mut self, index: usize) {
+        debug_assert_ne!(index, self.current_index, "reaping the running thread");
+        let Some(tcb) = self.threads[index] else {
+            return;
+        };
+        self.threads
+            .copy_within(index + 1..self.thread_count, index);
+        self.thread_count -= 1;
+        self.threads[self.thread_count] = None;
+        if self.current_index > index {
+            self.current_index -= 1;
+        }
+        drop(unsafe { Box::from_raw(tcb.as_ptr()) });
+    }
+
+    /// Free every exited detached thread.
+    ///
+    /// An exiting thread still runs on its kernel stack, so it is freed by the next spawn or
+    /// exit of another thread rather than by its own.
+    fn reap_detached(&mut self) {
+        let mut i = 0;
+        while i < self.thread_count {
+            let exited = i != self.current_index
+                && self.threads[i].is_some_and(|tcb| unsafe {
+                    (*tcb.as_ptr()).detached && (*tcb.as_ptr()).state == ThreadState::Exited
+                });
+            if exited {
+                self.reap(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    /// Snapshot the thread at `index`, charging the running thread for instructions so far.
+    pub fn thread_info(&self, index: usize) -> Option<ThreadInfo> {
+        if index >= self.thread_count {
+            return None;
+        }
+        let tcb = unsafe { self.threads[index]?.as_ref() };
+        let mut instret = tcb.instret;
+        if tcb.state == ThreadState::Running {
+            instret += karch::kinstret().wrapping_sub(tcb.switched_in_at);
+        }
+        Some(ThreadInfo {
+            tid: tcb.tid,
+            status: tcb.state.into(),
+            name: tcb.name,
+            exit_code: tcb.exit_code,
+            syscalls: tcb.syscalls,
+            yields: tcb.yields,
+            instret,
+        })
+    }
+
     pub fn current_tid_or_1(&self) -> usize {
         if let Some(tcb) = self.current_thread() {
             unsafe { (*tcb.as_ptr()).tid }
@@ -159,7 +237,21 @@ impl Scheduler {
             }
         }
 
-        let Some(next_idx) = self.find_next_ready((current_idx + 1) % self.thread_count) else {
+        self.wake_expired();
+        let mut next = self.find_next_ready((current_idx + 1) % self.thread_count);
+        // Nothing can run until a timed wait expires; let the clock get there.
+        while next.is_none() {
+            let Some(deadline) = self.next_wake_at() else {
+                break;
+            };
+            foundation::kfn::time::kspin_until(deadline);
+            self.wake_expired();
+            next = self.find_next_ready((current_idx + 1) % self.thread_count);
+        }
+        #[cfg(feature = "journal")]
+        let next = self.journal_next(next);
+
+        let Some(next_idx) = next else {
             if let Some(current_tcb) = self.threads[current_idx] {
                 unsafe {
                     if (*current_tcb.as_ptr()).state == ThreadState::Ready {
@@ -188,11 +280,28 @@ impl Scheduler {
 
         // Perform context switch if needed
         unsafe {
-            if let (Some(mut old_ptr), Some(new_ptr)) =
+            if let (Some(mut old_ptr), Some(mut new_ptr)) =
                 (self.threads[current_idx], self.threads[self.current_index])
             {
                 let old_tcb = old_ptr.as_mut();
-                let new_tcb = new_ptr.as_ref();
+                let new_tcb = new_ptr.as_mut();
+
+                // Catch kernel stack overflows at the latest when either side switches.
+                kthread::kcheck_kstack(old_tcb.kstack_base as *const ThreadAnchor, old_tcb.tid);
+                kthread::kcheck_kstack(new_tcb.kstack_base as *const ThreadAnchor, new_tcb.tid);
+                kthread::kguard_kstack(new_tcb.kstack_base as *const ThreadAnchor);
+
+                let now = karch::kinstret();
+                old_tcb.yields += 1;
+                old_tcb.instret += now.wrapping_sub(old_tcb.switched_in_at);
+                new_tcb.switched_in_at = now;
+
+                // `new` releases the lock when it leaves the critical section it switched away
+                // in; a thread that has never run was not in one.
+                if !core::mem::replace(&mut new_tcb.started, true) {
+                    SCHEDULER.force_unlock();
+                }
+
                 karch::kswitch_to(old_tcb.thread_ctx_ptr_mut(), new_tcb.thread_ctx_ptr());
             }
         }
@@ -240,8 +349,80 @@ impl Scheduler {
         0
     }
 
+    /// [`Self::wait_on_addr`] that gives up with `-ETIMEDOUT` once the clock reaches
+    /// `deadline_ns`.
+    pub fn wait_on_addr_until(&mut self, addr: usize, expected: i32, deadline_ns: u64) -> isize {
+        let actual = unsafe { core::ptr::read_volatile(addr as *const i32) };
+        if actual != expected {
+            return -EAGAIN as isize;
+        }
+        let Some(current_tcb) = self.current_thread() else {
+            return -ETIMEDOUT as isize;
+        };
+        if self.thread_count() <= 1 {
+            // No other thread can wake us; only the deadline can.
+            foundation::kfn::time::kspin_until(deadline_ns);
+            return -ETIMEDOUT as isize;
+        }
+
+        unsafe {
+            (*current_tcb.as_ptr()).state = ThreadState::Blocked;
+            (*current_tcb.as_ptr()).futex_wait_addr = addr;
+            (*current_tcb.as_ptr()).wake_at = deadline_ns;
+        }
+        self.yield_now();
+
+        let timed_out =
+            unsafe { core::mem::replace(&mut (*current_tcb.as_ptr()).wake_at, u64::MAX) };
+        if timed_out != u64::MAX {
+            -ETIMEDOUT as isize
+        } else {
+            0
+        }
+    }
+
+    /// Earliest deadline among the timed waiters.
+    fn next_wake_at(&self) -> Option<u64> {
+        self.threads[..self.thread_count]
+            .iter()
+            .flatten()
+            .map(|tcb| unsafe { tcb.as_ref() })
+            .filter(|tcb| tcb.state == ThreadState::Blocked)
+            .map(|tcb| tcb.wake_at)
+            .filter(|&at| at != u64::MAX)
+            .min()
+    }
+
+    /// Make every timed waiter whose deadline has passed ready again.
+    ///
+    /// The clock is only read while some thread waits with a timeout, so untimed programs
+    /// journal no extra readings.
+    fn wake_expired(&mut self) {
+        if self.next_wake_at().is_none() {
+            return;
+        }
+        self.expire_until(foundation::kfn::time::ktime_ns());
+    }
+
+    fn expire_until(&mut self, now: u64) {
+        for tcb in self.threads[..self.thread_count].iter().flatten() {
+            let tcb = unsafe { &mut *tcb.as_ptr() };
+            if tcb.state == ThreadState::Blocked && tcb.wake_at <= now {
+                tcb.state = ThreadState::Ready;
+                tcb.futex_wait_addr = 0;
+            }
+        }
+    }
+
     pub fn wake_on_addr(&mut self, addr: usize, count: usize) -> usize {
         let ret = self.wake_futex(addr, count);
+
+        // Under exploration a wake is also a scheduling point: the waker may lose the CPU.
+        #[cfg(feature = "explore")]
+        if ret > 0 {
+            self.yield_now();
+        }
+
         if let Some(tcb) = self.current_thread() {
             unsafe {
                 karch::kthread_ctx_set_retval((*tcb.as_ptr()).thread_ctx_ptr_mut(), ret);
@@ -262,6 +443,10 @@ impl Scheduler {
             // Scheduler must be initialized (boot TCB installed) before spawning threads.
             return -EPERM as isize;
         }
+        self.reap_detached();
+        if self.thread_count >= MAX_THREADS {
+            return -EPERM as isize;
+        }
 
         let new_tid = self.next_tid;
         self.next_tid += 1;
@@ -305,11 +490,13 @@ impl Scheduler {
         }
 
         child_tcb.clear_child_tid = clear_child_tid_ptr;
+        child_tcb.detached = true;
+        // Like Linux, a new thread inherits the name of its creator.
+        if let Some(parent_tcb) = self.current_thread() {
+            child_tcb.name = unsafe { (*parent_tcb.as_ptr()).name };
+        }
 
         let child_ptr = unsafe { NonNull::new_unchecked(Box::into_raw(child_tcb)) };
-        if self.thread_count >= MAX_THREADS {
-            return -EPERM as isize;
-        }
         self.threads[self.thread_count] = Some(child_ptr);
         self.thread_count += 1;
 
@@ -322,6 +509,88 @@ impl Scheduler {
         new_tid as isize
     }
 
+    /// Create a kernel thread entered at `entry(arg)` by the context switch (no trap frame).
+    pub fn spawn_kernel_thread(
+        &mut self,
+        entry: extern "C" fn(arg: usize) -> !,
+        arg: usize,
+    ) -> isize {
+        if self.thread_count == 0 {
+            return -EPERM as isize;
+        }
+        self.reap_detached();
+        if self.thread_count >= MAX_THREADS {
+            return -EAGAIN as isize;
+        }
+
+        let new_tid = self.next_tid;
+        self.next_tid += 1;
+
+        let entry = entry as usize;
+        let mut tcb = Box::new(ThreadControlBlock::new(new_tid, 0, 0, entry));
+        unsafe {
+            let anchor_ptr = tcb.kstack_base as *mut ThreadAnchor;
+            (*anchor_ptr).task_ptr = Box::as_ref(&tcb) as *const _ as usize;
+
+            // Run below the (unused) trap frame slot at the top of the kernel stack.
+            let sp = kthread::ktrap_frame_addr(anchor_ptr) & !0xF;
+            karch::kthread_ctx_set_sp(tcb.thread_ctx_ptr_mut(), sp);
+            karch::kthread_ctx_set_ra(tcb.thread_ctx_ptr_mut(), entry);
+            karch::kthread_ctx_set_retval(tcb.thread_ctx_ptr_mut(), arg);
+        }
+        if let Some(parent_tcb) = self.current_thread() {
+            tcb.name = unsafe { (*parent_tcb.as_ptr()).name };
+        }
+
+        let ptr = unsafe { NonNull::new_unchecked(Box::into_raw(tcb)) };
+        self.threads[self.thread_count] = Some(ptr);
+        self.thread_count += 1;
+
+        new_tid as isize
+    }
+
+    /// Record the scheduling decision, or substitute the recorded one when replaying.
+    #[cfg(feature = "journal")]
+    fn journal_next(&self, next: Option<usize>) -> Option<usize> {
+        use foundation::kfn::journal::{kjournal_u64, J_SCHED};
+
+        let tid = next
+            .and_then(|i| self.threads[i])
+            .map_or(0, |tcb| unsafe { (*tcb.as_ptr()).tid });
+        let tid = kjournal_u64(J_SCHED, tid as u64) as Tid;
+        if tid == 0 {
+            return None;
+        }
+        let idx = (0..self.thread_count).find(|&i| {
+            self.threads[i].is_some_and(|tcb| unsafe {
+                (*tcb.as_ptr()).tid == tid && (*tcb.as_ptr()).state == ThreadState::Ready
+            })
+        });
+        if idx.is_none() {
+            panic!("journal replay diverged: tid {} is not ready", tid);
+        }
+        idx
+    }
+
+    #[cfg(feature = "explore")]
+    #[inline(always)]
+    fn is_ready(&self, idx: usize) -> bool {
+        self.threads[idx].is_some_and(|tcb| unsafe { (*tcb.as_ptr()).state == ThreadState::Ready })
+    }
+
+    #[cfg(feature = "explore")]
+    fn find_next_ready(&self, _start_from: usize) -> Option<usize> {
+        let ready = (0..self.thread_count).filter(|&i| self.is_ready(i)).count();
+        if ready == 0 {
+            return None;
+        }
+        let nth = crate::explore::pick(ready);
+        (0..self.thread_count)
+            .filter(|&i| self.is_ready(i))
+            .nth(nth)
+    }
+
+    #[cfg(not(feature = "explore"))]
     fn find_next_ready(&self, start_from: usize) -> Option<usize> {
         for i in start_from..self.thread_count {
             if let Some(tcb) = self.threads[i] {
@@ -340,6 +609,45 @@ impl Scheduler {
         None
     }
 
+    #[cfg(feature = "explore")]
+    pub fn wake_futex(&mut self, futex_addr: usize, max_count: usize) -> usize {
+        let waits_here = |s: &Self, i: usize| {
+            s.threads[i].is_some_and(|tcb| unsafe {
+                (*tcb.as_ptr()).state == ThreadState::Blocked
+                    && (*tcb.as_ptr()).futex_wait_addr == futex_addr
+            })
+        };
+
+        // Wake waiters in a seed-dependent order instead of table order.
+        let mut woken = 0;
+        while woken < max_count {
+            let waiting = (0..self.thread_count)
+                .filter(|&i| waits_here(self, i))
+                .count();
+            if waiting == 0 {
+                break;
+            }
+            let nth = crate::explore::pick(waiting);
+            if let Some(i) = (0..self.thread_count)
+                .filter(|&i| waits_here(self, i))
+                .nth(nth)
+            {
+                if let Some(tcb) = self.threads[i] {
+                    unsafe {
+                        (*tcb.as_ptr()).state = ThreadState::Ready;
+                        (*tcb.as_ptr()).futex_wait_addr = 0;
+                        (*tcb.as_ptr()).wake_at = u64::MAX;
+                        (*tcb.as_ptr()).wake_at = u64::MAX;
+                    }
+                }
+                woken += 1;
+            }
+        }
+
+        woken
+    }
+
+    #[cfg(not(feature = "explore"))]
     pub fn wake_futex(&mut self, futex_addr: usize, max_count: usize) -> usize {
         let mut woken = 0;
 
@@ -354,6 +662,8 @@ impl Scheduler {
                     {
                         (*tcb.as_ptr()).state = ThreadState::Ready;
                         (*tcb.as_ptr()).futex_wait_addr = 0;
+                        (*tcb.as_ptr()).wake_at = u64::MAX;
+                        (*tcb.as_ptr()).wake_at = u64::MAX;
                         woken += 1;
                     }
                 }
@@ -363,12 +673,53 @@ impl Scheduler {
         woken
     }
 
+    /// Block until thread `tid` has exited, free it and return its exit code.
+    ///
+    /// Joiners sleep on the target's TCB address; `exit_current_and_yield` wakes them. Only the
+    /// first joiner sees the exit code; later ones find no such thread.
+    pub fn join_thread(&mut self, tid: Tid) -> isize {
+        let Some(current) = self.current_thread() else {
+            return -ESRCH as isize;
+        };
+
+        loop {
+            let Some(index) = self.index_of(tid) else {
+                return -ESRCH as isize;
+            };
+            if index == self.current_index {
+                return -EDEADLK as isize;
+            }
+            let target = self.threads[index].expect("thread table hole");
+            unsafe {
+                if (*target.as_ptr()).state == ThreadState::Exited {
+                    let exit_code = (*target.as_ptr()).exit_code;
+                    self.reap(index);
+                    return exit_code as isize;
+                }
+                (*current.as_ptr()).state = ThreadState::Blocked;
+                (*current.as_ptr()).futex_wait_addr = target.as_ptr() as usize;
+            }
+            self.yield_now();
+
+            // Nobody else could run: the target can never exit.
+            unsafe {
+                if (*current.as_ptr()).state == ThreadState::Blocked {
+                    (*current.as_ptr()).state = ThreadState::Running;
+                    (*current.as_ptr()).futex_wait_addr = 0;
+                    return -EDEADLK as isize;
+                }
+            }
+        }
+    }
+
     pub fn exit_current_and_yield(&mut self, exit_code: i32) -> isize {
         if let Some(current_tcb) = self.current_thread() {
             let is_main_thread = unsafe { (*current_tcb.as_ptr()).tid == 1 };
 
             unsafe {
                 (*current_tcb.as_ptr()).state = ThreadState::Exited;
+                (*current_tcb.as_ptr()).exit_code = exit_code;
+                self.wake_futex(current_tcb.as_ptr() as usize, usize::MAX);
 
                 let clear = (*current_tcb.as_ptr()).clear_child_tid;
                 if clear != 0 {
@@ -381,6 +732,7 @@ impl Scheduler {
                 foundation::kfn::kexit(exit_code);
             }
 
+            self.reap_detached();
             self.yield_now();
             0
         } else {
@@ -388,3 +740,206 @@ impl Scheduler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::thread::{ThreadContext, KSTACK_SIZE};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use foundation::ops::{MemoryOps, MemoryStats, ThreadStatus};
+    use std::sync::{Mutex, MutexGuard, Once};
+
+    // Platform hooks the kernel functions link against.
+    #[no_mangle]
+    extern "C" fn __platform_stdout_write(msg: *const u8, len: usize) {
+        use std::io::Write;
+        let _ = std::io::stdout().write_all(unsafe { core::slice::from_raw_parts(msg, len) });
+    }
+
+    #[no_mangle]
+    extern "C" fn __platform_exit(code: i32) -> ! {
+        std::process::exit(code)
+    }
+
+    /// Bytes currently allocated through the host memory ops.
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+    fn alloc(layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { std::alloc::alloc(layout) }
+    }
+
+    fn dealloc(ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { std::alloc::dealloc(ptr, layout) }
+    }
+
+    /// Install host memory ops and a fresh scheduler; tests share both, so they run one at a time.
+    fn setup() -> MutexGuard<'static, ()> {
+        static SERIAL: Mutex<()> = Mutex::new(());
+        static INIT: Once = Once::new();
+        let guard = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        INIT.call_once(|| {
+            foundation::register_memory(MemoryOps {
+                init: |_, _| {},
+                alloc,
+                dealloc,
+                realloc: |_, _, _| core::ptr::null_mut(),
+                stats: MemoryStats::default,
+            })
+        });
+        SCHEDULER.set(Scheduler::new());
+        guard
+    }
+
+    /// Add a thread with a real kernel stack (no arch context) to the table.
+    fn add(s: &mut Scheduler, tid: Tid, state: ThreadState) -> NonNull<ThreadControlBlock> {
+        let anchor = kthread::kalloc_kstack(KSTACK_SIZE, 64, 16);
+        assert!(!anchor.is_null());
+        let tcb = Box::new(ThreadControlBlock {
+            thread_ctx: ThreadContext(core::ptr::null_mut()),
+            tid,
+            state,
+            saved_pc: 0,
+            futex_wait_addr: 0,
+            wake_at: u64::MAX,
+            clear_child_tid: 0,
+            kstack_base: anchor as usize,
+            kstack_size: KSTACK_SIZE,
+            name: [0; THREAD_NAME_LEN],
+            exit_code: 0,
+            syscalls: 0,
+            yields: 0,
+            instret: 0,
+            switched_in_at: 0,
+            started: true,
+            detached: false,
+        });
+        let ptr = unsafe { NonNull::new_unchecked(Box::into_raw(tcb)) };
+        s.threads[s.thread_count] = Some(ptr);
+        s.thread_count += 1;
+        ptr
+    }
+
+    #[test]
+    fn test_join_frees_the_thread() {
+        let _serial = setup();
+        let live = LIVE.load(Ordering::Relaxed);
+        Scheduler::with_mut(|s| {
+            add(s, 1, ThreadState::Running);
+            let child = add(s, 2, ThreadState::Exited);
+            unsafe { (*child.as_ptr()).exit_code = 7 };
+            assert!(LIVE.load(Ordering::Relaxed) > live);
+
+            assert_eq!(s.join_thread(2), 7);
+            assert_eq!(s.thread_count(), 1);
+            assert_eq!(s.join_thread(2), -ESRCH as isize);
+            assert_eq!(s.join_thread(1), -EDEADLK as isize);
+        });
+        // Only the boot thread's kernel stack remains.
+        assert_eq!(LIVE.load(Ordering::Relaxed) - live, KSTACK_SIZE);
+    }
+
+    #[test]
+    fn test_exited_detached_threads_free_their_slot() {
+        let _serial = setup();
+        Scheduler::with_mut(|s| {
+            add(s, 1, ThreadState::Ready);
+            let detached = add(s, 2, ThreadState::Exited);
+            unsafe { (*detached.as_ptr()).detached = true };
+            add(s, 3, ThreadState::Exited);
+            add(s, 4, ThreadState::Running);
+            s.current_index = 3;
+
+            s.reap_detached();
+            // The joinable thread waits for its joiner; the running thread stays current.
+            let tids: std::vec::Vec<_> = s.threads[..s.thread_count]
+                .iter()
+                .map(|tcb| unsafe { (*tcb.unwrap().as_ptr()).tid })
+                .collect();
+            assert_eq!(tids, [1, 3, 4]);
+            assert_eq!(s.current_tid_or_1(), 4);
+        });
+    }
+
+    #[test]
+    fn test_table_is_reusable_after_exits() {
+        let _serial = setup();
+        Scheduler::with_mut(|s| {
+            add(s, 1, ThreadState::Running);
+            for tid in 2..(MAX_THREADS as Tid * 4) {
+                if s.thread_count == MAX_THREADS {
+                    s.reap_detached();
+                }
+                let tcb = add(s, tid, ThreadState::Exited);
+                unsafe { (*tcb.as_ptr()).detached = true };
+            }
+        });
+    }
+
+    #[test]
+    fn test_timed_waits_expire_in_deadline_order() {
+        let _serial = setup();
+        Scheduler::with_mut(|s| {
+            add(s, 1, ThreadState::Running);
+            let untimed = add(s, 2, ThreadState::Blocked);
+            let late = add(s, 3, ThreadState::Blocked);
+            let early = add(s, 4, ThreadState::Blocked);
+            unsafe {
+                (*untimed.as_ptr()).futex_wait_addr = 0x1000;
+                (*late.as_ptr()).futex_wait_addr = 0x1000;
+                (*late.as_ptr()).wake_at = 200;
+                (*early.as_ptr()).futex_wait_addr = 0x2000;
+                (*early.as_ptr()).wake_at = 100;
+            }
+            assert_eq!(s.next_wake_at(), Some(100));
+
+            s.expire_until(150);
+            unsafe {
+                // Timed out: ready, with the deadline kept for the waiter to see.
+                assert_eq!((*early.as_ptr()).state, ThreadState::Ready);
+                assert_eq!((*early.as_ptr()).wake_at, 100);
+                assert_eq!((*late.as_ptr()).state, ThreadState::Blocked);
+            }
+            assert_eq!(s.next_wake_at(), Some(200));
+
+            // A wake before the deadline cancels it.
+            assert_eq!(s.wake_futex(0x1000, 2), 2);
+            unsafe { assert_eq!((*late.as_ptr()).wake_at, u64::MAX) };
+            assert_eq!(s.next_wake_at(), None);
+        });
+    }
+
+    #[test]
+    fn test_thread_name_and_accounting() {
+        let _serial = setup();
+        let current = Scheduler::with_mut(|s| {
+            add(s, 1, ThreadState::Running);
+            let other = add(s, 2, ThreadState::Blocked);
+            unsafe {
+                (*other.as_ptr()).set_name(b"worker\0garbage");
+                (*other.as_ptr()).instret = 500;
+                (*other.as_ptr()).yields = 3;
+            }
+            s.threads[0].unwrap()
+        })
+        .unwrap();
+
+        crate::ops::set_thread_name(b"a-name-longer-than-fifteen-bytes");
+        crate::ops::account_syscall();
+        crate::ops::account_syscall();
+        unsafe {
+            assert_eq!(&(*current.as_ptr()).name, b"a-name-longer-t\0");
+            assert_eq!((*current.as_ptr()).syscalls, 2);
+        }
+
+        let info = crate::ops::thread_info(1).unwrap();
+        assert_eq!(info.tid, 2);
+        assert_eq!(info.status, ThreadStatus::Blocked);
+        assert_eq!(&info.name[..7], b"worker\0");
+        assert_eq!((info.instret, info.yields, info.syscalls), (500, 3, 0));
+        assert!(crate::ops::thread_info(2).is_none());
+    }
+}
@@ -1,5 +1,6 @@
 use alloc::alloc::Layout;
 use foundation::kfn::arch as karch;
+use foundation::ops::{ThreadStatus, THREAD_NAME_LEN};
 
 /// Thread ID type (arch-independent).
 pub type Tid = usize;
@@ -28,6 +29,17 @@ pub enum ThreadState {
     Exited,
 }
 
+impl From<ThreadState> for ThreadStatus {
+    fn from(state: ThreadState) -> Self {
+        match state {
+            ThreadState::Ready => ThreadStatus::Ready,
+            ThreadState::Running => ThreadStatus::Running,
+            ThreadState::Blocked => ThreadStatus::Blocked,
+            ThreadState::Exited => ThreadStatus::Exited,
+        }
+    }
+}
+
 #[repr(C)]
 pub struct ThreadControlBlock {
     pub thread_ctx: ThreadContext,
@@ -35,12 +47,30 @@ pub struct ThreadControlBlock {
     pub state: ThreadState,
     pub saved_pc: usize,
     pub futex_wait_addr: usize,
+    /// Clock deadline of a timed wait on `futex_wait_addr` (`u64::MAX`: none). A wake clears
+    /// it; a timeout leaves it set, which is how the waiter tells the two apart.
+    pub wake_at: u64,
     pub clear_child_tid: usize,
 
     // Kernel stack base/size (low-level thread anchor lives at base).
     // This is conceptually independent of the scheduler; the scheduler just tracks it.
     pub kstack_base: usize,
     pub kstack_size: usize,
+
+    pub name: [u8; THREAD_NAME_LEN],
+    pub exit_code: i32,
+
+    // Accounting (see `foundation::ops::ThreadInfo`).
+    pub syscalls: u64,
+    pub yields: u64,
+    pub instret: u64,
+    /// `instret` value when this thread last got the CPU.
+    pub switched_in_at: u64,
+    /// Whether the thread has been switched to yet.
+    pub started: bool,
+    /// Nobody joins the thread (Linux threads signal their exit through `clear_child_tid`), so
+    /// the scheduler frees it once it has exited.
+    pub detached: bool,
 }
 
 pub const KSTACK_SIZE: usize = 16 * 1024; // 16KB kernel stack
@@ -83,14 +113,31 @@ impl ThreadControlBlock {
             state: ThreadState::Ready,
             saved_pc: initial_pc,
             futex_wait_addr: 0,
+            wake_at: u64::MAX,
             clear_child_tid: 0,
             kstack_base: anchor_addr,
             kstack_size: KSTACK_SIZE,
+            name: [0; THREAD_NAME_LEN],
+            exit_code: 0,
+            syscalls: 0,
+            yields: 0,
+            instret: 0,
+            switched_in_at: 0,
+            started: false,
+            detached: false,
         }
     }
 
     // Boot thread is initialized eagerly in `Scheduler::init()`.
 
+    /// Set the thread name, truncating so a trailing NUL always fits.
+    pub fn set_name(&mut self, name: &[u8]) {
+        let name = name.split(|&b| b == 0).next().unwrap_or(&[]);
+        let len = name.len().min(THREAD_NAME_LEN - 1);
+        self.name = [0; THREAD_NAME_LEN];
+        self.name[..len].copy_from_slice(&name[..len]);
+    }
+
     #[inline(always)]
     pub fn thread_ctx_ptr(&self) -> *const u8 {
         self.thread_ctx.as_ptr()
@@ -101,3 +148,22 @@ impl ThreadControlBlock {
         self.thread_ctx.as_mut_ptr()
     }
 }
+
+impl Drop for ThreadControlBlock {
+    /// Free the kernel stack and switch context; the thread must never run again.
+    fn drop(&mut self) {
+        if !self.thread_ctx.0.is_null() {
+            let layout =
+                Layout::from_size_align(karch::kthread_ctx_size(), karch::kthread_ctx_align())
+                    .expect("invalid thread ctx layout");
+            foundation::kfn::memory::kfree(self.thread_ctx.0, layout);
+        }
+        if self.kstack_base != 0 {
+            unsafe {
+                foundation::kfn::scheduler::kfree_kstack(
+                    self.kstack_base as *mut foundation::kfn::scheduler::ThreadAnchor,
+                )
+            };
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! `/dev`: the device nodes registered with [`crate::register_device`] and
+//! [`crate::register_device_with`], in one flat directory.
+//!
+//! Opening a node calls its factory or open callback, so each descriptor gets its own device
+//! state. The directory cannot be changed from the guest.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use foundation::utils::KernelLock;
+
+use crate::mount::{open_dir, DirEntry, FileSystem};
+use crate::{DeviceFactory, DeviceOpenFn, FdEntry, Ino, VfsResult};
+
+/// Where the VFS mounts [`DEVFS`].
+pub(crate) const DEV_MOUNT: &str = "/dev";
+
+/// Device number reported in `st_dev`.
+const DEVFS_DEV: u64 = 2;
+
+/// The directory itself; device `i` is inode `i + 1`.
+const ROOT: Ino = 0;
+
+/// How a device node produces descriptors.
+#[derive(Clone, Copy)]
+pub(crate) enum Opener {
+    Factory(DeviceFactory),
+    /// Open callback and the context pointer registered with it.
+    Open(DeviceOpenFn, *mut u8),
+}
+
+// Registering an open callback commits its context to being opened from any thread.
+unsafe impl Send for Opener {}
+
+#[derive(Clone, Copy)]
+struct Device {
+    /// Path the node was registered under.
+    path: &'static str,
+    name: &'static str,
+    opener: Opener,
+}
+
+pub(crate) struct DevFs {
+    devices: KernelLock<Vec<Device>>,
+}
+
+pub(crate) static DEVFS: DevFs = DevFs::new();
+
+impl DevFs {
+    pub(crate) const fn new() -> Self {
+        Self {
+            devices: KernelLock::new(Vec::new()),
+        }
+    }
+
+    /// Add the device node `path`, which must name an entry directly under `/dev`.
+    pub(crate) fn register(&self, path: &'static str, opener: Opener) -> VfsResult<()> {
+        let name = path
+            .strip_prefix(DEV_MOUNT)
+            .and_then(|p| p.strip_prefix('/'))
+            .ok_or(-(libc::EINVAL as isize))?;
+        if name.is_empty() || name.contains('/') || matches!(name, "." | "..") {
+            return Err(-(libc::EINVAL as isize));
+        }
+        self.devices.with_mut(|devices| {
+            if devices.iter().any(|d| d.name == name) {
+                return Err(-(libc::EEXIST as isize));
+            }
+            devices.push(Device { path, name, opener });
+            Ok(())
+        })
+    }
+
+    fn device(&self, ino: Ino) -> VfsResult<Device> {
+        self.devices.with(|devices| {
+            ino.checked_sub(1)
+                .and_then(|i| devices.get(i).copied())
+                .ok_or(-(libc::ENOENT as isize))
+        })
+    }
+}
+
+impl FileSystem for DevFs {
+    fn root(&self) -> Ino {
+        ROOT
+    }
+
+    fn lookup(&self, dir: Ino, name: &str) -> VfsResult<Ino> {
+        if dir != ROOT {
+            return Err(-(libc::ENOTDIR as isize));
+        }
+        self.devices.with(|devices| {
+            devices
+                .iter()
+                .position(|d| d.name == name)
+                .map(|i| i + 1)
+                .ok_or(-(libc::ENOENT as isize))
+        })
+    }
+
+    fn create(&self, dir: Ino, name: &str, _mode: u32) -> VfsResult<Ino> {
+        match self.lookup(dir, name) {
+            Ok(_) => Err(-(libc::EEXIST as isize)),
+            Err(e) if e == -(libc::ENOENT as isize) => Err(-(libc::EACCES as isize)),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn unlink(&self, dir: Ino, name: &str, want_dir: bool) -> VfsResult<()> {
+        self.lookup(dir, name)?;
+        if want_dir {
+            return Err(-(libc::ENOTDIR as isize));
+        }
+        Err(-(libc::EACCES as isize))
+    }
+
+    fn readdir(&self, dir: Ino, index: usize) -> VfsResult<Option<DirEntry>> {
+        if dir != ROOT {
+            return Err(-(libc::ENOTDIR as isize));
+        }
+        let entry = match index {
+            0 | 1 => DirEntry {
+                name: String::from(if index == 0 { "." } else { ".." }),
+                ino: ROOT,
+                d_type: libc::DT_DIR,
+            },
+            _ => match self.device(index - 1) {
+                Ok(device) => DirEntry {
+                    name: String::from(device.name),
+                    ino: index - 1,
+                    d_type: libc::DT_CHR,
+                },
+                Err(_) => return Ok(None),
+            },
+        };
+        Ok(Some(entry))
+    }
+
+    fn stat(&self, ino: Ino, st: &mut libc::stat) -> VfsResult<()> {
+        let (mode, nlink) = if ino == ROOT {
+            (libc::S_IFDIR | 0o755, 2)
+        } else {
+            self.device(ino)?;
+            (libc::S_IFCHR | 0o666, 1)
+        };
+        *st = unsafe { core::mem::zeroed() };
+        st.st_dev = DEVFS_DEV as _;
+        st.st_ino = (ino + 1) as _;
+        st.st_mode = mode as _;
+        st.st_nlink = nlink as _;
+        st.st_blksize = 4096 as _;
+        Ok(())
+    }
+
+    fn open(&'static self, ino: Ino, flags: i32, mode: u32) -> VfsResult<FdEntry> {
+        if ino == ROOT {
+            return Ok(open_dir(self, ROOT));
+        }
+        let device = self.device(ino)?;
+        match device.opener {
+            Opener::Factory(factory) => Ok(factory()),
+            Opener::Open(open, ctx) => open(ctx, device.path, flags, mode),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn null() -> FdEntry {
+        FdEntry {
+            ops: &crate::ramfs::DIR_FOPS,
+            private_data: core::ptr::null_mut(),
+        }
+    }
+
+    /// Opens read-only, handing out the registered context as the descriptor's state.
+    fn open_ctx(ctx: *mut u8, path: &str, flags: i32, _mode: u32) -> VfsResult<FdEntry> {
+        assert_eq!(path, "/dev/ctx");
+        if flags & libc::O_ACCMODE != libc::O_RDONLY {
+            return Err(-(libc::EACCES as isize));
+        }
+        Ok(FdEntry {
+            ops: &crate::ramfs::DIR_FOPS,
+            private_data: ctx,
+        })
+    }
+
+    #[test]
+    fn test_register_and_list() {
+        static FS: DevFs = DevFs::new();
+        FS.register("/dev/null", Opener::Factory(null)).unwrap();
+        assert_eq!(
+            FS.register("/dev/null", Opener::Factory(null)),
+            Err(-(libc::EEXIST as isize))
+        );
+        assert_eq!(
+            FS.register("/dev/pts/0", Opener::Factory(null)),
+            Err(-(libc::EINVAL as isize))
+        );
+        assert_eq!(
+            FS.register("/tmp/null", Opener::Factory(null)),
+            Err(-(libc::EINVAL as isize))
+        );
+
+        let ino = FS.lookup(ROOT, "null").unwrap();
+        let mut st: libc::stat = unsafe { core::mem::zeroed() };
+        FS.stat(ino, &mut st).unwrap();
+        assert_eq!(st.st_mode & libc::S_IFMT, libc::S_IFCHR);
+        let names: Vec<String> = (0..)
+            .map_while(|i| FS.readdir(ROOT, i).unwrap())
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, [".", "..", "null"]);
+        assert_eq!(FS.create(ROOT, "x", 0o644), Err(-(libc::EACCES as isize)));
+        assert_eq!(
+            FS.unlink(ROOT, "null", false),
+            Err(-(libc::EACCES as isize))
+        );
+    }
+
+    #[test]
+    fn test_open_callback_gets_context() {
+        static FS: DevFs = DevFs::new();
+        static mut CTX: u32 = 7;
+        let ctx = &raw mut CTX as *mut u8;
+        FS.register("/dev/ctx", Opener::Open(open_ctx, ctx))
+            .unwrap();
+
+        let ino = FS.lookup(ROOT, "ctx").unwrap();
+        let entry = FS.open(ino, libc::O_RDONLY, 0).unwrap();
+        assert_eq!(entry.private_data, ctx);
+        assert_eq!(
+            FS.open(ino, libc::O_WRONLY, 0).err(),
+            Some(-(libc::EACCES as isize)),
+            "the callback can reject open flags"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! `epoll` instances.
+//!
+//! An instance is a list of interests checked against each file's `FileOps::poll` whenever it
+//! is waited on; there are no per-file wait queues. Interests are keyed by descriptor and
+//! remember which open file they were registered on, so closing the descriptor (or reusing the
+//! number for another file) drops the interest, as Linux does once the file is released.
+//!
+//! `EPOLLET` reports a readiness bit when it was not set at the previous report, or when the
+//! kernel readiness generation moved since then (the file may have been drained and refilled
+//! in between; a spurious edge is harmless, a lost one hangs the waiter).
+//!
+//! `EPOLLONESHOT` disables the interest after one report until it is re-armed with
+//! `EPOLL_CTL_MOD`.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::{anon_fstat, noop_getdents64, noop_ioctl, noop_read, noop_seek, noop_write};
+use crate::{Fd, FdEntry, FileOps, VfsResult};
+
+/// Bits `FileOps::poll` can report that `epoll_ctl` callers may ask for.
+const POLL_BITS: u32 = (libc::EPOLLIN
+    | libc::EPOLLPRI
+    | libc::EPOLLOUT
+    | libc::EPOLLRDNORM
+    | libc::EPOLLWRNORM
+    | libc::EPOLLRDHUP) as u32;
+
+/// Reported whether or not they were asked for.
+const ALWAYS: u32 = (libc::EPOLLERR | libc::EPOLLHUP) as u32;
+
+struct Interest {
+    fd: Fd,
+    /// `FdEntry::private_data` of the file at registration (identity check).
+    file: *mut u8,
+    events: u32,
+    data: u64,
+    /// Ready bits and readiness generation at the previous report (`EPOLLET`).
+    seen: u32,
+    seen_seq: i32,
+    /// Disarmed by `EPOLLONESHOT` until the next `EPOLL_CTL_MOD`.
+    disabled: bool,
+}
+
+pub(crate) struct Epoll {
+    interests: Vec<Interest>,
+}
+
+/// Current file behind `fd` and its `POLL*` readiness, if `fd` is open.
+pub(crate) type Lookup<'a> = &'a dyn Fn(Fd) -> Option<(*mut u8, i16)>;
+
+pub(crate) fn create() -> FdEntry {
+    let ep = Box::new(Epoll {
+        interests: Vec::new(),
+    });
+    FdEntry {
+        ops: &EPOLL_FOPS,
+        private_data: Box::into_raw(ep) as *mut u8,
+    }
+}
+
+/// The epoll instance behind `entry`, if it is one.
+pub(crate) fn instance<'a>(entry: &FdEntry) -> Option<&'a mut Epoll> {
+    if core::ptr::eq(entry.ops, &EPOLL_FOPS) {
+        Some(unsafe { &mut *(entry.private_data as *mut Epoll) })
+    } else {
+        None
+    }
+}
+
+impl Epoll {
+    pub(crate) fn ctl(
+        &mut self,
+        op: i32,
+        fd: Fd,
+        file: *mut u8,
+        event: Option<libc::epoll_event>,
+    ) -> VfsResult<()> {
+        // Interests on a closed or reused descriptor no longer exist.
+        self.interests
+            .retain(|i| i.fd != fd || core::ptr::eq(i.file, file));
+        let pos = self.interests.iter().position(|i| i.fd == fd);
+        match (op, pos, event) {
+            (libc::EPOLL_CTL_ADD, Some(_), _) => Err(-(libc::EEXIST as isize)),
+            (libc::EPOLL_CTL_ADD, None, Some(ev)) => {
+                self.interests.push(Interest {
+                    fd,
+                    file,
+                    events: ev.events,
+                    data: ev.u64,
+                    seen: 0,
+                    seen_seq: 0,
+                    disabled: false,
+                });
+                Ok(())
+            }
+            (libc::EPOLL_CTL_MOD, Some(pos), Some(ev)) => {
+                let i = &mut self.interests[pos];
+                i.events = ev.events;
+                i.data = ev.u64;
+                i.seen = 0;
+                i.disabled = false;
+                Ok(())
+            }
+            (libc::EPOLL_CTL_DEL, Some(pos), _) => {
+                self.interests.remove(pos);
+                Ok(())
+            }
+            (libc::EPOLL_CTL_ADD | libc::EPOLL_CTL_MOD, _, None) => Err(-(libc::EFAULT as isize)),
+            (libc::EPOLL_CTL_MOD | libc::EPOLL_CTL_DEL, None, _) => Err(-(libc::ENOENT as isize)),
+            _ => Err(-(libc::EINVAL as isize)),
+        }
+    }
+
+    /// Descriptors watched, with the file each was registered on.
+    pub(crate) fn watched(&self) -> impl Iterator<Item = (Fd, *mut u8)> + '_ {
+        self.interests.iter().map(|i| (i.fd, i.file))
+    }
+
+    /// Bits of `i` that would be reported now, given the file's `revents` and the readiness
+    /// generation `seq`.
+    fn pending(i: &Interest, revents: i16, seq: i32) -> u32 {
+        let ready = revents as u16 as u32 & ((i.events & POLL_BITS) | ALWAYS);
+        if i.events & libc::EPOLLET as u32 != 0 && seq == i.seen_seq {
+            ready & !i.seen
+        } else {
+            ready
+        }
+    }
+
+    /// Fill `out` with ready events, dropping interests whose file is gone.
+    pub(crate) fn collect(
+        &mut self,
+        out: &mut [libc::epoll_event],
+        lookup: Lookup,
+        seq: i32,
+    ) -> usize {
+        let mut n = 0;
+        self.interests.retain_mut(|i| {
+            let Some((_, revents)) = lookup(i.fd).filter(|&(f, _)| core::ptr::eq(f, i.file)) else {
+                return false;
+            };
+            if n == out.len() || i.disabled {
+                return true;
+            }
+            let bits = Self::pending(i, revents, seq);
+            i.seen = revents as u16 as u32;
+            i.seen_seq = seq;
+            if bits != 0 {
+                out[n] = libc::epoll_event {
+                    events: bits,
+                    u64: i.data,
+                };
+                n += 1;
+                if i.events & libc::EPOLLONESHOT as u32 != 0 {
+                    i.disabled = true;
+                }
+            }
+            true
+        });
+        n
+    }
+
+    /// Readiness of the epoll descriptor itself (nested epoll): readable while any interest
+    /// would be reported. Edge state is not consumed.
+    pub(crate) fn poll(&self, lookup: Lookup, seq: i32) -> i16 {
+        let ready = self.interests.iter().any(|i| {
+            !i.disabled
+                && lookup(i.fd).is_some_and(|(f, revents)| {
+                    core::ptr::eq(f, i.file) && Self::pending(i, revents, seq) != 0
+                })
+        });
+        if ready {
+            libc::POLLIN | libc::POLLRDNORM
+        } else {
+            0
+        }
+    }
+}
+
+fn epoll_release(file: *mut u8) -> isize {
+    drop(unsafe { Box::from_raw(file as *mut Epoll) });
+    0
+}
+
+/// Readiness depends on other descriptors, so the VFS asks [`Epoll::poll`] instead.
+fn epoll_poll(_file: *mut u8) -> i16 {
+    0
+}
+
+static EPOLL_FOPS: FileOps = FileOps {
+    read: noop_read,
+    write: noop_write,
+    release: epoll_release,
+    llseek: noop_seek,
+    ioctl: noop_ioctl,
+    fstat: anon_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: epoll_poll,
+    mmap: None,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(events: i32, data: u64) -> Option<libc::epoll_event> {
+        Some(libc::epoll_event {
+            events: events as u32,
+            u64: data,
+        })
+    }
+
+    #[test]
+    fn test_epoll_level_and_edge() {
+        let file = 0x1000 as *mut u8;
+        let readable = |fd: Fd| (fd == 5).then_some((file, libc::POLLIN));
+        let mut ep = Epoll {
+            interests: Vec::new(),
+        };
+        let mut out = [libc::epoll_event { events: 0, u64: 0 }; 4];
+
+        assert_eq!(
+            ep.ctl(libc::EPOLL_CTL_ADD, 5, file, event(libc::EPOLLIN, 7)),
+            Ok(())
+        );
+        assert_eq!(
+            ep.ctl(libc::EPOLL_CTL_ADD, 5, file, event(libc::EPOLLIN, 7)),
+            Err(-(libc::EEXIST as isize))
+        );
+        // Level-triggered: reported on every wait.
+        assert_eq!(ep.collect(&mut out, &readable, 0), 1);
+        assert_eq!({ out[0].u64 }, 7);
+        assert_eq!(ep.collect(&mut out, &readable, 0), 1);
+
+        // Edge-triggered: reported once until readiness drops and returns.
+        let et = libc::EPOLLIN | libc::EPOLLET;
+        assert_eq!(ep.ctl(libc::EPOLL_CTL_MOD, 5, file, event(et, 8)), Ok(()));
+        assert_eq!(ep.collect(&mut out, &readable, 0), 1);
+        assert_eq!(ep.collect(&mut out, &readable, 0), 0);
+        assert_eq!(ep.collect(&mut out, &|_| Some((file, 0)), 0), 0);
+        assert_eq!(ep.collect(&mut out, &readable, 0), 1);
+        assert_eq!(ep.collect(&mut out, &readable, 0), 0);
+        // Drained and refilled between waits: the generation moved, so the edge is kept.
+        assert_eq!(ep.collect(&mut out, &readable, 1), 1);
+    }
+
+    #[test]
+    fn test_epoll_drops_closed() {
+        let file = 0x1000 as *mut u8;
+        let mut ep = Epoll {
+            interests: Vec::new(),
+        };
+        let mut out = [libc::epoll_event { events: 0, u64: 0 }; 4];
+        assert_eq!(
+            ep.ctl(libc::EPOLL_CTL_ADD, 5, file, event(libc::EPOLLIN, 0)),
+            Ok(())
+        );
+        assert_eq!(ep.collect(&mut out, &|_| None, 0), 0);
+        assert_eq!(
+            ep.ctl(libc::EPOLL_CTL_DEL, 5, file, None),
+            Err(-(libc::ENOENT as isize))
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! `eventfd2` counters.
+//!
+//! Blocking reads and writes sleep on the kernel readiness generation, like pipes.
+
+use alloc::boxed::Box;
+
+use foundation::kfn::vfs::{kpoll_notify, kpoll_seq, kpoll_wait};
+
+use crate::{anon_fstat, noop_getdents64, noop_ioctl, noop_seek, FdEntry, FileOps};
+
+/// Largest value the counter can hold.
+const MAX: u64 = u64::MAX - 1;
+
+struct EventFd {
+    count: u64,
+    semaphore: bool,
+    nonblock: bool,
+}
+
+fn eventfd<'a>(file: *mut u8) -> &'a mut EventFd {
+    unsafe { &mut *(file as *mut EventFd) }
+}
+
+/// Create an eventfd with `initval`; `flags` are `EFD_*` (already validated).
+pub(crate) fn create(initval: u32, flags: i32) -> FdEntry {
+    let efd = Box::new(EventFd {
+        count: initval as u64,
+        semaphore: flags & libc::EFD_SEMAPHORE != 0,
+        nonblock: flags & libc::EFD_NONBLOCK != 0,
+    });
+    FdEntry {
+        ops: &EVENTFD_FOPS,
+        private_data: Box::into_raw(efd) as *mut u8,
+    }
+}
+
+fn eventfd_read(file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    if count < 8 {
+        return -(libc::EINVAL as isize);
+    }
+    loop {
+        let seq = kpoll_seq();
+        let efd = eventfd(file);
+        if efd.count > 0 {
+            let value = if efd.semaphore { 1 } else { efd.count };
+            efd.count -= value;
+            unsafe { (buf as *mut u64).write_unaligned(value) };
+            kpoll_notify();
+            return 8;
+        }
+        if efd.nonblock || !kpoll_wait(seq) {
+            return -(libc::EAGAIN as isize);
+        }
+    }
+}
+
+fn eventfd_write(file: *mut u8, buf: *const u8, count: usize) -> isize {
+    if count < 8 {
+        return -(libc::EINVAL as isize);
+    }
+    let value = unsafe { (buf as *const u64).read_unaligned() };
+    if value == u64::MAX {
+        return -(libc::EINVAL as isize);
+    }
+    loop {
+        let seq = kpoll_seq();
+        let efd = eventfd(file);
+        if MAX - efd.count >= value {
+            efd.count += value;
+            if value != 0 {
+                kpoll_notify();
+            }
+            return 8;
+        }
+        if efd.nonblock || !kpoll_wait(seq) {
+            return -(libc::EAGAIN as isize);
+        }
+    }
+}
+
+fn eventfd_release(file: *mut u8) -> isize {
+    drop(unsafe { Box::from_raw(file as *mut EventFd) });
+    0
+}
+
+fn eventfd_poll(file: *mut u8) -> i16 {
+    let efd = eventfd(file);
+    let mut mask = 0;
+    if efd.count > 0 {
+        mask |= libc::POLLIN | libc::POLLRDNORM;
+    }
+    if efd.count < MAX {
+        mask |= libc::POLLOUT | libc::POLLWRNORM;
+    }
+    mask
+}
+
+static EVENTFD_FOPS: FileOps = FileOps {
+    read: eventfd_read,
+    write: eventfd_write,
+    release: eventfd_release,
+    llseek: noop_seek,
+    ioctl: noop_ioctl,
+    fstat: anon_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: eventfd_poll,
+    mmap: None,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(e: &FdEntry, v: u64) -> isize {
+        (e.ops.write)(e.private_data, v.to_ne_bytes().as_ptr(), 8)
+    }
+
+    fn read(e: &FdEntry) -> Result<u64, isize> {
+        let mut buf = [0u8; 8];
+        match (e.ops.read)(e.private_data, buf.as_mut_ptr(), 8) {
+            8 => Ok(u64::from_ne_bytes(buf)),
+            r => Err(r),
+        }
+    }
+
+    #[test]
+    fn test_eventfd_counter() {
+        let e = create(2, libc::EFD_NONBLOCK);
+        assert_eq!(write(&e, 3), 8);
+        assert_eq!(read(&e), Ok(5));
+        assert_eq!(read(&e), Err(-(libc::EAGAIN as isize)));
+        assert_eq!((e.ops.poll)(e.private_data) & libc::POLLIN, 0);
+        assert_eq!(write(&e, u64::MAX), -(libc::EINVAL as isize));
+        (e.ops.release)(e.private_data);
+    }
+
+    #[test]
+    fn test_eventfd_semaphore() {
+        let e = create(2, libc::EFD_SEMAPHORE | libc::EFD_NONBLOCK);
+        assert_eq!(read(&e), Ok(1));
+        assert_eq!(read(&e), Ok(1));
+        assert_eq!(read(&e), Err(-(libc::EAGAIN as isize)));
+        (e.ops.release)(e.private_data);
+    }
+}
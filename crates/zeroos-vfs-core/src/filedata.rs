@@ -0,0 +1,135 @@
+//! Page-aligned storage for regular ramfs files.
+//!
+//! `MAP_SHARED` mappings hand out pointers into this buffer, so while any are live it must not
+//! move: growing past the capacity it had when first mapped fails with `ENOSPC` instead of
+//! reallocating.
+
+use alloc::alloc::{alloc_zeroed, dealloc, Layout};
+use core::ptr::NonNull;
+
+use crate::VfsResult;
+
+const PAGE_SIZE: usize = 4096;
+
+pub(crate) struct FileData {
+    ptr: NonNull<u8>,
+    len: usize,
+    cap: usize,
+    /// Live `MAP_SHARED` mappings of this buffer.
+    maps: u32,
+}
+
+// The buffer is owned like a `Vec`'s; mappings into it are tracked by `maps`.
+unsafe impl Send for FileData {}
+
+impl FileData {
+    pub(crate) const fn new() -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            len: 0,
+            cap: 0,
+            maps: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr()
+    }
+
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    /// Grow the buffer to hold at least `cap` bytes.
+    fn reserve(&mut self, cap: usize) -> VfsResult<()> {
+        if cap <= self.cap {
+            return Ok(());
+        }
+        if self.maps > 0 {
+            return Err(-(libc::ENOSPC as isize));
+        }
+        let cap = cap
+            .max(self.cap * 2)
+            .checked_next_multiple_of(PAGE_SIZE)
+            .ok_or(-(libc::EFBIG as isize))?;
+        let layout =
+            Layout::from_size_align(cap, PAGE_SIZE).map_err(|_| -(libc::EFBIG as isize))?;
+        let ptr = NonNull::new(unsafe { alloc_zeroed(layout) }).ok_or(-(libc::ENOMEM as isize))?;
+        if self.cap > 0 {
+            unsafe {
+                core::ptr::copy_nonoverlapping(self.ptr.as_ptr(), ptr.as_ptr(), self.len);
+                dealloc(self.ptr.as_ptr(), self.layout());
+            }
+        }
+        self.ptr = ptr;
+        self.cap = cap;
+        Ok(())
+    }
+
+    /// Set the length; new bytes read as zero.
+    pub(crate) fn resize(&mut self, len: usize) -> VfsResult<()> {
+        if len > self.len {
+            self.reserve(len)?;
+            // Bytes past the end may hold stale data (an earlier shrink, or a shared mapping).
+            unsafe { core::ptr::write_bytes(self.ptr.as_ptr().add(self.len), 0, len - self.len) };
+        }
+        self.len = len;
+        Ok(())
+    }
+
+    /// Pin the buffer for a shared mapping of `[offset, offset + len)` and return its address.
+    ///
+    /// The range may extend past the end of the file; the buffer is grown to cover it.
+    pub(crate) fn map(&mut self, offset: usize, len: usize) -> VfsResult<*mut u8> {
+        let end = offset.checked_add(len).ok_or(-(libc::EINVAL as isize))?;
+        self.reserve(end).map_err(|_| -(libc::ENOMEM as isize))?;
+        self.maps += 1;
+        Ok(unsafe { self.ptr.as_ptr().add(offset) })
+    }
+
+    pub(crate) fn unmap(&mut self) {
+        self.maps -= 1;
+    }
+
+    fn layout(&self) -> Layout {
+        Layout::from_size_align(self.cap, PAGE_SIZE).expect("valid file layout")
+    }
+}
+
+impl Drop for FileData {
+    fn drop(&mut self) {
+        if self.cap > 0 {
+            unsafe { dealloc(self.ptr.as_ptr(), self.layout()) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapped_storage_is_pinned() {
+        let mut data = FileData::new();
+        data.resize(10).unwrap();
+        let addr = data.map(0, PAGE_SIZE).unwrap();
+        assert!((addr as usize).is_multiple_of(PAGE_SIZE));
+        unsafe { addr.write(7) };
+        assert_eq!(unsafe { data.as_ptr().read() }, 7);
+
+        data.resize(PAGE_SIZE).unwrap();
+        assert_eq!(
+            data.as_mut_ptr(),
+            addr,
+            "growing within capacity must not move"
+        );
+        assert_eq!(data.resize(PAGE_SIZE + 1), Err(-(libc::ENOSPC as isize)));
+        data.unmap();
+        data.resize(PAGE_SIZE + 1).unwrap();
+        assert_eq!(unsafe { data.as_ptr().read() }, 7);
+    }
+}
@@ -1,5 +1,7 @@
 #![no_std]
 
+extern crate alloc;
+
 pub use foundation::ops::VfsOps;
 
 pub use libc::{
@@ -7,14 +9,41 @@ pub use libc::{
     S_IRUSR, S_IRWXG, S_IRWXO, S_IRWXU, S_IWGRP, S_IWOTH, S_IWUSR, S_IXGRP, S_IXOTH, S_IXUSR,
 };
 
+mod devfs;
+mod epoll;
+mod eventfd;
+mod filedata;
+mod mount;
+mod pipe;
+mod procfs;
+mod ramfs;
+mod socket;
 mod vfs;
 
+pub use mount::{open_dir, DirEntry, FileSystem};
+pub use ramfs::Ino;
 pub use vfs::*;
 
 pub type Fd = i32;
 
 pub type VfsResult<T> = Result<T, isize>;
 
+pub type PreadFn = fn(file: *mut u8, buf: *mut u8, count: usize, offset: usize) -> isize;
+pub type PwriteFn = fn(file: *mut u8, buf: *const u8, count: usize, offset: usize) -> isize;
+pub type MmapFn =
+    fn(file: *mut u8, offset: usize, len: usize, write: bool) -> VfsResult<SharedMapping>;
+
+/// A `MAP_SHARED` view of a file's own storage, pinned until `unmap(token)` is called.
+#[derive(Clone, Copy)]
+pub struct SharedMapping {
+    pub addr: *mut u8,
+    pub token: usize,
+    pub unmap: fn(token: usize),
+}
+
+// The pointer is into storage the filesystem keeps alive until `unmap`.
+unsafe impl Send for SharedMapping {}
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct FileOps {
@@ -23,6 +52,21 @@ pub struct FileOps {
     pub release: fn(file: *mut u8) -> isize,
     pub llseek: fn(file: *mut u8, offset: isize, whence: i32) -> isize,
     pub ioctl: fn(file: *mut u8, request: usize, arg: usize) -> isize,
+    pub fstat: fn(file: *mut u8, statbuf: *mut libc::stat) -> isize,
+    /// Fill `buf` with `linux_dirent64` records; only directories implement it.
+    pub getdents64: fn(file: *mut u8, buf: *mut u8, count: usize) -> isize,
+    /// Read at `offset` without moving the file position.
+    ///
+    /// `None` marks a stream (pipe, socket, terminal): `pread` fails with `ESPIPE`.
+    pub pread: Option<PreadFn>,
+    /// Write at `offset` without moving the file position (`None`: `ESPIPE`).
+    pub pwrite: Option<PwriteFn>,
+    /// Current readiness as a `POLL*` mask; `POLLHUP`/`POLLERR` are reported even if unasked.
+    pub poll: fn(file: *mut u8) -> i16,
+    /// Map `len` bytes at `offset` shared with the file (`MAP_SHARED`; `write` for `PROT_WRITE`).
+    ///
+    /// `None` means only private copies (`MAP_PRIVATE`, filled through reads) are possible.
+    pub mmap: Option<MmapFn>,
 }
 
 #[repr(C)]
@@ -32,8 +76,14 @@ pub struct FdEntry {
     pub private_data: *mut u8,
 }
 
+/// Produces the descriptor for each open of a stateless device.
 pub type DeviceFactory = fn() -> FdEntry;
 
+/// Opens a device node registered with [`register_device_with`]: `ctx` is the pointer it was
+/// registered with and `path` the node's path. Errors are returned to `open` as is, so a
+/// device can reject flags it does not support.
+pub type DeviceOpenFn = fn(ctx: *mut u8, path: &str, flags: i32, mode: u32) -> VfsResult<FdEntry>;
+
 pub fn noop_close(_file: *mut u8) -> isize {
     0
 }
@@ -53,3 +103,44 @@ pub fn noop_read(_file: *mut u8, _buf: *mut u8, _count: usize) -> isize {
 pub fn noop_write(_file: *mut u8, _buf: *const u8, _count: usize) -> isize {
     -(libc::EBADF as isize)
 }
+
+pub fn noop_getdents64(_file: *mut u8, _buf: *mut u8, _count: usize) -> isize {
+    -(libc::ENOTDIR as isize)
+}
+
+/// `poll` for files that never block: always readable and writable.
+pub fn poll_ready(_file: *mut u8) -> i16 {
+    libc::POLLIN | libc::POLLRDNORM | libc::POLLOUT | libc::POLLWRNORM
+}
+
+/// `poll` for read-only files that never block.
+pub fn poll_readable(_file: *mut u8) -> i16 {
+    libc::POLLIN | libc::POLLRDNORM
+}
+
+/// `poll` for write-only files that never block.
+pub fn poll_writable(_file: *mut u8) -> i16 {
+    libc::POLLOUT | libc::POLLWRNORM
+}
+
+/// Fill `st` for a file with no backing node: only the type, permissions and block size.
+///
+/// `st` comes from the VFS, which has already checked it, like every `FileOps::fstat` buffer.
+pub(crate) fn fill_stat(st: *mut libc::stat, mode: u32, blksize: i64) -> isize {
+    let st = unsafe { &mut *st };
+    *st = unsafe { core::mem::zeroed() };
+    st.st_mode = mode as _;
+    st.st_nlink = 1;
+    st.st_blksize = blksize as _;
+    0
+}
+
+/// `fstat` for kernel objects without a backing node (eventfd, epoll).
+pub fn anon_fstat(_file: *mut u8, statbuf: *mut libc::stat) -> isize {
+    fill_stat(statbuf, 0o600, 4096)
+}
+
+/// `fstat` for character devices: a world-readable/writable `S_IFCHR` node.
+pub fn chrdev_fstat(_file: *mut u8, statbuf: *mut libc::stat) -> isize {
+    fill_stat(statbuf, libc::S_IFCHR | 0o666, 4096)
+}
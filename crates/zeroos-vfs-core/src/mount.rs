@@ -0,0 +1,440 @@
+//! Filesystem drivers and the mount table.
+//!
+//! The namespace is the root filesystem (the ramfs) with other filesystems mounted over
+//! directories of it. Paths are resolved lexically into a canonical form (no leading slash,
+//! no `.`, `..` or empty components; the root is `""`), and the mount with the longest
+//! matching prefix serves the rest of the path.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{noop_ioctl, poll_readable, FdEntry, FileOps, Ino, VfsResult};
+
+/// Symlinks followed while resolving one path before giving up with `ELOOP`.
+const MAX_SYMLINKS: usize = 8;
+
+/// One directory entry as reported by [`FileSystem::readdir`].
+pub struct DirEntry {
+    pub name: String,
+    pub ino: Ino,
+    /// `d_type` (`DT_*`).
+    pub d_type: u8,
+}
+
+/// A filesystem driver that can be mounted into the namespace with [`crate::mount`].
+///
+/// Nodes are addressed by [`Ino`], which `stat` should report as `st_ino - 1`. Names passed to
+/// the driver are single path components, never `.` or `..`; the VFS resolves those itself.
+/// Drivers live in statics, so methods take `&self` and keep their state in a `KernelLock`.
+pub trait FileSystem: Sync {
+    /// The root directory.
+    fn root(&self) -> Ino;
+
+    /// Look up `name` in directory `dir`.
+    fn lookup(&self, dir: Ino, name: &str) -> VfsResult<Ino>;
+
+    /// Create the regular file `name` in directory `dir`.
+    fn create(&self, dir: Ino, name: &str, mode: u32) -> VfsResult<Ino>;
+
+    /// Remove `name` from directory `dir`; `want_dir` selects `rmdir` semantics.
+    fn unlink(&self, dir: Ino, name: &str, want_dir: bool) -> VfsResult<()>;
+
+    /// Entry `index` of directory `dir`, counting `.` and `..` first; `None` past the end.
+    fn readdir(&self, dir: Ino, index: usize) -> VfsResult<Option<DirEntry>>;
+
+    fn stat(&self, ino: Ino, st: &mut libc::stat) -> VfsResult<()>;
+
+    /// Open `ino` with the `open` flags and creation mode; directories can use [`open_dir`].
+    ///
+    /// The VFS has already rejected `O_DIRECTORY` on non-directories and write access to
+    /// directories.
+    fn open(&'static self, ino: Ino, flags: i32, mode: u32) -> VfsResult<FdEntry>;
+
+    fn mkdir(&self, _dir: Ino, _name: &str, _mode: u32) -> VfsResult<Ino> {
+        Err(-(libc::EPERM as isize))
+    }
+
+    /// Append the target of symlink `ino` to `target`.
+    fn readlink(&self, _ino: Ino, _target: &mut String) -> VfsResult<()> {
+        Err(-(libc::EINVAL as isize))
+    }
+
+    /// `renameat2` between two directories of this filesystem.
+    fn rename(&self, _old: (Ino, &str), _new: (Ino, &str), _flags: u32) -> VfsResult<()> {
+        Err(-(libc::EPERM as isize))
+    }
+}
+
+/// Canonical form of `path`, resolved against the canonical directory `base` if relative.
+///
+/// `..` is applied lexically and stops at the root.
+pub(crate) fn normalize(base: &str, path: &str) -> String {
+    let mut out = if path.starts_with('/') {
+        String::new()
+    } else {
+        String::from(base)
+    };
+    for name in path.split('/') {
+        match name {
+            "" | "." => {}
+            ".." => {
+                let cut = out.rfind('/').unwrap_or(0);
+                out.truncate(cut);
+            }
+            _ => {
+                if !out.is_empty() {
+                    out.push('/');
+                }
+                out.push_str(name);
+            }
+        }
+    }
+    out
+}
+
+/// Canonical path of `name` inside canonical directory `dir`.
+pub(crate) fn join(dir: &str, name: &str) -> String {
+    let mut path = String::from(dir);
+    if !path.is_empty() {
+        path.push('/');
+    }
+    path.push_str(name);
+    path
+}
+
+/// A resolved node: the filesystem serving it, its inode there and its canonical path.
+pub(crate) struct Vnode {
+    pub(crate) fs: &'static dyn FileSystem,
+    pub(crate) ino: Ino,
+    pub(crate) path: String,
+}
+
+impl Vnode {
+    /// `S_IFMT` bits of the node.
+    pub(crate) fn file_type(&self) -> VfsResult<u32> {
+        let mut st: libc::stat = unsafe { core::mem::zeroed() };
+        self.fs.stat(self.ino, &mut st)?;
+        Ok(st.st_mode as u32 & libc::S_IFMT)
+    }
+}
+
+pub(crate) struct MountTable {
+    root: &'static dyn FileSystem,
+    /// Canonical mount points and the filesystems mounted there.
+    mounts: Vec<(String, &'static dyn FileSystem)>,
+}
+
+impl MountTable {
+    pub(crate) const fn new(root: &'static dyn FileSystem) -> Self {
+        Self {
+            root,
+            mounts: Vec::new(),
+        }
+    }
+
+    /// Mount `fs` at canonical `path`; the directory must already exist (callers check).
+    pub(crate) fn mount(&mut self, path: String, fs: &'static dyn FileSystem) -> VfsResult<()> {
+        if path.is_empty() || self.is_mount_point(&path) {
+            return Err(-(libc::EBUSY as isize));
+        }
+        self.mounts.push((path, fs));
+        Ok(())
+    }
+
+    pub(crate) fn is_mount_point(&self, path: &str) -> bool {
+        self.mounts.iter().any(|(mp, _)| mp == path)
+    }
+
+    /// Resolve canonical `path`, following symlinks in every directory component and a final
+    /// symlink if `follow` is set.
+    ///
+    /// With `dir_only` the result must be a directory, and a final symlink is always followed.
+    fn lookup(&self, mut path: String, follow: bool, dir_only: bool) -> VfsResult<Vnode> {
+        let mut links = 0;
+        'restart: loop {
+            let names: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+            let mut node = Vnode {
+                fs: self.root,
+                ino: self.root.root(),
+                path: String::new(),
+            };
+            for (i, &name) in names.iter().enumerate() {
+                let last = i + 1 == names.len();
+                let child = join(&node.path, name);
+                match self.mounts.iter().find(|(mp, _)| *mp == child) {
+                    Some(&(_, fs)) => (node.fs, node.ino) = (fs, fs.root()),
+                    None => node.ino = node.fs.lookup(node.ino, name)?,
+                }
+                match node.file_type()? {
+                    libc::S_IFLNK if !last || follow || dir_only => {
+                        links += 1;
+                        if links > MAX_SYMLINKS {
+                            return Err(-(libc::ELOOP as isize));
+                        }
+                        let mut target = String::new();
+                        node.fs.readlink(node.ino, &mut target)?;
+                        if target.is_empty() {
+                            return Err(-(libc::ENOENT as isize));
+                        }
+                        // The target replaces the link; the rest of the path is resolved from it.
+                        let mut next = normalize(&node.path, &target);
+                        for rest in &names[i + 1..] {
+                            next = join(&next, rest);
+                        }
+                        path = next;
+                        continue 'restart;
+                    }
+                    libc::S_IFDIR => {}
+                    _ if !last || dir_only => return Err(-(libc::ENOTDIR as isize)),
+                    _ => {}
+                }
+                node.path = child;
+            }
+            return Ok(node);
+        }
+    }
+
+    /// Resolve `path` relative to canonical directory `base`.
+    ///
+    /// A trailing slash only names directories.
+    pub(crate) fn resolve(&self, base: &str, path: &str, follow: bool) -> VfsResult<Vnode> {
+        if path.is_empty() {
+            return Err(-(libc::ENOENT as isize));
+        }
+        self.lookup(normalize(base, path), follow, path.ends_with('/'))
+    }
+
+    /// Resolve all but the last component of `path`; returns the directory and the last name.
+    ///
+    /// The last name is never empty; it may be `.` or `..`, which callers must reject as needed.
+    pub(crate) fn resolve_parent<'p>(
+        &self,
+        base: &str,
+        path: &'p str,
+    ) -> VfsResult<(Vnode, &'p str)> {
+        if path.is_empty() {
+            return Err(-(libc::ENOENT as isize));
+        }
+        let trimmed = path.trim_end_matches('/');
+        let (dir_path, name) = match trimmed.rfind('/') {
+            Some(i) => (&trimmed[..=i], &trimmed[i + 1..]),
+            None => ("", trimmed),
+        };
+        if name.is_empty() {
+            // The path is "/" (or only slashes).
+            return Err(-(libc::EEXIST as isize));
+        }
+        let dir = self.lookup(normalize(base, dir_path), true, true)?;
+        Ok((dir, name))
+    }
+}
+
+/// Fill `buf` with `struct linux_dirent64` records for entries `*pos` onwards, advancing `*pos`.
+///
+/// `entry(index)` returns the name, inode and `d_type` of an entry, or `None` past the end.
+pub(crate) fn fill_dirents<S: AsRef<str>>(
+    buf: *mut u8,
+    count: usize,
+    pos: &mut usize,
+    mut entry: impl FnMut(usize) -> Option<(S, Ino, u8)>,
+) -> isize {
+    const HEADER: usize = 19; // d_ino + d_off + d_reclen + d_type
+
+    let mut written = 0usize;
+    while let Some((name, ino, d_type)) = entry(*pos) {
+        let name = name.as_ref();
+        let reclen = (HEADER + name.len() + 1).next_multiple_of(8);
+        if written + reclen > count {
+            if written == 0 {
+                return -(libc::EINVAL as isize);
+            }
+            break;
+        }
+        unsafe {
+            let rec = buf.add(written);
+            core::ptr::write_bytes(rec, 0, reclen);
+            (rec as *mut u64).write_unaligned((ino + 1) as u64);
+            (rec.add(8) as *mut i64).write_unaligned((*pos + 1) as i64);
+            (rec.add(16) as *mut u16).write_unaligned(reclen as u16);
+            rec.add(18).write(d_type);
+            core::ptr::copy_nonoverlapping(name.as_ptr(), rec.add(HEADER), name.len());
+        }
+        written += reclen;
+        *pos += 1;
+    }
+    written as isize
+}
+
+/// Per-descriptor state of a directory opened with [`open_dir`].
+struct OpenDir {
+    fs: &'static dyn FileSystem,
+    ino: Ino,
+    /// Index of the next entry.
+    pos: usize,
+}
+
+/// A descriptor listing directory `dir` of `fs` through [`FileSystem::readdir`].
+pub fn open_dir(fs: &'static dyn FileSystem, dir: Ino) -> FdEntry {
+    let dir = Box::new(OpenDir {
+        fs,
+        ino: dir,
+        pos: 0,
+    });
+    FdEntry {
+        ops: &OPEN_DIR_FOPS,
+        private_data: Box::into_raw(dir) as *mut u8,
+    }
+}
+
+fn open_dir_of<'a>(file: *mut u8) -> &'a mut OpenDir {
+    unsafe { &mut *(file as *mut OpenDir) }
+}
+
+fn open_dir_release(file: *mut u8) -> isize {
+    drop(unsafe { Box::from_raw(file as *mut OpenDir) });
+    0
+}
+
+fn open_dir_llseek(file: *mut u8, offset: isize, whence: i32) -> isize {
+    if whence != libc::SEEK_SET || offset < 0 {
+        return -(libc::EINVAL as isize);
+    }
+    open_dir_of(file).pos = offset as usize;
+    offset
+}
+
+fn open_dir_fstat(file: *mut u8, st: *mut libc::stat) -> isize {
+    let d = open_dir_of(file);
+    match d.fs.stat(d.ino, unsafe { &mut *st }) {
+        Ok(()) => 0,
+        Err(e) => e,
+    }
+}
+
+fn open_dir_getdents64(file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    let d = open_dir_of(file);
+    let (fs, ino) = (d.fs, d.ino);
+    fill_dirents(buf, count, &mut d.pos, |index| {
+        let e = fs.readdir(ino, index).ok()??;
+        Some((e.name, e.ino, e.d_type))
+    })
+}
+
+static OPEN_DIR_FOPS: FileOps = FileOps {
+    read: crate::ramfs::dir_read,
+    write: crate::ramfs::dir_write,
+    release: open_dir_release,
+    llseek: open_dir_llseek,
+    ioctl: noop_ioctl,
+    fstat: open_dir_fstat,
+    getdents64: open_dir_getdents64,
+    pread: Some(crate::ramfs::dir_pread),
+    pwrite: Some(crate::ramfs::dir_pwrite),
+    poll: poll_readable,
+    mmap: None,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devfs::{DevFs, Opener};
+
+    fn device() -> FdEntry {
+        FdEntry {
+            ops: &OPEN_DIR_FOPS,
+            private_data: core::ptr::null_mut(),
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("", "/a//b/./c/"), "a/b/c");
+        assert_eq!(normalize("a/b", "../c"), "a/c");
+        assert_eq!(normalize("a", "/../.."), "");
+        assert_eq!(normalize("a", "."), "a");
+    }
+
+    #[test]
+    fn test_longest_prefix_resolution() {
+        static ROOT: DevFs = DevFs::new();
+        static DEV: DevFs = DevFs::new();
+        static PTS: DevFs = DevFs::new();
+        ROOT.register("/dev/devnull", Opener::Factory(device))
+            .unwrap();
+        DEV.register("/dev/null", Opener::Factory(device)).unwrap();
+        PTS.register("/dev/0", Opener::Factory(device)).unwrap();
+
+        let mut table = MountTable::new(&ROOT);
+        table.mount(String::from("dev"), &DEV).unwrap();
+        table.mount(String::from("dev/pts"), &PTS).unwrap();
+        assert_eq!(
+            table.mount(String::from("dev"), &PTS).err(),
+            Some(-(libc::EBUSY as isize))
+        );
+
+        let null = table.resolve("", "/dev/null", true).unwrap();
+        assert!(core::ptr::addr_eq(null.fs, &DEV));
+        assert_eq!(null.path, "dev/null");
+        let pty = table.resolve("dev", "pts/../pts/0", true).unwrap();
+        assert!(core::ptr::addr_eq(pty.fs, &PTS));
+        let root = table.resolve("dev/pts", "../..", true).unwrap();
+        assert!(core::ptr::addr_eq(root.fs, &ROOT));
+        assert_eq!(root.path, "");
+        assert!(
+            table.resolve("", "/devnull", true).is_ok(),
+            "not under /dev"
+        );
+        assert_eq!(
+            table.resolve("", "/dev/null/", true).err(),
+            Some(-(libc::ENOTDIR as isize))
+        );
+
+        let (dir, name) = table.resolve_parent("", "/dev/pts/1").unwrap();
+        assert!(core::ptr::addr_eq(dir.fs, &PTS));
+        assert_eq!((dir.path.as_str(), name), ("dev/pts", "1"));
+        assert_eq!(
+            table.resolve_parent("", "/").err(),
+            Some(-(libc::EEXIST as isize))
+        );
+    }
+
+    #[test]
+    fn test_symlinks_resolve_in_every_component() {
+        use crate::filedata::FileData;
+        use crate::ramfs::{with_fs, Kind, ROOTFS};
+
+        fn real(out: &mut String) {
+            out.push_str("real");
+        }
+        fn itself(out: &mut String) {
+            out.push_str("/links/loop");
+        }
+        with_fs(|fs| {
+            let data = FileData::new();
+            fs.install("/links/real/f", Kind::File { data }, 0o644)
+                .unwrap();
+            fs.install("/links/dir", Kind::Symlink(real), 0o777)
+                .unwrap();
+            fs.install("/links/loop", Kind::Symlink(itself), 0o777)
+                .unwrap();
+        });
+
+        let table = MountTable::new(&ROOTFS);
+        let f = table.resolve("", "/links/dir/f", false).unwrap();
+        assert_eq!(f.path, "links/real/f");
+        let (dir, name) = table.resolve_parent("links", "dir/g").unwrap();
+        assert_eq!((dir.path.as_str(), name), ("links/real", "g"));
+        assert_eq!(
+            table.resolve("", "/links/dir/f/", true).err(),
+            Some(-(libc::ENOTDIR as isize))
+        );
+
+        let link = table.resolve("", "/links/loop", false).unwrap();
+        assert_eq!(link.file_type(), Ok(libc::S_IFLNK));
+        assert_eq!(
+            table.resolve("", "/links/loop/f", false).err(),
+            Some(-(libc::ELOOP as isize))
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Anonymous pipes (`pipe2`).
+//!
+//! Both ends share one heap-allocated [`Pipe`], freed when the last end is released. Blocking
+//! reads and writes sleep on the kernel readiness generation (`kfn::vfs::kpoll_wait`) and fail
+//! with `EAGAIN` instead of deadlocking when no other thread could ever make progress. Writes
+//! with no reader left fail with `EPIPE`; the OS layer raises the matching `SIGPIPE`.
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+
+use foundation::kfn::vfs::{kpoll_notify, kpoll_seq, kpoll_wait};
+
+use crate::{
+    fill_stat, noop_getdents64, noop_ioctl, noop_read, noop_seek, noop_write, FdEntry, FileOps,
+};
+
+/// Pipe capacity in bytes (the Linux default).
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// Writes of at most this many bytes are atomic, and a pipe is writable once this much fits.
+const PIPE_BUF: usize = 4096;
+
+struct Pipe {
+    buf: VecDeque<u8>,
+    readers: u32,
+    writers: u32,
+}
+
+/// Per-descriptor state of one pipe end (`FdEntry::private_data`).
+struct PipeEnd {
+    pipe: *mut Pipe,
+    nonblock: bool,
+}
+
+fn end<'a>(file: *mut u8) -> &'a PipeEnd {
+    unsafe { &*(file as *const PipeEnd) }
+}
+
+fn pipe_of<'a>(end: &PipeEnd) -> &'a mut Pipe {
+    unsafe { &mut *end.pipe }
+}
+
+/// Create a pipe and return its read and write ends.
+pub(crate) fn create(flags: i32) -> (FdEntry, FdEntry) {
+    let pipe = Box::into_raw(Box::new(Pipe {
+        buf: VecDeque::new(),
+        readers: 1,
+        writers: 1,
+    }));
+    let nonblock = flags & libc::O_NONBLOCK != 0;
+    let end = |ops| FdEntry {
+        ops,
+        private_data: Box::into_raw(Box::new(PipeEnd { pipe, nonblock })) as *mut u8,
+    };
+    (end(&PIPE_READ_FOPS), end(&PIPE_WRITE_FOPS))
+}
+
+fn pipe_read(file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    if count == 0 {
+        return 0;
+    }
+    let end = end(file);
+    loop {
+        let seq = kpoll_seq();
+        let pipe = pipe_of(end);
+        if !pipe.buf.is_empty() {
+            let n = count.min(pipe.buf.len());
+            for (i, b) in pipe.buf.drain(..n).enumerate() {
+                unsafe { buf.add(i).write(b) };
+            }
+            kpoll_notify();
+            return n as isize;
+        }
+        if pipe.writers == 0 {
+            return 0;
+        }
+        if end.nonblock || !kpoll_wait(seq) {
+            return -(libc::EAGAIN as isize);
+        }
+    }
+}
+
+fn pipe_write(file: *mut u8, buf: *const u8, count: usize) -> isize {
+    let end = end(file);
+    let mut written = 0;
+    while written < count {
+        let seq = kpoll_seq();
+        let pipe = pipe_of(end);
+        if pipe.readers == 0 {
+            return if written > 0 {
+                written as isize
+            } else {
+                -(libc::EPIPE as isize)
+            };
+        }
+        let room = PIPE_CAPACITY - pipe.buf.len();
+        // Small writes go in whole or not at all.
+        let fits = if count - written <= PIPE_BUF {
+            room >= count - written
+        } else {
+            room > 0
+        };
+        if fits {
+            let n = room.min(count - written);
+            let src = unsafe { core::slice::from_raw_parts(buf.add(written), n) };
+            pipe.buf.extend(src);
+            written += n;
+            kpoll_notify();
+            continue;
+        }
+        if end.nonblock || !kpoll_wait(seq) {
+            return if written > 0 {
+                written as isize
+            } else {
+                -(libc::EAGAIN as isize)
+            };
+        }
+    }
+    written as isize
+}
+
+fn release(file: *mut u8, reader: bool) -> isize {
+    let end = unsafe { Box::from_raw(file as *mut PipeEnd) };
+    let pipe = pipe_of(&end);
+    if reader {
+        pipe.readers -= 1;
+    } else {
+        pipe.writers -= 1;
+    }
+    if pipe.readers == 0 && pipe.writers == 0 {
+        drop(unsafe { Box::from_raw(end.pipe) });
+    }
+    // The other end now sees EOF / EPIPE.
+    kpoll_notify();
+    0
+}
+
+fn pipe_read_release(file: *mut u8) -> isize {
+    release(file, true)
+}
+
+fn pipe_write_release(file: *mut u8) -> isize {
+    release(file, false)
+}
+
+fn pipe_read_poll(file: *mut u8) -> i16 {
+    let pipe = pipe_of(end(file));
+    let mut mask = 0;
+    if !pipe.buf.is_empty() {
+        mask |= libc::POLLIN | libc::POLLRDNORM;
+    }
+    if pipe.writers == 0 {
+        mask |= libc::POLLHUP;
+    }
+    mask
+}
+
+fn pipe_write_poll(file: *mut u8) -> i16 {
+    let pipe = pipe_of(end(file));
+    if pipe.readers == 0 {
+        return libc::POLLERR;
+    }
+    if PIPE_CAPACITY - pipe.buf.len() >= PIPE_BUF {
+        libc::POLLOUT | libc::POLLWRNORM
+    } else {
+        0
+    }
+}
+
+fn fifo_fstat(_file: *mut u8, statbuf: *mut libc::stat) -> isize {
+    fill_stat(statbuf, libc::S_IFIFO | 0o600, PIPE_BUF as i64)
+}
+
+static PIPE_READ_FOPS: FileOps = FileOps {
+    read: pipe_read,
+    write: noop_write,
+    release: pipe_read_release,
+    llseek: noop_seek,
+    ioctl: noop_ioctl,
+    fstat: fifo_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: pipe_read_poll,
+    mmap: None,
+};
+
+static PIPE_WRITE_FOPS: FileOps = FileOps {
+    read: noop_read,
+    write: pipe_write,
+    release: pipe_write_release,
+    llseek: noop_seek,
+    ioctl: noop_ioctl,
+    fstat: fifo_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: pipe_write_poll,
+    mmap: None,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipe_roundtrip() {
+        let (r, w) = create(libc::O_NONBLOCK);
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            (r.ops.read)(r.private_data, buf.as_mut_ptr(), buf.len()),
+            -(libc::EAGAIN as isize),
+            "empty pipe should not block with O_NONBLOCK"
+        );
+        assert_eq!((r.ops.poll)(r.private_data), 0);
+
+        assert_eq!((w.ops.write)(w.private_data, b"hello".as_ptr(), 5), 5);
+        assert_ne!((r.ops.poll)(r.private_data) & libc::POLLIN, 0);
+        assert_eq!((r.ops.read)(r.private_data, buf.as_mut_ptr(), buf.len()), 5);
+        assert_eq!(&buf[..5], b"hello");
+
+        (w.ops.release)(w.private_data);
+        assert_ne!((r.ops.poll)(r.private_data) & libc::POLLHUP, 0);
+        assert_eq!(
+            (r.ops.read)(r.private_data, buf.as_mut_ptr(), buf.len()),
+            0,
+            "read after the last writer closed should return EOF"
+        );
+        (r.ops.release)(r.private_data);
+    }
+
+    #[test]
+    fn test_pipe_broken() {
+        let (r, w) = create(0);
+        (r.ops.release)(r.private_data);
+        assert_eq!((w.ops.poll)(w.private_data), libc::POLLERR);
+        assert_eq!(
+            (w.ops.write)(w.private_data, b"x".as_ptr(), 1),
+            -(libc::EPIPE as isize)
+        );
+        (w.ops.release)(w.private_data);
+    }
+}
@@ -0,0 +1,252 @@
+//! Synthetic, read-only `/proc`.
+//!
+//! Files are generated from kernel state when opened, so a descriptor sees one consistent
+//! snapshot: `self/maps` lists the regions recorded with `kfn::memory::kmap_region` (the image
+//! sections the platform registers plus `mmap`), `self/status` the scheduler's threads, and
+//! `meminfo` the allocator's statistics.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt::Write;
+
+use foundation::kfn;
+use foundation::ops::IMAGE_PATH;
+
+use crate::ramfs::{with_fs, Generator, Ino, Kind, RamFs};
+use crate::{noop_getdents64, noop_ioctl, poll_ready, FdEntry, FileOps, VfsResult};
+
+const FILES: &[(&str, Generator)] = &[
+    ("/proc/cpuinfo", cpuinfo),
+    ("/proc/meminfo", meminfo),
+    ("/proc/self/maps", maps),
+    ("/proc/self/status", status),
+];
+
+const LINKS: &[(&str, Generator)] = &[("/proc/self/exe", exe)];
+
+/// Create the `/proc` tree; its directories are sealed against changes from the guest.
+pub(crate) fn install(fs: &mut RamFs) -> VfsResult<()> {
+    for &(path, generate) in FILES {
+        fs.install(path, Kind::Generated(generate), 0o444)?;
+    }
+    for &(path, target) in LINKS {
+        fs.install(path, Kind::Symlink(target), 0o777)?;
+    }
+    let proc = fs.mkdir_all("/proc")?;
+    fs.seal(proc);
+    Ok(())
+}
+
+fn exe(out: &mut String) {
+    out.push_str(IMAGE_PATH);
+}
+
+fn cpuinfo(out: &mut String) {
+    let isa = [
+        (cfg!(target_feature = "m"), 'm'),
+        (cfg!(target_feature = "a"), 'a'),
+        (cfg!(target_feature = "f"), 'f'),
+        (cfg!(target_feature = "d"), 'd'),
+        (cfg!(target_feature = "c"), 'c'),
+    ];
+    let _ = write!(
+        out,
+        "processor\t: 0\nhart\t\t: 0\nisa\t\t: rv{}i",
+        usize::BITS
+    );
+    for (_, ext) in isa.iter().filter(|(on, _)| *on) {
+        out.push(*ext);
+    }
+    out.push_str("\nmmu\t\t: none\n\n");
+}
+
+fn meminfo(out: &mut String) {
+    let stats = kfn::memory::kstats();
+    let _ = write!(
+        out,
+        "MemTotal:{:>15} kB\nMemFree:{:>16} kB\nMemAvailable:{:>11} kB\n",
+        stats.total / 1024,
+        stats.free / 1024,
+        stats.free / 1024,
+    );
+}
+
+/// One line per region, in the kernel's `/proc/<pid>/maps` layout (names start at column 74).
+fn maps(out: &mut String) {
+    kfn::memory::kregions(|r| {
+        let line = out.len();
+        let perms = core::str::from_utf8(&r.perms).unwrap_or("----");
+        let _ = write!(
+            out,
+            "{:08x}-{:08x} {} 00000000 00:00 0",
+            r.start, r.end, perms
+        );
+        if !r.name.is_empty() {
+            let width = 73usize.saturating_sub(out.len() - line).max(1);
+            let _ = write!(out, "{:width$}{}", "", r.name);
+        }
+        out.push('\n');
+    });
+}
+
+fn status(out: &mut String) {
+    let name = IMAGE_PATH.rsplit('/').next().unwrap_or(IMAGE_PATH);
+    let mut vm_size = 0;
+    kfn::memory::kregions(|r| vm_size += r.end - r.start);
+    let _ = write!(
+        out,
+        "Name:\t{name}\nState:\tR (running)\nTgid:\t1\nPid:\t1\nPPid:\t0\n\
+         Uid:\t0\t0\t0\t0\nGid:\t0\t0\t0\t0\nVmSize:\t{:>8} kB\nThreads:\t{}\n",
+        vm_size / 1024,
+        threads(),
+    );
+}
+
+#[cfg(feature = "scheduler")]
+fn threads() -> usize {
+    use foundation::ops::ThreadStatus;
+
+    kfn::scheduler::kthreads()
+        .filter(|t| !matches!(t.status, ThreadStatus::Exited))
+        .count()
+}
+
+#[cfg(not(feature = "scheduler"))]
+fn threads() -> usize {
+    1
+}
+
+/// Per-descriptor snapshot of a generated file (`FdEntry::private_data`).
+struct ProcFile {
+    ino: Ino,
+    data: String,
+    pos: usize,
+}
+
+/// Open generated file `ino`, running its generator.
+pub(crate) fn open(
+    fs: &mut RamFs,
+    ino: Ino,
+    generate: Generator,
+    flags: i32,
+) -> VfsResult<FdEntry> {
+    if flags & libc::O_ACCMODE != libc::O_RDONLY || flags & libc::O_TRUNC != 0 {
+        return Err(-(libc::EACCES as isize));
+    }
+    let mut data = String::new();
+    generate(&mut data);
+    fs.opened(ino);
+    let file = Box::new(ProcFile { ino, data, pos: 0 });
+    Ok(FdEntry {
+        ops: &PROC_FOPS,
+        private_data: Box::into_raw(file) as *mut u8,
+    })
+}
+
+fn proc_file<'a>(file: *mut u8) -> &'a mut ProcFile {
+    unsafe { &mut *(file as *mut ProcFile) }
+}
+
+fn proc_read(file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    let f = proc_file(file);
+    let n = proc_pread(file, buf, count, f.pos);
+    f.pos += n as usize;
+    n
+}
+
+fn proc_pread(file: *mut u8, buf: *mut u8, count: usize, offset: usize) -> isize {
+    let data = proc_file(file).data.as_bytes();
+    let n = data.len().saturating_sub(offset).min(count);
+    if n > 0 {
+        unsafe { core::ptr::copy_nonoverlapping(data.as_ptr().add(offset), buf, n) };
+    }
+    n as isize
+}
+
+fn proc_write(_file: *mut u8, _buf: *const u8, _count: usize) -> isize {
+    -(libc::EBADF as isize)
+}
+
+fn proc_pwrite(_file: *mut u8, _buf: *const u8, _count: usize, _offset: usize) -> isize {
+    -(libc::EBADF as isize)
+}
+
+fn proc_llseek(file: *mut u8, offset: isize, whence: i32) -> isize {
+    let f = proc_file(file);
+    let base = match whence {
+        libc::SEEK_SET => 0,
+        libc::SEEK_CUR => f.pos as isize,
+        libc::SEEK_END => f.data.len() as isize,
+        _ => return -(libc::EINVAL as isize),
+    };
+    match base.checked_add(offset) {
+        Some(pos) if pos >= 0 => {
+            f.pos = pos as usize;
+            pos
+        }
+        _ => -(libc::EINVAL as isize),
+    }
+}
+
+fn proc_release(file: *mut u8) -> isize {
+    let f = unsafe { Box::from_raw(file as *mut ProcFile) };
+    with_fs(|fs| fs.closed(f.ino));
+    0
+}
+
+fn proc_fstat(file: *mut u8, st: *mut libc::stat) -> isize {
+    let f = proc_file(file);
+    with_fs(|fs| fs.stat(f.ino, unsafe { &mut *st }));
+    0
+}
+
+static PROC_FOPS: FileOps = FileOps {
+    read: proc_read,
+    write: proc_write,
+    release: proc_release,
+    llseek: proc_llseek,
+    ioctl: noop_ioctl,
+    fstat: proc_fstat,
+    getdents64: noop_getdents64,
+    pread: Some(proc_pread),
+    pwrite: Some(proc_pwrite),
+    poll: poll_ready,
+    mmap: None,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ramfs::ROOT;
+
+    #[test]
+    fn test_generated_files_and_links() {
+        let mut fs = RamFs::new();
+        fs.init_root();
+        install(&mut fs).unwrap();
+        let exe = fs.lookup(ROOT, "/proc/self/exe").unwrap();
+        assert_eq!(fs.node(exe).dirent_type(), libc::DT_LNK);
+        let mut target = String::new();
+        fs.readlink(exe, &mut target).unwrap();
+        assert_eq!(target, IMAGE_PATH);
+        assert_eq!(
+            fs.lookup(ROOT, &target),
+            Err(-(libc::ENOENT as isize)),
+            "the image is not a file in the namespace"
+        );
+        let proc = fs.lookup(ROOT, "/proc").unwrap();
+        assert_eq!(
+            fs.create_file(proc, "x", 0o644),
+            Err(-(libc::EACCES as isize))
+        );
+        assert_eq!(
+            fs.unlink(proc, "cpuinfo", false),
+            Err(-(libc::EACCES as isize))
+        );
+
+        let mut out = String::new();
+        cpuinfo(&mut out);
+        assert!(out.starts_with("processor\t: 0\n"));
+        assert!(out.contains("isa\t\t: rv"));
+    }
+}
@@ -0,0 +1,841 @@
+//! In-memory directory tree: the root filesystem.
+//!
+//! Everything not under another mount lives here: regular files and directories created by
+//! the guest, and the read-only `/proc` tree. Nodes are addressed by index ([`Ino`]); a node is
+//! freed once it has no links and no open descriptors.
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use foundation::utils::KernelLock;
+
+use crate::filedata::FileData;
+use crate::mount::{fill_dirents, DirEntry, FileSystem};
+use crate::{
+    noop_getdents64, noop_ioctl, poll_readable, poll_ready, procfs, FdEntry, FileOps,
+    SharedMapping, VfsResult,
+};
+
+pub type Ino = usize;
+
+/// The root directory.
+pub const ROOT: Ino = 0;
+
+/// Device number reported in `st_dev`.
+const RAMFS_DEV: u64 = 1;
+
+/// Produces the contents of a generated file or the target of a symlink.
+pub(crate) type Generator = fn(out: &mut String);
+
+pub(crate) enum Kind {
+    Dir {
+        parent: Ino,
+        entries: Vec<(String, Ino)>,
+    },
+    File {
+        data: FileData,
+    },
+    /// Read-only file whose contents are generated on open (procfs).
+    Generated(Generator),
+    Symlink(Generator),
+}
+
+pub(crate) struct Node {
+    pub(crate) kind: Kind,
+    /// Permission bits (`0o7777`).
+    mode: u32,
+    nlink: u32,
+    /// Open descriptors referring to this node.
+    open: u32,
+    /// Directory whose entries cannot be added, removed or renamed.
+    readonly: bool,
+}
+
+impl Node {
+    fn file_type(&self) -> u32 {
+        match self.kind {
+            Kind::Dir { .. } => libc::S_IFDIR,
+            Kind::File { .. } => libc::S_IFREG,
+            Kind::Generated(_) => libc::S_IFREG,
+            Kind::Symlink(_) => libc::S_IFLNK,
+        }
+    }
+
+    /// `d_type` for `getdents64`.
+    pub(crate) fn dirent_type(&self) -> u8 {
+        match self.kind {
+            Kind::Dir { .. } => libc::DT_DIR,
+            Kind::File { .. } => libc::DT_REG,
+            Kind::Generated(_) => libc::DT_REG,
+            Kind::Symlink(_) => libc::DT_LNK,
+        }
+    }
+
+    pub(crate) fn is_dir(&self) -> bool {
+        matches!(self.kind, Kind::Dir { .. })
+    }
+}
+
+pub(crate) struct RamFs {
+    nodes: Vec<Option<Node>>,
+}
+
+pub(crate) static RAMFS: KernelLock<RamFs> = KernelLock::new(RamFs::new());
+
+/// Run `f` on the filesystem, creating the root directory on first use.
+pub(crate) fn with_fs<R>(f: impl FnOnce(&mut RamFs) -> R) -> R {
+    RAMFS.with_mut(|fs| {
+        fs.init_root();
+        f(fs)
+    })
+}
+
+/// Split `path` into its non-empty components.
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|c| !c.is_empty())
+}
+
+impl RamFs {
+    pub(crate) const fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    /// Create the root directory if it does not exist yet.
+    pub(crate) fn init_root(&mut self) {
+        if self.nodes.is_empty() {
+            self.nodes.push(Some(Node {
+                kind: Kind::Dir {
+                    parent: ROOT,
+                    entries: Vec::new(),
+                },
+                mode: 0o755,
+                nlink: 2,
+                open: 0,
+                readonly: false,
+            }));
+        }
+    }
+
+    pub(crate) fn node(&self, ino: Ino) -> &Node {
+        self.nodes[ino].as_ref().expect("stale inode")
+    }
+
+    pub(crate) fn node_mut(&mut self, ino: Ino) -> &mut Node {
+        self.nodes[ino].as_mut().expect("stale inode")
+    }
+
+    fn entries(&self, dir: Ino) -> VfsResult<&Vec<(String, Ino)>> {
+        match &self.node(dir).kind {
+            Kind::Dir { entries, .. } => Ok(entries),
+            _ => Err(-(libc::ENOTDIR as isize)),
+        }
+    }
+
+    fn entries_mut(&mut self, dir: Ino) -> VfsResult<&mut Vec<(String, Ino)>> {
+        match &mut self.node_mut(dir).kind {
+            Kind::Dir { entries, .. } => Ok(entries),
+            _ => Err(-(libc::ENOTDIR as isize)),
+        }
+    }
+
+    pub(crate) fn parent(&self, dir: Ino) -> Ino {
+        match self.node(dir).kind {
+            Kind::Dir { parent, .. } => parent,
+            _ => dir,
+        }
+    }
+
+    /// Look up one name in directory `dir`.
+    fn child(&self, dir: Ino, name: &str) -> VfsResult<Ino> {
+        match name {
+            "." => {
+                self.entries(dir)?;
+                Ok(dir)
+            }
+            ".." => {
+                self.entries(dir)?;
+                Ok(self.parent(dir))
+            }
+            _ => self
+                .entries(dir)?
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|&(_, ino)| ino)
+                .ok_or(-(libc::ENOENT as isize)),
+        }
+    }
+
+    /// Resolve `path` starting at `start` (absolute paths restart at the root).
+    #[cfg(test)]
+    pub(crate) fn lookup(&self, start: Ino, path: &str) -> VfsResult<Ino> {
+        if path.is_empty() {
+            return Err(-(libc::ENOENT as isize));
+        }
+        let mut ino = if path.starts_with('/') { ROOT } else { start };
+        for name in components(path) {
+            ino = self.child(ino, name)?;
+        }
+        // A trailing slash only names directories.
+        if path.ends_with('/') && !self.node(ino).is_dir() {
+            return Err(-(libc::ENOTDIR as isize));
+        }
+        Ok(ino)
+    }
+
+    fn alloc(&mut self, node: Node) -> Ino {
+        if let Some(ino) = self.nodes.iter().position(Option::is_none) {
+            self.nodes[ino] = Some(node);
+            ino
+        } else {
+            self.nodes.push(Some(node));
+            self.nodes.len() - 1
+        }
+    }
+
+    /// Free `ino` once nothing refers to it any more.
+    fn maybe_free(&mut self, ino: Ino) {
+        let node = self.node(ino);
+        if node.nlink == 0 && node.open == 0 {
+            self.nodes[ino] = None;
+        }
+    }
+
+    fn link(&mut self, dir: Ino, name: &str, kind: Kind, mode: u32) -> VfsResult<Ino> {
+        if self.node(dir).readonly {
+            return Err(-(libc::EACCES as isize));
+        }
+        if matches!(name, "." | "..") || self.child(dir, name).is_ok() {
+            return Err(-(libc::EEXIST as isize));
+        }
+        let is_dir = matches!(kind, Kind::Dir { .. });
+        let ino = self.alloc(Node {
+            kind,
+            mode: mode & 0o7777,
+            nlink: if is_dir { 2 } else { 1 },
+            open: 0,
+            readonly: false,
+        });
+        self.entries_mut(dir)?.push((String::from(name), ino));
+        if is_dir {
+            self.node_mut(dir).nlink += 1;
+        }
+        Ok(ino)
+    }
+
+    pub(crate) fn create_file(&mut self, dir: Ino, name: &str, mode: u32) -> VfsResult<Ino> {
+        self.link(
+            dir,
+            name,
+            Kind::File {
+                data: FileData::new(),
+            },
+            mode,
+        )
+    }
+
+    /// Create a regular file that no directory refers to (`memfd_create`); it lives as long as
+    /// it is open.
+    pub(crate) fn create_anon(&mut self, mode: u32) -> Ino {
+        self.alloc(Node {
+            kind: Kind::File {
+                data: FileData::new(),
+            },
+            mode: mode & 0o7777,
+            nlink: 0,
+            open: 0,
+            readonly: false,
+        })
+    }
+
+    pub(crate) fn mkdir(&mut self, dir: Ino, name: &str, mode: u32) -> VfsResult<Ino> {
+        let kind = Kind::Dir {
+            parent: dir,
+            entries: Vec::new(),
+        };
+        self.link(dir, name, kind, mode)
+    }
+
+    /// Create every missing directory of `path` (absolute) and return the last one.
+    pub(crate) fn mkdir_all(&mut self, path: &str) -> VfsResult<Ino> {
+        let mut dir = ROOT;
+        for name in components(path) {
+            dir = match self.child(dir, name) {
+                Ok(ino) => ino,
+                Err(_) => self.mkdir(dir, name, 0o755)?,
+            };
+        }
+        Ok(dir)
+    }
+
+    /// Create a node at `path` (absolute), creating parent directories as needed.
+    pub(crate) fn install(&mut self, path: &str, kind: Kind, mode: u32) -> VfsResult<Ino> {
+        let path = path.trim_end_matches('/');
+        let (dir_path, name) = match path.rfind('/') {
+            Some(i) => (&path[..i], &path[i + 1..]),
+            None => ("", path),
+        };
+        let dir = self.mkdir_all(dir_path)?;
+        self.link(dir, name, kind, mode)
+    }
+
+    /// Freeze the entries of directory `dir` and every directory below it.
+    pub(crate) fn seal(&mut self, dir: Ino) {
+        let subdirs: Vec<Ino> = match self.entries(dir) {
+            Ok(entries) => entries.iter().map(|&(_, ino)| ino).collect(),
+            Err(_) => return,
+        };
+        self.node_mut(dir).readonly = true;
+        for ino in subdirs {
+            self.seal(ino);
+        }
+    }
+
+    /// Remove `name` from `dir`. `want_dir` selects `rmdir` semantics.
+    pub(crate) fn unlink(&mut self, dir: Ino, name: &str, want_dir: bool) -> VfsResult<()> {
+        match name {
+            "." => return Err(-(libc::EINVAL as isize)),
+            ".." => return Err(-(libc::ENOTEMPTY as isize)),
+            _ => {}
+        }
+        let ino = self.child(dir, name)?;
+        if self.node(dir).readonly {
+            return Err(-(libc::EACCES as isize));
+        }
+        let node = self.node(ino);
+        match (want_dir, node.is_dir()) {
+            (true, false) => return Err(-(libc::ENOTDIR as isize)),
+            (false, true) => return Err(-(libc::EISDIR as isize)),
+            (true, true) if !self.entries(ino)?.is_empty() => {
+                return Err(-(libc::ENOTEMPTY as isize))
+            }
+            _ => {}
+        }
+        self.entries_mut(dir)?.retain(|&(_, i)| i != ino);
+        if want_dir {
+            self.node_mut(dir).nlink -= 1;
+            self.node_mut(ino).nlink = 0;
+        } else {
+            self.node_mut(ino).nlink -= 1;
+        }
+        self.maybe_free(ino);
+        Ok(())
+    }
+
+    /// Whether `ino` is `ancestor` or lies below it.
+    fn is_within(&self, mut ino: Ino, ancestor: Ino) -> bool {
+        loop {
+            if ino == ancestor {
+                return true;
+            }
+            if ino == ROOT {
+                return false;
+            }
+            ino = self.parent(ino);
+        }
+    }
+
+    fn set_parent(&mut self, ino: Ino, new_parent: Ino) {
+        if let Kind::Dir { parent, .. } = &mut self.node_mut(ino).kind {
+            *parent = new_parent;
+        }
+    }
+
+    fn replace_entry(&mut self, dir: Ino, name: &str, ino: Ino) -> VfsResult<()> {
+        if let Some(e) = self.entries_mut(dir)?.iter_mut().find(|(n, _)| n == name) {
+            e.1 = ino;
+        }
+        Ok(())
+    }
+
+    /// `renameat2` with `RENAME_NOREPLACE` / `RENAME_EXCHANGE` support.
+    pub(crate) fn rename(
+        &mut self,
+        (old_dir, old_name): (Ino, &str),
+        (new_dir, new_name): (Ino, &str),
+        flags: u32,
+    ) -> VfsResult<()> {
+        const RENAME_NOREPLACE: u32 = 1;
+        const RENAME_EXCHANGE: u32 = 2;
+
+        if flags & !(RENAME_NOREPLACE | RENAME_EXCHANGE) != 0
+            || flags == RENAME_NOREPLACE | RENAME_EXCHANGE
+        {
+            return Err(-(libc::EINVAL as isize));
+        }
+        if matches!(old_name, "." | "..") || matches!(new_name, "." | "..") {
+            return Err(-(libc::EBUSY as isize));
+        }
+        let src = self.child(old_dir, old_name)?;
+        let dst = self.child(new_dir, new_name).ok();
+        if self.node(old_dir).readonly || self.node(new_dir).readonly {
+            return Err(-(libc::EACCES as isize));
+        }
+
+        if flags & RENAME_EXCHANGE != 0 {
+            let dst = dst.ok_or(-(libc::ENOENT as isize))?;
+            if (self.node(src).is_dir() && self.is_within(new_dir, src))
+                || (self.node(dst).is_dir() && self.is_within(old_dir, dst))
+            {
+                return Err(-(libc::EINVAL as isize));
+            }
+            self.replace_entry(old_dir, old_name, dst)?;
+            self.replace_entry(new_dir, new_name, src)?;
+            self.set_parent(src, new_dir);
+            self.set_parent(dst, old_dir);
+            return Ok(());
+        }
+
+        let src_is_dir = self.node(src).is_dir();
+        if src_is_dir && self.is_within(new_dir, src) {
+            return Err(-(libc::EINVAL as isize));
+        }
+        if let Some(dst) = dst {
+            if flags & RENAME_NOREPLACE != 0 {
+                return Err(-(libc::EEXIST as isize));
+            }
+            if dst == src {
+                return Ok(());
+            }
+            let dst_is_dir = self.node(dst).is_dir();
+            match (src_is_dir, dst_is_dir) {
+                (true, false) => return Err(-(libc::ENOTDIR as isize)),
+                (false, true) => return Err(-(libc::EISDIR as isize)),
+                _ => {}
+            }
+            self.unlink(new_dir, new_name, dst_is_dir)?;
+        }
+
+        self.entries_mut(old_dir)?.retain(|&(_, i)| i != src);
+        self.entries_mut(new_dir)?
+            .push((String::from(new_name), src));
+        if src_is_dir {
+            self.node_mut(old_dir).nlink -= 1;
+            self.node_mut(new_dir).nlink += 1;
+            self.set_parent(src, new_dir);
+        }
+        Ok(())
+    }
+
+    /// `(name, ino)` of directory entry `index`, counting `.` and `..` first.
+    pub(crate) fn dir_entry(&self, dir: Ino, index: usize) -> Option<(&str, Ino)> {
+        match index {
+            0 => Some((".", dir)),
+            1 => Some(("..", self.parent(dir))),
+            _ => self
+                .entries(dir)
+                .ok()?
+                .get(index - 2)
+                .map(|(n, i)| (n.as_str(), *i)),
+        }
+    }
+
+    pub(crate) fn opened(&mut self, ino: Ino) {
+        self.node_mut(ino).open += 1;
+    }
+
+    pub(crate) fn closed(&mut self, ino: Ino) {
+        self.node_mut(ino).open -= 1;
+        self.maybe_free(ino);
+    }
+
+    /// Set the length of a regular file, zero-filling any extension.
+    pub(crate) fn truncate(&mut self, ino: Ino, len: usize) -> VfsResult<()> {
+        match &mut self.node_mut(ino).kind {
+            Kind::File { data } => data.resize(len),
+            Kind::Dir { .. } => Err(-(libc::EISDIR as isize)),
+            Kind::Generated(_) => Err(-(libc::EACCES as isize)),
+            Kind::Symlink(_) => Err(-(libc::EINVAL as isize)),
+        }
+    }
+
+    /// Append the target of symlink `ino` to `target`.
+    pub(crate) fn readlink(&self, ino: Ino, target: &mut String) -> VfsResult<()> {
+        match self.node(ino).kind {
+            Kind::Symlink(generate) => {
+                generate(target);
+                Ok(())
+            }
+            _ => Err(-(libc::EINVAL as isize)),
+        }
+    }
+
+    /// Open `ino` for a descriptor.
+    fn open(&mut self, ino: Ino, flags: i32) -> VfsResult<FdEntry> {
+        let writable = flags & libc::O_ACCMODE != libc::O_RDONLY;
+        let ops = match &self.node(ino).kind {
+            Kind::Dir { .. } => &DIR_FOPS,
+            Kind::File { .. } => {
+                if writable && flags & libc::O_TRUNC != 0 {
+                    self.truncate(ino, 0)?;
+                }
+                &FILE_FOPS
+            }
+            Kind::Generated(generate) => return procfs::open(self, ino, *generate, flags),
+            Kind::Symlink(_) => return Err(-(libc::ELOOP as isize)),
+        };
+        Ok(FdEntry {
+            ops,
+            private_data: OpenFile::open(self, ino, flags),
+        })
+    }
+
+    pub(crate) fn stat(&self, ino: Ino, st: &mut libc::stat) {
+        let node = self.node(ino);
+        let size = match &node.kind {
+            Kind::File { data } => data.len(),
+            Kind::Symlink(target) => {
+                let mut path = String::new();
+                target(&mut path);
+                path.len()
+            }
+            _ => 0,
+        };
+        *st = unsafe { core::mem::zeroed() };
+        st.st_dev = RAMFS_DEV as _;
+        st.st_ino = (ino + 1) as _;
+        st.st_mode = (node.file_type() | node.mode) as _;
+        st.st_nlink = node.nlink as _;
+        st.st_size = size as _;
+        st.st_blksize = 4096 as _;
+        st.st_blocks = size.div_ceil(512) as _;
+    }
+}
+
+/// Per-descriptor state of an open ramfs file or directory (`FdEntry::private_data`).
+pub(crate) struct OpenFile {
+    pub(crate) ino: Ino,
+    /// Byte offset for files, entry index for directories.
+    pos: usize,
+    flags: i32,
+}
+
+impl OpenFile {
+    /// Box a new open-file record and count it against `ino`.
+    pub(crate) fn open(fs: &mut RamFs, ino: Ino, flags: i32) -> *mut u8 {
+        fs.opened(ino);
+        Box::into_raw(Box::new(OpenFile { ino, pos: 0, flags })) as *mut u8
+    }
+}
+
+fn open_file<'a>(file: *mut u8) -> &'a mut OpenFile {
+    unsafe { &mut *(file as *mut OpenFile) }
+}
+
+fn can_read(f: &OpenFile) -> bool {
+    f.flags & libc::O_ACCMODE != libc::O_WRONLY
+}
+
+fn can_write(f: &OpenFile) -> bool {
+    f.flags & libc::O_ACCMODE != libc::O_RDONLY
+}
+
+fn file_read(file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    let f = open_file(file);
+    let n = file_pread(file, buf, count, f.pos);
+    if n > 0 {
+        f.pos += n as usize;
+    }
+    n
+}
+
+fn file_pread(file: *mut u8, buf: *mut u8, count: usize, offset: usize) -> isize {
+    let f = open_file(file);
+    if !can_read(f) {
+        return -(libc::EBADF as isize);
+    }
+    with_fs(|fs| {
+        let Kind::File { data } = &fs.node(f.ino).kind else {
+            return -(libc::EISDIR as isize);
+        };
+        let n = data.len().saturating_sub(offset).min(count);
+        if n > 0 {
+            unsafe { core::ptr::copy_nonoverlapping(data.as_ptr().add(offset), buf, n) };
+        }
+        n as isize
+    })
+}
+
+fn file_write(file: *mut u8, buf: *const u8, count: usize) -> isize {
+    let f = open_file(file);
+    if f.flags & libc::O_APPEND != 0 {
+        f.pos = with_fs(|fs| match &fs.node(f.ino).kind {
+            Kind::File { data } => data.len(),
+            _ => f.pos,
+        });
+    }
+    let n = file_pwrite(file, buf, count, f.pos);
+    if n > 0 {
+        f.pos += n as usize;
+    }
+    n
+}
+
+fn file_pwrite(file: *mut u8, buf: *const u8, count: usize, offset: usize) -> isize {
+    let f = open_file(file);
+    if !can_write(f) {
+        return -(libc::EBADF as isize);
+    }
+    let Some(end) = offset.checked_add(count) else {
+        return -(libc::EFBIG as isize);
+    };
+    with_fs(|fs| {
+        let Kind::File { data } = &mut fs.node_mut(f.ino).kind else {
+            return -(libc::EISDIR as isize);
+        };
+        if data.len() < end {
+            if let Err(e) = data.resize(end) {
+                return e;
+            }
+        }
+        unsafe { core::ptr::copy_nonoverlapping(buf, data.as_mut_ptr().add(offset), count) };
+        count as isize
+    })
+}
+
+fn file_llseek(file: *mut u8, offset: isize, whence: i32) -> isize {
+    let f = open_file(file);
+    let base = match whence {
+        libc::SEEK_SET => 0,
+        libc::SEEK_CUR => f.pos as isize,
+        libc::SEEK_END => with_fs(|fs| match &fs.node(f.ino).kind {
+            Kind::File { data } => data.len() as isize,
+            _ => 0,
+        }),
+        _ => return -(libc::EINVAL as isize),
+    };
+    match base.checked_add(offset) {
+        Some(pos) if pos >= 0 => {
+            f.pos = pos as usize;
+            pos
+        }
+        _ => -(libc::EINVAL as isize),
+    }
+}
+
+/// `ftruncate` on an open regular file.
+pub(crate) fn ftruncate(file: *mut u8, len: usize) -> VfsResult<()> {
+    let f = open_file(file);
+    if !can_write(f) {
+        return Err(-(libc::EINVAL as isize));
+    }
+    with_fs(|fs| fs.truncate(f.ino, len))
+}
+
+/// Share the file's storage; the node stays alive until the mapping is released.
+fn file_mmap(file: *mut u8, offset: usize, len: usize, write: bool) -> VfsResult<SharedMapping> {
+    let f = open_file(file);
+    if !can_read(f) || (write && !can_write(f)) {
+        return Err(-(libc::EACCES as isize));
+    }
+    with_fs(|fs| {
+        let Kind::File { data } = &mut fs.node_mut(f.ino).kind else {
+            return Err(-(libc::ENODEV as isize));
+        };
+        let addr = data.map(offset, len)?;
+        fs.opened(f.ino);
+        Ok(SharedMapping {
+            addr,
+            token: f.ino,
+            unmap: file_unmap,
+        })
+    })
+}
+
+fn file_unmap(ino: usize) {
+    with_fs(|fs| {
+        if let Kind::File { data } = &mut fs.node_mut(ino).kind {
+            data.unmap();
+        }
+        fs.closed(ino);
+    });
+}
+
+fn file_release(file: *mut u8) -> isize {
+    let f = unsafe { Box::from_raw(file as *mut OpenFile) };
+    with_fs(|fs| fs.closed(f.ino));
+    0
+}
+
+fn file_fstat(file: *mut u8, st: *mut libc::stat) -> isize {
+    let f = open_file(file);
+    with_fs(|fs| fs.stat(f.ino, unsafe { &mut *st }));
+    0
+}
+
+pub(crate) fn dir_read(_file: *mut u8, _buf: *mut u8, _count: usize) -> isize {
+    -(libc::EISDIR as isize)
+}
+
+pub(crate) fn dir_write(_file: *mut u8, _buf: *const u8, _count: usize) -> isize {
+    -(libc::EBADF as isize)
+}
+
+pub(crate) fn dir_pread(_file: *mut u8, _buf: *mut u8, _count: usize, _offset: usize) -> isize {
+    -(libc::EISDIR as isize)
+}
+
+pub(crate) fn dir_pwrite(_file: *mut u8, _buf: *const u8, _count: usize, _offset: usize) -> isize {
+    -(libc::EBADF as isize)
+}
+
+/// Directories only support rewinding (`rewinddir`) and restoring a `d_off` (`seekdir`).
+fn dir_llseek(file: *mut u8, offset: isize, whence: i32) -> isize {
+    if whence != libc::SEEK_SET || offset < 0 {
+        return -(libc::EINVAL as isize);
+    }
+    open_file(file).pos = offset as usize;
+    offset
+}
+
+/// Fill `buf` with `struct linux_dirent64` records.
+fn dir_getdents64(file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    let f = open_file(file);
+    with_fs(|fs| {
+        fill_dirents(buf, count, &mut f.pos, |index| {
+            let (name, ino) = fs.dir_entry(f.ino, index)?;
+            Some((name, ino, fs.node(ino).dirent_type()))
+        })
+    })
+}
+
+pub(crate) static FILE_FOPS: FileOps = FileOps {
+    read: file_read,
+    write: file_write,
+    release: file_release,
+    llseek: file_llseek,
+    ioctl: noop_ioctl,
+    fstat: file_fstat,
+    getdents64: noop_getdents64,
+    pread: Some(file_pread),
+    pwrite: Some(file_pwrite),
+    poll: poll_ready,
+    mmap: Some(file_mmap),
+};
+
+pub(crate) static DIR_FOPS: FileOps = FileOps {
+    read: dir_read,
+    write: dir_write,
+    release: file_release,
+    llseek: dir_llseek,
+    ioctl: noop_ioctl,
+    fstat: file_fstat,
+    getdents64: dir_getdents64,
+    pread: Some(dir_pread),
+    pwrite: Some(dir_pwrite),
+    poll: poll_readable,
+    mmap: None,
+};
+
+/// The ramfs as a [`FileSystem`], for the mount table.
+pub(crate) struct RootFs;
+
+pub(crate) static ROOTFS: RootFs = RootFs;
+
+impl FileSystem for RootFs {
+    fn root(&self) -> Ino {
+        ROOT
+    }
+
+    fn lookup(&self, dir: Ino, name: &str) -> VfsResult<Ino> {
+        with_fs(|fs| fs.child(dir, name))
+    }
+
+    fn create(&self, dir: Ino, name: &str, mode: u32) -> VfsResult<Ino> {
+        with_fs(|fs| fs.create_file(dir, name, mode))
+    }
+
+    fn unlink(&self, dir: Ino, name: &str, want_dir: bool) -> VfsResult<()> {
+        with_fs(|fs| fs.unlink(dir, name, want_dir))
+    }
+
+    fn readdir(&self, dir: Ino, index: usize) -> VfsResult<Option<DirEntry>> {
+        with_fs(|fs| {
+            fs.entries(dir)?;
+            Ok(fs.dir_entry(dir, index).map(|(name, ino)| DirEntry {
+                name: String::from(name),
+                ino,
+                d_type: fs.node(ino).dirent_type(),
+            }))
+        })
+    }
+
+    fn stat(&self, ino: Ino, st: &mut libc::stat) -> VfsResult<()> {
+        with_fs(|fs| fs.stat(ino, st));
+        Ok(())
+    }
+
+    fn open(&'static self, ino: Ino, flags: i32, _mode: u32) -> VfsResult<FdEntry> {
+        with_fs(|fs| fs.open(ino, flags))
+    }
+
+    fn mkdir(&self, dir: Ino, name: &str, mode: u32) -> VfsResult<Ino> {
+        with_fs(|fs| fs.mkdir(dir, name, mode))
+    }
+
+    fn readlink(&self, ino: Ino, target: &mut String) -> VfsResult<()> {
+        with_fs(|fs| fs.readlink(ino, target))
+    }
+
+    fn rename(&self, old: (Ino, &str), new: (Ino, &str), flags: u32) -> VfsResult<()> {
+        with_fs(|fs| fs.rename(old, new, flags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fresh() -> RamFs {
+        let mut fs = RamFs::new();
+        fs.init_root();
+        fs
+    }
+
+    #[test]
+    fn test_lookup_and_parent() {
+        let mut fs = fresh();
+        let a = fs.mkdir(ROOT, "a", 0o755).unwrap();
+        let b = fs.mkdir(a, "b", 0o755).unwrap();
+        let f = fs.create_file(b, "f", 0o644).unwrap();
+        assert_eq!(fs.lookup(ROOT, "/a/b/f"), Ok(f));
+        assert_eq!(fs.lookup(b, "../b/./f"), Ok(f));
+        assert_eq!(fs.lookup(b, "f/"), Err(-(libc::ENOTDIR as isize)));
+        assert_eq!(fs.lookup(ROOT, "/.."), Ok(ROOT));
+        assert_eq!(fs.parent(b), a);
+        assert_eq!(fs.parent(ROOT), ROOT);
+    }
+
+    #[test]
+    fn test_unlink_rules() {
+        let mut fs = fresh();
+        let a = fs.mkdir(ROOT, "a", 0o755).unwrap();
+        fs.create_file(a, "f", 0o644).unwrap();
+        assert_eq!(fs.unlink(ROOT, "a", true), Err(-(libc::ENOTEMPTY as isize)));
+        assert_eq!(fs.unlink(a, "f", true), Err(-(libc::ENOTDIR as isize)));
+        assert_eq!(fs.unlink(ROOT, "a", false), Err(-(libc::EISDIR as isize)));
+        fs.unlink(a, "f", false).unwrap();
+        fs.unlink(ROOT, "a", true).unwrap();
+        assert_eq!(fs.node(ROOT).nlink, 2);
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut fs = fresh();
+        let a = fs.mkdir(ROOT, "a", 0o755).unwrap();
+        let b = fs.mkdir(ROOT, "b", 0o755).unwrap();
+        let f = fs.create_file(a, "f", 0o644).unwrap();
+        fs.rename((a, "f"), (b, "g"), 0).unwrap();
+        assert_eq!(fs.lookup(ROOT, "/b/g"), Ok(f));
+        assert_eq!(
+            fs.rename((ROOT, "a"), (a, "x"), 0),
+            Err(-(libc::EINVAL as isize))
+        );
+        fs.rename((ROOT, "a"), (b, "a"), 0).unwrap();
+        assert_eq!(fs.lookup(ROOT, "/b/a"), Ok(a));
+        assert_eq!(fs.parent(a), b);
+        assert_eq!(fs.node(b).nlink, 3);
+    }
+}
@@ -0,0 +1,743 @@
+//! Loopback sockets: `AF_UNIX` streams and an in-kernel `127.0.0.1` (`AF_INET`) stack.
+//!
+//! Only `SOCK_STREAM` is supported and nothing leaves the guest. A connection is a pair of byte
+//! queues shared by its two ends; `connect` creates it as soon as a listener is bound to the
+//! address and queues the server end for `accept`, so every exchange is deterministic.
+//! Blocking follows pipes: callers sleep on the kernel readiness generation
+//! (`kfn::vfs::kpoll_wait`) and get `EAGAIN` when no other thread could make progress.
+//!
+//! Any `127.0.0.0/8` or wildcard address means the loopback interface; ports are the only
+//! `AF_INET` namespace. Pathname `AF_UNIX` addresses live in their own namespace next to
+//! abstract ones: binding does not create a node in the filesystem.
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use foundation::kfn::vfs::{kpoll_notify, kpoll_seq, kpoll_wait};
+use foundation::utils::KernelLock;
+
+use crate::{fill_stat, noop_getdents64, noop_seek, FdEntry, FileOps, VfsResult};
+
+/// Bytes queued in each direction of a connection.
+const BUF_CAPACITY: usize = 64 * 1024;
+
+/// Ports handed out to sockets that connect or bind to port 0 (the IANA dynamic range).
+const EPHEMERAL_PORTS: core::ops::RangeInclusive<u16> = 49152..=65535;
+
+/// `sizeof(struct sockaddr_in)`.
+const SOCKADDR_IN_LEN: usize = 16;
+
+#[derive(Clone, PartialEq, Eq)]
+enum Addr {
+    /// `AF_UNIX` pathname, or abstract name (including its leading NUL).
+    Unix(Vec<u8>),
+    /// `AF_INET` address (network byte order) and port.
+    Inet([u8; 4], u16),
+}
+
+impl Addr {
+    /// Whether binding `self` conflicts with an existing binding to `other`.
+    fn clashes(&self, other: &Addr) -> bool {
+        match (self, other) {
+            (Addr::Inet(_, a), Addr::Inet(_, b)) => a == b,
+            _ => self == other,
+        }
+    }
+}
+
+/// The two byte queues of a connection; end `i` reads `buf[i]` and writes `buf[1 - i]`.
+struct Link {
+    buf: [VecDeque<u8>; 2],
+    /// End `i` stopped reading (`SHUT_RD` or closed).
+    rd_shut: [bool; 2],
+    /// End `i` stopped writing (`SHUT_WR` or closed).
+    wr_shut: [bool; 2],
+    ends: u32,
+}
+
+enum State {
+    Idle,
+    Listening {
+        backlog: usize,
+        /// Connected server ends waiting for `accept`.
+        pending: VecDeque<*mut Socket>,
+    },
+    Connected {
+        link: *mut Link,
+        end: usize,
+        /// `None` for an unbound `AF_UNIX` peer.
+        peer: Option<Addr>,
+    },
+}
+
+pub(crate) struct Socket {
+    domain: i32,
+    nonblock: bool,
+    /// `None` until bound (explicitly, or implicitly by `listen`/`connect` for `AF_INET`).
+    local: Option<Addr>,
+    state: State,
+}
+
+/// Bound sockets, looked up by `connect`.
+static BOUND: KernelLock<Bound> = KernelLock::new(Bound(Vec::new()));
+
+struct Bound(Vec<(Addr, *mut Socket)>);
+
+// Entries point at sockets owned by open descriptors, which `release` unbinds.
+unsafe impl Send for Bound {}
+
+impl core::ops::Deref for Bound {
+    type Target = Vec<(Addr, *mut Socket)>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl core::ops::DerefMut for Bound {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+fn socket<'a>(file: *mut u8) -> &'a mut Socket {
+    unsafe { &mut *(file as *mut Socket) }
+}
+
+fn entry(sock: Socket) -> FdEntry {
+    FdEntry {
+        ops: &SOCKET_FOPS,
+        private_data: Box::into_raw(Box::new(sock)) as *mut u8,
+    }
+}
+
+/// Check `socket`/`socketpair` arguments; returns whether `SOCK_NONBLOCK` was given.
+fn check_type(domain: i32, ty: i32, protocol: i32) -> VfsResult<bool> {
+    let tcp = match domain {
+        libc::AF_UNIX => false,
+        libc::AF_INET => true,
+        _ => return Err(-(libc::EAFNOSUPPORT as isize)),
+    };
+    if ty & !(libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC) != libc::SOCK_STREAM {
+        return Err(-(libc::ESOCKTNOSUPPORT as isize));
+    }
+    if protocol != 0 && !(tcp && protocol == libc::IPPROTO_TCP) {
+        return Err(-(libc::EPROTONOSUPPORT as isize));
+    }
+    Ok(ty & libc::SOCK_NONBLOCK != 0)
+}
+
+/// Create an unbound socket (`socket(2)`).
+pub(crate) fn create(domain: i32, ty: i32, protocol: i32) -> VfsResult<FdEntry> {
+    let nonblock = check_type(domain, ty, protocol)?;
+    Ok(entry(Socket {
+        domain,
+        nonblock,
+        local: None,
+        state: State::Idle,
+    }))
+}
+
+/// Create a connected pair of unnamed sockets (`socketpair(2)`).
+pub(crate) fn pair(domain: i32, ty: i32, protocol: i32) -> VfsResult<(FdEntry, FdEntry)> {
+    let nonblock = check_type(domain, ty, protocol)?;
+    if domain != libc::AF_UNIX {
+        return Err(-(libc::EOPNOTSUPP as isize));
+    }
+    let link = new_link();
+    let end = |end| {
+        entry(Socket {
+            domain,
+            nonblock,
+            local: None,
+            state: State::Connected {
+                link,
+                end,
+                peer: None,
+            },
+        })
+    };
+    Ok((end(0), end(1)))
+}
+
+/// The socket behind `entry`, if it is one.
+pub(crate) fn instance<'a>(entry: &FdEntry) -> Option<&'a mut Socket> {
+    if core::ptr::eq(entry.ops, &SOCKET_FOPS) {
+        Some(socket(entry.private_data))
+    } else {
+        None
+    }
+}
+
+fn new_link() -> *mut Link {
+    Box::into_raw(Box::new(Link {
+        buf: [VecDeque::new(), VecDeque::new()],
+        rd_shut: [false; 2],
+        wr_shut: [false; 2],
+        ends: 2,
+    }))
+}
+
+fn link_of<'a>(link: *mut Link) -> &'a mut Link {
+    unsafe { &mut *link }
+}
+
+/// Lowest free ephemeral port.
+fn ephemeral_port(bound: &[(Addr, *mut Socket)]) -> VfsResult<u16> {
+    EPHEMERAL_PORTS
+        .into_iter()
+        .find(|&p| !bound.iter().any(|(a, _)| a.clashes(&Addr::Inet([0; 4], p))))
+        .ok_or(-(libc::EADDRINUSE as isize))
+}
+
+impl Socket {
+    /// Parse a `struct sockaddr` of this socket's family.
+    fn parse(&self, addr: &[u8]) -> VfsResult<Addr> {
+        if addr.len() < 2 {
+            return Err(-(libc::EINVAL as isize));
+        }
+        let family = u16::from_ne_bytes([addr[0], addr[1]]) as i32;
+        match self.domain {
+            libc::AF_INET if family != libc::AF_INET => Err(-(libc::EAFNOSUPPORT as isize)),
+            libc::AF_INET if addr.len() < SOCKADDR_IN_LEN => Err(-(libc::EINVAL as isize)),
+            libc::AF_INET => Ok(Addr::Inet(
+                [addr[4], addr[5], addr[6], addr[7]],
+                u16::from_be_bytes([addr[2], addr[3]]),
+            )),
+            _ if family != libc::AF_UNIX => Err(-(libc::EINVAL as isize)),
+            _ => {
+                let path = &addr[2..];
+                let name = match path.first() {
+                    None => return Err(-(libc::EINVAL as isize)),
+                    // Abstract names are the whole buffer, NULs included.
+                    Some(0) => path,
+                    Some(_) => path.split(|&b| b == 0).next().unwrap_or(path),
+                };
+                Ok(Addr::Unix(name.to_vec()))
+            }
+        }
+    }
+
+    /// Encode `addr` (or this family's unbound address) as a `struct sockaddr` into `out`;
+    /// returns the full length, which may exceed `out.len()`.
+    fn encode(&self, addr: Option<&Addr>, out: &mut [u8]) -> usize {
+        let mut bytes = Vec::with_capacity(SOCKADDR_IN_LEN);
+        bytes.extend_from_slice(&(self.domain as u16).to_ne_bytes());
+        match addr {
+            Some(Addr::Inet(ip, port)) => {
+                bytes.extend_from_slice(&port.to_be_bytes());
+                bytes.extend_from_slice(ip);
+            }
+            Some(Addr::Unix(name)) => {
+                bytes.extend_from_slice(name);
+                if name.first() != Some(&0) {
+                    bytes.push(0);
+                }
+            }
+            None => {}
+        }
+        if self.domain == libc::AF_INET {
+            bytes.resize(SOCKADDR_IN_LEN, 0);
+        }
+        let n = bytes.len().min(out.len());
+        out[..n].copy_from_slice(&bytes[..n]);
+        bytes.len()
+    }
+
+    fn bind_to(&mut self, addr: Addr, bound: &mut Vec<(Addr, *mut Socket)>) -> VfsResult<()> {
+        let addr = match addr {
+            Addr::Inet(ip, 0) => Addr::Inet(ip, ephemeral_port(bound)?),
+            addr => addr,
+        };
+        if bound.iter().any(|(a, _)| a.clashes(&addr)) {
+            return Err(-(libc::EADDRINUSE as isize));
+        }
+        bound.push((addr.clone(), self as *mut Socket));
+        self.local = Some(addr);
+        Ok(())
+    }
+
+    /// Bind to the wildcard address with an ephemeral port, unless already bound.
+    fn autobind(&mut self, bound: &mut Vec<(Addr, *mut Socket)>) -> VfsResult<()> {
+        if self.local.is_none() && self.domain == libc::AF_INET {
+            self.bind_to(Addr::Inet([0; 4], 0), bound)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn bind(&mut self, addr: &[u8]) -> VfsResult<()> {
+        let addr = self.parse(addr)?;
+        if self.local.is_some() {
+            return Err(-(libc::EINVAL as isize));
+        }
+        if let Addr::Inet(ip, _) = addr {
+            if ip != [0; 4] && ip[0] != 127 {
+                return Err(-(libc::EADDRNOTAVAIL as isize));
+            }
+        }
+        BOUND.with_mut(|bound| self.bind_to(addr, bound))
+    }
+
+    pub(crate) fn listen(&mut self, backlog: i32) -> VfsResult<()> {
+        let backlog = backlog.clamp(1, 4096) as usize;
+        match &mut self.state {
+            State::Idle => {
+                if self.local.is_none() && self.domain == libc::AF_UNIX {
+                    return Err(-(libc::EINVAL as isize));
+                }
+                BOUND.with_mut(|bound| self.autobind(bound))?;
+                self.state = State::Listening {
+                    backlog,
+                    pending: VecDeque::new(),
+                };
+                Ok(())
+            }
+            State::Listening { backlog: b, .. } => {
+                *b = backlog;
+                Ok(())
+            }
+            State::Connected { .. } => Err(-(libc::EINVAL as isize)),
+        }
+    }
+
+    /// Take the next queued connection without blocking (`EAGAIN` if there is none).
+    pub(crate) fn accept(&mut self, flags: i32) -> VfsResult<FdEntry> {
+        let State::Listening { pending, .. } = &mut self.state else {
+            return Err(-(libc::EINVAL as isize));
+        };
+        let sock = pending.pop_front().ok_or(-(libc::EAGAIN as isize))?;
+        socket(sock as *mut u8).nonblock = flags & libc::SOCK_NONBLOCK != 0;
+        kpoll_notify();
+        Ok(FdEntry {
+            ops: &SOCKET_FOPS,
+            private_data: sock as *mut u8,
+        })
+    }
+
+    /// Connect to a listener, queueing the server end for `accept`; `EAGAIN` while the
+    /// listener's backlog is full.
+    pub(crate) fn connect(&mut self, addr: &[u8]) -> VfsResult<()> {
+        let addr = self.parse(addr)?;
+        match self.state {
+            State::Idle => {}
+            State::Connected { .. } => return Err(-(libc::EISCONN as isize)),
+            State::Listening { .. } => return Err(-(libc::EINVAL as isize)),
+        }
+        if let Addr::Inet(ip, _) = addr {
+            if ip != [0; 4] && ip[0] != 127 {
+                return Err(-(libc::ENETUNREACH as isize));
+            }
+        }
+        BOUND.with_mut(|bound| {
+            let Some(&(_, listener)) = bound.iter().find(|(a, _)| a.clashes(&addr)) else {
+                return Err(match addr {
+                    Addr::Unix(_) => -(libc::ENOENT as isize),
+                    Addr::Inet(..) => -(libc::ECONNREFUSED as isize),
+                });
+            };
+            let listener = unsafe { &mut *listener };
+            let State::Listening { backlog, pending } = &mut listener.state else {
+                return Err(-(libc::ECONNREFUSED as isize));
+            };
+            if pending.len() >= *backlog {
+                return Err(-(libc::EAGAIN as isize));
+            }
+            self.autobind(bound)?;
+
+            let link = new_link();
+            let server = Box::new(Socket {
+                domain: self.domain,
+                nonblock: false,
+                local: listener.local.clone(),
+                state: State::Connected {
+                    link,
+                    end: 1,
+                    peer: self.local.clone(),
+                },
+            });
+            pending.push_back(Box::into_raw(server));
+            self.state = State::Connected {
+                link,
+                end: 0,
+                peer: listener.local.clone(),
+            };
+            kpoll_notify();
+            Ok(())
+        })
+    }
+
+    pub(crate) fn shutdown(&mut self, how: i32) -> VfsResult<()> {
+        let (rd, wr) = match how {
+            libc::SHUT_RD => (true, false),
+            libc::SHUT_WR => (false, true),
+            libc::SHUT_RDWR => (true, true),
+            _ => return Err(-(libc::EINVAL as isize)),
+        };
+        let State::Connected { link, end, .. } = self.state else {
+            return Err(-(libc::ENOTCONN as isize));
+        };
+        let link = link_of(link);
+        link.rd_shut[end] |= rd;
+        link.wr_shut[end] |= wr;
+        kpoll_notify();
+        Ok(())
+    }
+
+    /// Local (or, with `peer`, remote) address as a `struct sockaddr`; returns its full length.
+    pub(crate) fn name(&self, peer: bool, out: &mut [u8]) -> VfsResult<usize> {
+        if !peer {
+            return Ok(self.encode(self.local.as_ref(), out));
+        }
+        match &self.state {
+            State::Connected { peer, .. } => Ok(self.encode(peer.as_ref(), out)),
+            _ => Err(-(libc::ENOTCONN as isize)),
+        }
+    }
+
+    /// `SOL_SOCKET` options callers commonly read; everything else is `ENOPROTOOPT`.
+    pub(crate) fn getsockopt(&self, level: i32, name: i32) -> VfsResult<i32> {
+        match (level, name) {
+            (libc::SOL_SOCKET, libc::SO_TYPE) => Ok(libc::SOCK_STREAM),
+            (libc::SOL_SOCKET, libc::SO_DOMAIN) => Ok(self.domain),
+            (libc::SOL_SOCKET, libc::SO_PROTOCOL) if self.domain == libc::AF_INET => {
+                Ok(libc::IPPROTO_TCP)
+            }
+            (libc::SOL_SOCKET, libc::SO_PROTOCOL) => Ok(0),
+            (libc::SOL_SOCKET, libc::SO_ERROR) => Ok(0),
+            (libc::SOL_SOCKET, libc::SO_ACCEPTCONN) => {
+                Ok(matches!(self.state, State::Listening { .. }) as i32)
+            }
+            (libc::SOL_SOCKET, libc::SO_SNDBUF | libc::SO_RCVBUF) => Ok(BUF_CAPACITY as i32),
+            _ => Err(-(libc::ENOPROTOOPT as isize)),
+        }
+    }
+
+    /// Accept the options that only tune what loopback does not have (address reuse, buffer
+    /// sizes, keepalive, Nagle, lingering), without effect. Any other option fails with
+    /// `ENOPROTOOPT` rather than appear to take effect.
+    pub(crate) fn setsockopt(&self, level: i32, name: i32, optlen: usize) -> VfsResult<()> {
+        let size = match (level, name) {
+            (
+                libc::SOL_SOCKET,
+                libc::SO_REUSEADDR
+                | libc::SO_REUSEPORT
+                | libc::SO_KEEPALIVE
+                | libc::SO_SNDBUF
+                | libc::SO_RCVBUF,
+            ) => core::mem::size_of::<i32>(),
+            (libc::SOL_SOCKET, libc::SO_LINGER) => core::mem::size_of::<libc::linger>(),
+            (
+                libc::IPPROTO_TCP,
+                libc::TCP_NODELAY | libc::TCP_KEEPIDLE | libc::TCP_KEEPINTVL | libc::TCP_KEEPCNT,
+            ) if self.domain == libc::AF_INET => core::mem::size_of::<i32>(),
+            _ => return Err(-(libc::ENOPROTOOPT as isize)),
+        };
+        if optlen < size {
+            return Err(-(libc::EINVAL as isize));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn nonblock(&self) -> bool {
+        self.nonblock
+    }
+}
+
+/// Receive into `buf` (`recvfrom(2)` without an address); honours `MSG_DONTWAIT`/`MSG_PEEK`.
+pub(crate) fn recv(file: *mut u8, buf: *mut u8, count: usize, flags: i32) -> isize {
+    if flags & libc::MSG_OOB != 0 {
+        return -(libc::EOPNOTSUPP as isize);
+    }
+    loop {
+        let seq = kpoll_seq();
+        let sock = socket(file);
+        let State::Connected { link, end, .. } = sock.state else {
+            return -(libc::ENOTCONN as isize);
+        };
+        if count == 0 {
+            return 0;
+        }
+        let link = link_of(link);
+        let queue = &mut link.buf[end];
+        if !queue.is_empty() {
+            let n = count.min(queue.len());
+            for (i, &b) in queue.iter().take(n).enumerate() {
+                unsafe { buf.add(i).write(b) };
+            }
+            if flags & libc::MSG_PEEK == 0 {
+                queue.drain(..n);
+                kpoll_notify();
+            }
+            return n as isize;
+        }
+        if link.wr_shut[1 - end] || link.rd_shut[end] {
+            return 0;
+        }
+        if sock.nonblock || flags & libc::MSG_DONTWAIT != 0 || !kpoll_wait(seq) {
+            return -(libc::EAGAIN as isize);
+        }
+    }
+}
+
+/// Send `buf` (`sendto(2)` on a connected socket); honours `MSG_DONTWAIT`.
+pub(crate) fn send(file: *mut u8, buf: *const u8, count: usize, flags: i32) -> isize {
+    if flags & libc::MSG_OOB != 0 {
+        return -(libc::EOPNOTSUPP as isize);
+    }
+    let mut sent = 0;
+    loop {
+        let seq = kpoll_seq();
+        let sock = socket(file);
+        let State::Connected { link, end, .. } = sock.state else {
+            return -(libc::ENOTCONN as isize);
+        };
+        let link = link_of(link);
+        if link.wr_shut[end] || link.rd_shut[1 - end] {
+            return if sent > 0 {
+                sent as isize
+            } else {
+                -(libc::EPIPE as isize)
+            };
+        }
+        if sent == count {
+            return sent as isize;
+        }
+        let queue = &mut link.buf[1 - end];
+        let n = (BUF_CAPACITY - queue.len()).min(count - sent);
+        if n > 0 {
+            queue.extend(unsafe { core::slice::from_raw_parts(buf.add(sent), n) });
+            sent += n;
+            kpoll_notify();
+            continue;
+        }
+        if sock.nonblock || flags & libc::MSG_DONTWAIT != 0 || !kpoll_wait(seq) {
+            return if sent > 0 {
+                sent as isize
+            } else {
+                -(libc::EAGAIN as isize)
+            };
+        }
+    }
+}
+
+fn socket_read(file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    recv(file, buf, count, 0)
+}
+
+fn socket_write(file: *mut u8, buf: *const u8, count: usize) -> isize {
+    send(file, buf, count, 0)
+}
+
+/// Close one end of a connection, freeing the link with its last end.
+fn close_end(link: *mut Link, end: usize) {
+    let l = link_of(link);
+    l.rd_shut[end] = true;
+    l.wr_shut[end] = true;
+    l.ends -= 1;
+    if l.ends == 0 {
+        drop(unsafe { Box::from_raw(link) });
+    }
+}
+
+fn socket_release(file: *mut u8) -> isize {
+    let sock = unsafe { Box::from_raw(file as *mut Socket) };
+    if sock.local.is_some() {
+        BOUND.with_mut(|bound| bound.retain(|&(_, s)| !core::ptr::eq(s, file as *mut Socket)));
+    }
+    match sock.state {
+        State::Idle => {}
+        State::Listening { pending, .. } => {
+            // Queued connections are reset: their clients see EOF.
+            for s in pending {
+                socket_release(s as *mut u8);
+            }
+        }
+        State::Connected { link, end, .. } => close_end(link, end),
+    }
+    kpoll_notify();
+    0
+}
+
+/// `FIONBIO` and `FIONREAD`; the argument points to an `int`.
+fn socket_ioctl(file: *mut u8, request: usize, arg: usize) -> isize {
+    const FIONBIO: usize = libc::FIONBIO as usize;
+    const FIONREAD: usize = libc::FIONREAD as usize;
+    let sock = socket(file);
+    match request {
+        FIONBIO => {
+            sock.nonblock = unsafe { (arg as *const i32).read_unaligned() } != 0;
+            0
+        }
+        FIONREAD => {
+            let queued = match sock.state {
+                State::Connected { link, end, .. } => link_of(link).buf[end].len(),
+                _ => 0,
+            };
+            unsafe { (arg as *mut i32).write_unaligned(queued as i32) };
+            0
+        }
+        _ => -(libc::ENOTTY as isize),
+    }
+}
+
+fn socket_poll(file: *mut u8) -> i16 {
+    match socket(file).state {
+        // An unconnected socket has hung up, as in Linux's `tcp_poll`.
+        State::Idle => libc::POLLOUT | libc::POLLHUP,
+        State::Listening { ref pending, .. } if !pending.is_empty() => {
+            libc::POLLIN | libc::POLLRDNORM
+        }
+        State::Listening { .. } => 0,
+        State::Connected { link, end, .. } => {
+            let link = link_of(link);
+            let mut mask = 0;
+            if !link.buf[end].is_empty() || link.wr_shut[1 - end] || link.rd_shut[end] {
+                mask |= libc::POLLIN | libc::POLLRDNORM;
+            }
+            if link.wr_shut[1 - end] {
+                mask |= libc::POLLRDHUP;
+            }
+            if link.wr_shut[end] || link.rd_shut[1 - end] {
+                // Writes fail at once with EPIPE.
+                mask |= libc::POLLOUT | libc::POLLWRNORM;
+                if link.wr_shut[1 - end] {
+                    mask |= libc::POLLHUP;
+                }
+            } else if BUF_CAPACITY - link.buf[1 - end].len() > 0 {
+                mask |= libc::POLLOUT | libc::POLLWRNORM;
+            }
+            mask
+        }
+    }
+}
+
+fn socket_fstat(_file: *mut u8, statbuf: *mut libc::stat) -> isize {
+    fill_stat(statbuf, libc::S_IFSOCK | 0o777, 4096)
+}
+
+static SOCKET_FOPS: FileOps = FileOps {
+    read: socket_read,
+    write: socket_write,
+    release: socket_release,
+    llseek: noop_seek,
+    ioctl: socket_ioctl,
+    fstat: socket_fstat,
+    getdents64: noop_getdents64,
+    pread: None,
+    pwrite: None,
+    poll: socket_poll,
+    mmap: None,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inet(port: u16) -> [u8; SOCKADDR_IN_LEN] {
+        let mut a = [0u8; SOCKADDR_IN_LEN];
+        a[..2].copy_from_slice(&(libc::AF_INET as u16).to_ne_bytes());
+        a[2..4].copy_from_slice(&port.to_be_bytes());
+        a[4..8].copy_from_slice(&[127, 0, 0, 1]);
+        a
+    }
+
+    fn send_all(e: &FdEntry, data: &[u8]) -> isize {
+        send(e.private_data, data.as_ptr(), data.len(), 0)
+    }
+
+    fn recv_some(e: &FdEntry, buf: &mut [u8]) -> isize {
+        recv(
+            e.private_data,
+            buf.as_mut_ptr(),
+            buf.len(),
+            libc::MSG_DONTWAIT,
+        )
+    }
+
+    #[test]
+    fn test_setsockopt_rejects_unimplemented_options() {
+        let (a, b) = pair(libc::AF_UNIX, libc::SOCK_STREAM, 0).unwrap();
+        let sock = socket(a.private_data);
+        assert_eq!(
+            sock.setsockopt(libc::SOL_SOCKET, libc::SO_REUSEADDR, 4),
+            Ok(())
+        );
+        assert_eq!(
+            sock.setsockopt(libc::SOL_SOCKET, libc::SO_REUSEADDR, 2),
+            Err(-(libc::EINVAL as isize))
+        );
+        for (level, name) in [
+            (libc::SOL_SOCKET, libc::SO_RCVTIMEO),
+            (libc::SOL_SOCKET, libc::SO_BINDTODEVICE),
+            // TCP options only apply to `AF_INET` sockets.
+            (libc::IPPROTO_TCP, libc::TCP_NODELAY),
+            (libc::IPPROTO_IP, libc::IP_TTL),
+        ] {
+            assert_eq!(
+                sock.setsockopt(level, name, 16),
+                Err(-(libc::ENOPROTOOPT as isize))
+            );
+        }
+        (a.ops.release)(a.private_data);
+        (b.ops.release)(b.private_data);
+    }
+
+    #[test]
+    fn test_socketpair() {
+        let (a, b) = pair(libc::AF_UNIX, libc::SOCK_STREAM, 0).unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(recv_some(&b, &mut buf), -(libc::EAGAIN as isize));
+        assert_eq!(send_all(&a, b"ping"), 4);
+        assert_ne!((b.ops.poll)(b.private_data) & libc::POLLIN, 0);
+        assert_eq!(recv_some(&b, &mut buf), 4);
+        assert_eq!(&buf[..4], b"ping");
+
+        (a.ops.release)(a.private_data);
+        assert_eq!(recv_some(&b, &mut buf), 0, "peer closed: EOF");
+        assert_eq!(send_all(&b, b"x"), -(libc::EPIPE as isize));
+        (b.ops.release)(b.private_data);
+    }
+
+    #[test]
+    fn test_loopback_connect_accept() {
+        let server = create(libc::AF_INET, libc::SOCK_STREAM, 0).unwrap();
+        let listener = instance(&server).unwrap();
+        listener.bind(&inet(0)).unwrap();
+        listener.listen(1).unwrap();
+        let mut name = [0u8; SOCKADDR_IN_LEN];
+        assert_eq!(listener.name(false, &mut name), Ok(SOCKADDR_IN_LEN));
+        let port = u16::from_be_bytes([name[2], name[3]]);
+        assert!(EPHEMERAL_PORTS.contains(&port));
+        assert_eq!(
+            listener.accept(0).err(),
+            Some(-(libc::EAGAIN as isize)),
+            "nothing queued yet"
+        );
+
+        let client = create(libc::AF_INET, libc::SOCK_STREAM, 0).unwrap();
+        assert_eq!(
+            instance(&client).unwrap().connect(&inet(1)),
+            Err(-(libc::ECONNREFUSED as isize))
+        );
+        assert_eq!(instance(&client).unwrap().connect(&inet(port)), Ok(()));
+        let conn = listener.accept(0).unwrap();
+
+        let mut peer = [0u8; SOCKADDR_IN_LEN];
+        instance(&client).unwrap().name(true, &mut peer).unwrap();
+        assert_eq!(peer, name);
+
+        assert_eq!(send_all(&client, b"GET /"), 5);
+        let mut buf = [0u8; 8];
+        assert_eq!(recv_some(&conn, &mut buf), 5);
+        assert_eq!(&buf[..5], b"GET /");
+
+        instance(&conn).unwrap().shutdown(libc::SHUT_WR).unwrap();
+        assert_eq!(recv_some(&client, &mut buf), 0);
+
+        for e in [client, conn, server] {
+            (e.ops.release)(e.private_data);
+        }
+        assert!(BOUND.with(|b| b.is_empty()));
+    }
+}
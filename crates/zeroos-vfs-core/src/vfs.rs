@@ -1,12 +1,78 @@
-use crate::{DeviceFactory, Fd, FdEntry, VfsResult};
-use foundation::utils::GlobalCell;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 
-const MAX_FDS: usize = 256;
+use crate::devfs::{Opener, DEVFS, DEV_MOUNT};
+use crate::mount::{join, normalize, FileSystem, MountTable, Vnode};
+use crate::ramfs::{self, with_fs, OpenFile, FILE_FOPS, ROOTFS};
+use crate::socket::{self, Socket};
+use crate::{epoll, eventfd, pipe, procfs};
+use crate::{DeviceFactory, DeviceOpenFn, Fd, FdEntry, SharedMapping, VfsResult};
+use foundation::kfn::vfs::{kpoll_seq, kpoll_wait};
+use foundation::utils::KernelLock;
+
+pub use foundation::ops::MAX_FDS;
+
+/// Deepest chain of epoll instances watching each other (Linux's `EP_MAX_NESTS`).
+const EPOLL_MAX_NESTS: usize = 4;
+
+/// An open file description; `release` runs when the last reference is dropped.
+///
+/// Reads and writes run on a reference taken out of the table, after the VFS lock is dropped,
+/// so a descriptor closed while another thread is blocked on it stays valid until that call
+/// returns.
+struct File(FdEntry);
+
+impl File {
+    /// Release the file now, returning the result of its `release`.
+    fn release(self) -> isize {
+        let entry = self.0;
+        core::mem::forget(self);
+        (entry.ops.release)(entry.private_data)
+    }
+}
+
+// Descriptors are shared by every thread of the process; file ops already run on any of them.
+unsafe impl Send for File {}
+unsafe impl Sync for File {}
+
+impl core::ops::Deref for File {
+    type Target = FdEntry;
+
+    fn deref(&self) -> &FdEntry {
+        &self.0
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        (self.0.ops.release)(self.0.private_data);
+    }
+}
+
+struct OpenFd {
+    entry: Arc<File>,
+    /// Canonical path of a directory descriptor, for `*at` calls and `fchdir`.
+    dir: Option<String>,
+}
+
+impl OpenFd {
+    fn new(entry: FdEntry, dir: Option<String>) -> Self {
+        Self {
+            entry: Arc::new(File(entry)),
+            dir,
+        }
+    }
+}
 
 pub struct Vfs {
-    fd_table: [Option<FdEntry>; MAX_FDS],
+    fd_table: [Option<OpenFd>; MAX_FDS],
     next_fd: Fd,
-    devices: [(Option<&'static str>, Option<DeviceFactory>); 32],
+    /// Canonical path of the current working directory (shared by all threads of the process).
+    cwd: String,
+    mounts: MountTable,
+    /// Live `MAP_SHARED` file mappings: start address, length and the file's pin.
+    mappings: Vec<(usize, usize, SharedMapping)>,
 }
 
 impl Default for Vfs {
@@ -18,11 +84,12 @@ impl Default for Vfs {
 impl Vfs {
     /// Create a new VFS instance
     pub const fn new() -> Self {
-        const NONE: (Option<&'static str>, Option<DeviceFactory>) = (None, None);
         Self {
-            fd_table: [None; MAX_FDS],
+            fd_table: [const { None }; MAX_FDS],
             next_fd: 3,
-            devices: [NONE; 32],
+            cwd: String::new(),
+            mounts: MountTable::new(&ROOTFS),
+            mappings: Vec::new(),
         }
     }
 
@@ -30,150 +97,727 @@ impl Vfs {
         if fd < 0 || fd as usize >= MAX_FDS {
             return Err(-(libc::EINVAL as isize));
         }
-        self.fd_table[fd as usize] = Some(entry);
+        self.fd_table[fd as usize] = Some(OpenFd::new(entry, None));
         Ok(())
     }
 
+    /// Create the device node `path`, which must name an entry directly under `/dev`.
     pub fn register_device(&mut self, path: &'static str, factory: DeviceFactory) -> VfsResult<()> {
-        for entry in &mut self.devices {
-            if entry.0.is_none() {
-                *entry = (Some(path), Some(factory));
-                return Ok(());
-            }
-        }
-        Err(-(libc::ENOMEM as isize))
+        DEVFS.register(path, Opener::Factory(factory))
     }
 
-    pub fn open(&mut self, path: &str, _flags: i32, _mode: u32) -> VfsResult<Fd> {
-        let factory = self
-            .devices
-            .iter()
-            .find(|(p, _)| p.is_some_and(|device_path| device_path == path))
-            .and_then(|(_, f)| *f)
-            .ok_or(-(libc::ENOENT as isize))?;
+    /// Like [`register_device`](Self::register_device), but every open calls `open` with `ctx`.
+    pub fn register_device_with(
+        &mut self,
+        path: &'static str,
+        open: DeviceOpenFn,
+        ctx: *mut u8,
+    ) -> VfsResult<()> {
+        DEVFS.register(path, Opener::Open(open, ctx))
+    }
 
-        let mut found: Option<Fd> = None;
-        let start = self.next_fd.max(3) as usize;
-        for idx in start..MAX_FDS {
-            if self.fd_table[idx].is_none() {
-                found = Some(idx as Fd);
-                break;
-            }
+    /// Mount `fs` over directory `path` (absolute), creating the directory if it is missing.
+    pub fn mount(&mut self, path: &str, fs: &'static dyn FileSystem) -> VfsResult<()> {
+        if !path.starts_with('/') {
+            return Err(-(libc::EINVAL as isize));
         }
-        if found.is_none() {
-            for idx in 3..start.min(MAX_FDS) {
-                if self.fd_table[idx].is_none() {
-                    found = Some(idx as Fd);
-                    break;
+        if normalize("", path).is_empty() {
+            return Err(-(libc::EBUSY as isize));
+        }
+        let mut dir = String::new();
+        for name in path.split('/').filter(|c| !c.is_empty()) {
+            dir = match self.mounts.resolve(&dir, name, true) {
+                Ok(node) if node.file_type()? == libc::S_IFDIR => node.path,
+                Ok(_) => return Err(-(libc::ENOTDIR as isize)),
+                Err(e) if e == -(libc::ENOENT as isize) => {
+                    let (parent, name) = self.mounts.resolve_parent(&dir, name)?;
+                    parent.fs.mkdir(parent.ino, name, 0o755)?;
+                    join(&parent.path, name)
                 }
-            }
+                Err(e) => return Err(e),
+            };
         }
-        let fd = match found {
-            Some(fd) => fd,
-            None => return Err(-(libc::EMFILE as isize)),
-        };
+        self.mounts.mount(dir, fs)
+    }
+
+    fn get(&self, fd: Fd) -> VfsResult<&OpenFd> {
+        if fd < 0 || fd as usize >= MAX_FDS {
+            return Err(-(libc::EBADF as isize));
+        }
+        self.fd_table[fd as usize]
+            .as_ref()
+            .ok_or(-(libc::EBADF as isize))
+    }
+
+    /// Directory relative paths are resolved against: `dirfd`, or the cwd for `AT_FDCWD`.
+    ///
+    /// Absolute paths ignore it, so `dirfd` is only validated for relative ones.
+    fn base(&self, dirfd: Fd, path: &str) -> VfsResult<&str> {
+        if path.starts_with('/') {
+            return Ok("");
+        }
+        if dirfd == libc::AT_FDCWD {
+            return Ok(&self.cwd);
+        }
+        self.get(dirfd)?
+            .dir
+            .as_deref()
+            .ok_or(-(libc::ENOTDIR as isize))
+    }
+
+    fn alloc_fd(&mut self) -> VfsResult<Fd> {
+        let start = self.next_fd.max(3) as usize;
+        let fd = (start..MAX_FDS)
+            .chain(3..start.min(MAX_FDS))
+            .find(|&idx| self.fd_table[idx].is_none())
+            .ok_or(-(libc::EMFILE as isize))? as Fd;
         self.next_fd = if (fd as usize) + 1 < MAX_FDS {
             fd + 1
         } else {
             3
         };
+        Ok(fd)
+    }
 
-        let entry = factory();
-        self.fd_table[fd as usize] = Some(entry);
+    pub fn openat(&mut self, dirfd: Fd, path: &str, flags: i32, mode: u32) -> VfsResult<Fd> {
+        let fd = self.alloc_fd()?;
+        let base = self.base(dirfd, path)?;
+        let node = match self
+            .mounts
+            .resolve(base, path, flags & libc::O_NOFOLLOW == 0)
+        {
+            Ok(_) if flags & libc::O_CREAT != 0 && flags & libc::O_EXCL != 0 => {
+                return Err(-(libc::EEXIST as isize));
+            }
+            Ok(node) => node,
+            Err(e) if e == -(libc::ENOENT as isize) && flags & libc::O_CREAT != 0 => {
+                let (dir, name) = self.mounts.resolve_parent(base, path)?;
+                if path.ends_with('/') {
+                    return Err(-(libc::EISDIR as isize));
+                }
+                Vnode {
+                    ino: dir.fs.create(dir.ino, name, mode)?,
+                    fs: dir.fs,
+                    path: join(&dir.path, name),
+                }
+            }
+            Err(e) => return Err(e),
+        };
 
+        let is_dir = node.file_type()? == libc::S_IFDIR;
+        let writable = flags & libc::O_ACCMODE != libc::O_RDONLY;
+        if is_dir && (writable || flags & libc::O_CREAT != 0) {
+            return Err(-(libc::EISDIR as isize));
+        }
+        if !is_dir && flags & libc::O_DIRECTORY != 0 {
+            return Err(-(libc::ENOTDIR as isize));
+        }
+        let entry = node.fs.open(node.ino, flags, mode)?;
+        let dir = is_dir.then_some(node.path);
+        self.fd_table[fd as usize] = Some(OpenFd::new(entry, dir));
         Ok(fd)
     }
 
-    pub fn read(&self, fd: Fd, buf: *mut u8, count: usize) -> isize {
+    pub fn lseek(&self, fd: Fd, offset: isize, whence: i32) -> isize {
+        match self.get(fd) {
+            Ok(f) => (f.entry.ops.llseek)(f.entry.private_data, offset, whence),
+            Err(e) => e,
+        }
+    }
+
+    pub fn ioctl(&self, fd: Fd, request: usize, arg: usize) -> isize {
+        match self.get(fd) {
+            Ok(f) => (f.entry.ops.ioctl)(f.entry.private_data, request, arg),
+            Err(e) => e,
+        }
+    }
+
+    pub fn close(&mut self, fd: Fd) -> isize {
         if fd < 0 || fd as usize >= MAX_FDS {
             return -(libc::EBADF as isize);
         }
-        if count != 0 && buf.is_null() {
-            return -(libc::EFAULT as isize);
-        }
 
-        match self.fd_table[fd as usize] {
-            Some(entry) => (entry.ops.read)(entry.private_data, buf, count),
+        match self.fd_table[fd as usize].take() {
+            Some(f) => match Arc::try_unwrap(f.entry) {
+                Ok(file) => file.release(),
+                // In use by a blocked call: released when it returns.
+                Err(_) => 0,
+            },
             None => -(libc::EBADF as isize),
         }
     }
 
-    pub fn write(&self, fd: Fd, buf: *const u8, count: usize) -> isize {
-        if fd < 0 || fd as usize >= MAX_FDS {
-            return -(libc::EBADF as isize);
+    pub fn fstat(&self, fd: Fd, statbuf: *mut libc::stat) -> isize {
+        if statbuf.is_null() {
+            return -(libc::EFAULT as isize);
         }
-        if count != 0 && buf.is_null() {
+        match self.get(fd) {
+            Ok(f) => (f.entry.ops.fstat)(f.entry.private_data, statbuf),
+            Err(e) => e,
+        }
+    }
+
+    /// `newfstatat`; `AT_SYMLINK_NOFOLLOW` stats a final symlink itself.
+    pub fn fstatat(&self, dirfd: Fd, path: &str, st: &mut libc::stat, flags: i32) -> isize {
+        if path.is_empty() && flags & libc::AT_EMPTY_PATH != 0 {
+            if dirfd != libc::AT_FDCWD {
+                return self.fstat(dirfd, st);
+            }
+        } else if path.is_empty() {
+            return -(libc::ENOENT as isize);
+        }
+        let follow = flags & libc::AT_SYMLINK_NOFOLLOW == 0;
+        let result = self.base(dirfd, path).and_then(|base| {
+            // An empty path is the cwd itself (`AT_EMPTY_PATH`).
+            let node =
+                self.mounts
+                    .resolve(base, if path.is_empty() { "." } else { path }, follow)?;
+            node.fs.stat(node.ino, st)
+        });
+        status(result)
+    }
+
+    pub fn getdents64(&self, fd: Fd, buf: *mut u8, count: usize) -> isize {
+        if buf.is_null() {
             return -(libc::EFAULT as isize);
         }
+        match self.get(fd) {
+            Ok(f) => (f.entry.ops.getdents64)(f.entry.private_data, buf, count),
+            Err(e) => e,
+        }
+    }
 
-        match self.fd_table[fd as usize] {
-            Some(entry) => (entry.ops.write)(entry.private_data, buf, count),
-            None => -(libc::EBADF as isize),
+    /// Readiness of `fd` as a `POLL*` mask; `POLLNVAL` if it is not open.
+    pub fn poll(&self, fd: Fd) -> i16 {
+        match self.get(fd) {
+            Ok(f) => self.readiness(&f.entry, 0),
+            Err(_) => libc::POLLNVAL,
         }
     }
 
-    pub fn lseek(&self, fd: Fd, offset: isize, whence: i32) -> isize {
-        if fd < 0 || fd as usize >= MAX_FDS {
-            return -(libc::EBADF as isize);
+    /// Readiness of `entry`, `depth` epoll instances below the one asked; epoll instances look
+    /// up their interests here rather than through the global table, whose lock the caller
+    /// holds.
+    fn readiness(&self, entry: &FdEntry, depth: usize) -> i16 {
+        match epoll::instance(entry) {
+            // `epoll_ctl` only checks below the instance added to, so a chain can still grow
+            // from the top; instances past the limit read as not ready.
+            Some(_) if depth > EPOLL_MAX_NESTS => 0,
+            Some(ep) => ep.poll(&|fd| self.poll_file(fd, depth + 1), kpoll_seq()),
+            None => (entry.ops.poll)(entry.private_data),
         }
+    }
 
-        match self.fd_table[fd as usize] {
-            Some(entry) => (entry.ops.llseek)(entry.private_data, offset, whence),
-            None => -(libc::EBADF as isize),
+    /// File behind `fd` and its readiness (for epoll interests).
+    fn poll_file(&self, fd: Fd, depth: usize) -> Option<(*mut u8, i16)> {
+        let f = self.get(fd).ok()?;
+        Some((f.entry.private_data, self.readiness(&f.entry, depth)))
+    }
+
+    /// How many epoll instances deep `entry` nests (0 if it is not one); `ELOOP` if it reaches
+    /// the instance `epoll` or nests deeper than `EPOLL_MAX_NESTS`.
+    fn epoll_nesting(&self, entry: &FdEntry, epoll: *mut u8, depth: usize) -> VfsResult<usize> {
+        let Some(ep) = epoll::instance(entry) else {
+            return Ok(0);
+        };
+        if core::ptr::eq(entry.private_data, epoll) || depth > EPOLL_MAX_NESTS {
+            return Err(-(libc::ELOOP as isize));
+        }
+        let mut deepest = 0;
+        for (fd, file) in ep.watched() {
+            match self.get(fd) {
+                Ok(f) if core::ptr::eq(f.entry.private_data, file) => {
+                    deepest = deepest.max(self.epoll_nesting(&f.entry, epoll, depth + 1)?);
+                }
+                _ => {}
+            }
         }
+        Ok(deepest + 1)
     }
 
-    pub fn ioctl(&self, fd: Fd, request: usize, arg: usize) -> isize {
-        if fd < 0 || fd as usize >= MAX_FDS {
-            return -(libc::EBADF as isize);
+    fn install(&mut self, entry: FdEntry) -> VfsResult<Fd> {
+        match self.alloc_fd() {
+            Ok(fd) => {
+                self.fd_table[fd as usize] = Some(OpenFd::new(entry, None));
+                Ok(fd)
+            }
+            Err(e) => {
+                (entry.ops.release)(entry.private_data);
+                Err(e)
+            }
         }
+    }
 
-        match self.fd_table[fd as usize] {
-            Some(entry) => (entry.ops.ioctl)(entry.private_data, request, arg),
-            None => -(libc::EBADF as isize),
+    pub fn eventfd2(&mut self, initval: u32, flags: i32) -> VfsResult<Fd> {
+        if flags & !(libc::EFD_SEMAPHORE | libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) != 0 {
+            return Err(-(libc::EINVAL as isize));
         }
+        self.install(eventfd::create(initval, flags))
     }
 
-    pub fn close(&mut self, fd: Fd) -> isize {
-        if fd < 0 || fd as usize >= MAX_FDS {
-            return -(libc::EBADF as isize);
+    pub fn epoll_create1(&mut self, flags: i32) -> VfsResult<Fd> {
+        if flags & !libc::EPOLL_CLOEXEC != 0 {
+            return Err(-(libc::EINVAL as isize));
+        }
+        self.install(epoll::create())
+    }
+
+    pub fn epoll_ctl(
+        &self,
+        epfd: Fd,
+        op: i32,
+        fd: Fd,
+        event: Option<libc::epoll_event>,
+    ) -> VfsResult<()> {
+        let epoll = &self.get(epfd)?.entry;
+        let ep = epoll::instance(epoll).ok_or(-(libc::EINVAL as isize))?;
+        let target = &self.get(fd)?.entry;
+        if fd == epfd {
+            return Err(-(libc::EINVAL as isize));
+        }
+        // Watching an instance that (through others) watches this one would make readiness
+        // checks recurse forever.
+        if op == libc::EPOLL_CTL_ADD {
+            self.epoll_nesting(target, epoll.private_data, 1)?;
         }
+        ep.ctl(op, fd, target.private_data, event)
+    }
 
-        match self.fd_table[fd as usize].take() {
-            Some(entry) => (entry.ops.release)(entry.private_data),
-            None => -(libc::EBADF as isize),
+    /// Collect ready events of `epfd` into `out` without blocking.
+    pub fn epoll_scan(&self, epfd: Fd, out: &mut [libc::epoll_event]) -> isize {
+        let ep = match self.get(epfd).map(|f| epoll::instance(&f.entry)) {
+            Ok(Some(ep)) => ep,
+            Ok(None) => return -(libc::EINVAL as isize),
+            Err(e) => return e,
+        };
+        ep.collect(out, &|fd| self.poll_file(fd, 1), kpoll_seq()) as isize
+    }
+
+    /// Install both ends of a pipe or socket pair.
+    fn install_pair(&mut self, (a, b): (FdEntry, FdEntry)) -> VfsResult<(Fd, Fd)> {
+        let afd = match self.install(a) {
+            Ok(fd) => fd,
+            Err(e) => {
+                (b.ops.release)(b.private_data);
+                return Err(e);
+            }
+        };
+        let bfd = self.install(b).inspect_err(|_| {
+            self.close(afd);
+        })?;
+        Ok((afd, bfd))
+    }
+
+    /// Create a pipe; returns its read and write descriptors.
+    pub fn pipe2(&mut self, flags: i32) -> VfsResult<(Fd, Fd)> {
+        if flags & !(libc::O_NONBLOCK | libc::O_CLOEXEC | libc::O_DIRECT) != 0 {
+            return Err(-(libc::EINVAL as isize));
         }
+        self.install_pair(pipe::create(flags))
     }
 
-    pub fn fstat(&self, fd: Fd, statbuf: *mut libc::stat) -> isize {
-        if fd < 0 || fd as usize >= MAX_FDS {
-            return -(libc::EBADF as isize);
+    fn socket_of(&self, fd: Fd) -> VfsResult<&mut Socket> {
+        socket::instance(&self.get(fd)?.entry).ok_or(-(libc::ENOTSOCK as isize))
+    }
+
+    pub fn socket(&mut self, domain: i32, ty: i32, protocol: i32) -> VfsResult<Fd> {
+        let entry = socket::create(domain, ty, protocol)?;
+        self.install(entry)
+    }
+
+    pub fn socketpair(&mut self, domain: i32, ty: i32, protocol: i32) -> VfsResult<(Fd, Fd)> {
+        let pair = socket::pair(domain, ty, protocol)?;
+        self.install_pair(pair)
+    }
+
+    pub fn bind(&self, fd: Fd, addr: &[u8]) -> VfsResult<()> {
+        self.socket_of(fd)?.bind(addr)
+    }
+
+    pub fn listen(&self, fd: Fd, backlog: i32) -> VfsResult<()> {
+        self.socket_of(fd)?.listen(backlog)
+    }
+
+    /// Accept a queued connection without blocking (`EAGAIN` if there is none).
+    pub fn accept4(&mut self, fd: Fd, flags: i32) -> VfsResult<Fd> {
+        if flags & !(libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC) != 0 {
+            return Err(-(libc::EINVAL as isize));
         }
+        let entry = self.socket_of(fd)?.accept(flags)?;
+        self.install(entry)
+    }
 
-        if statbuf.is_null() {
-            return -(libc::EFAULT as isize);
+    /// Connect without blocking (`EAGAIN` while the listener's backlog is full).
+    pub fn connect(&self, fd: Fd, addr: &[u8]) -> VfsResult<()> {
+        self.socket_of(fd)?.connect(addr)
+    }
+
+    pub fn shutdown(&self, fd: Fd, how: i32) -> VfsResult<()> {
+        self.socket_of(fd)?.shutdown(how)
+    }
+
+    /// Local (or, with `peer`, remote) address of `fd` as a `struct sockaddr` copied into `out`;
+    /// returns its full length.
+    pub fn sockname(&self, fd: Fd, peer: bool, out: &mut [u8]) -> VfsResult<usize> {
+        self.socket_of(fd)?.name(peer, out)
+    }
+
+    pub fn getsockopt(&self, fd: Fd, level: i32, name: i32) -> VfsResult<i32> {
+        self.socket_of(fd)?.getsockopt(level, name)
+    }
+
+    pub fn setsockopt(&self, fd: Fd, level: i32, name: i32, optlen: usize) -> VfsResult<()> {
+        self.socket_of(fd)?.setsockopt(level, name, optlen)
+    }
+
+    /// Create an anonymous regular file open for reading and writing.
+    pub fn memfd_create(&mut self, name: &str, flags: u32) -> VfsResult<Fd> {
+        if flags & !(libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING) != 0 {
+            return Err(-(libc::EINVAL as isize));
+        }
+        // `NAME_MAX` less the "memfd:" prefix Linux shows in /proc.
+        if name.len() > 249 {
+            return Err(-(libc::EINVAL as isize));
+        }
+        let fd = self.alloc_fd()?;
+        let entry = with_fs(|fs| {
+            let ino = fs.create_anon(0o777);
+            FdEntry {
+                ops: &FILE_FOPS,
+                private_data: OpenFile::open(fs, ino, libc::O_RDWR),
+            }
+        });
+        self.fd_table[fd as usize] = Some(OpenFd::new(entry, None));
+        Ok(fd)
+    }
+
+    pub fn ftruncate(&self, fd: Fd, len: isize) -> VfsResult<()> {
+        if len < 0 {
+            return Err(-(libc::EINVAL as isize));
+        }
+        let entry = &self.get(fd)?.entry;
+        if !core::ptr::eq(entry.ops, &FILE_FOPS) {
+            return Err(-(libc::EINVAL as isize));
+        }
+        ramfs::ftruncate(entry.private_data, len as usize)
+    }
+
+    /// Map `len` bytes of `fd` at `offset` shared with the file; returns the address.
+    pub fn mmap_shared(
+        &mut self,
+        fd: Fd,
+        offset: usize,
+        len: usize,
+        write: bool,
+    ) -> VfsResult<usize> {
+        let entry = self.get(fd)?.entry.0;
+        let mmap = entry.ops.mmap.ok_or(-(libc::ENODEV as isize))?;
+        let map = mmap(entry.private_data, offset, len, write)?;
+        self.mappings.push((map.addr as usize, len, map));
+        Ok(map.addr as usize)
+    }
+
+    /// Release shared mappings inside `[addr, addr + len)`; returns whether the range touches
+    /// any shared mapping (such memory is never returned to the allocator).
+    ///
+    /// A mapping only partially covered by the range stays mapped.
+    pub fn munmap_shared(&mut self, addr: usize, len: usize) -> bool {
+        let end = addr.saturating_add(len);
+        let mut touched = false;
+        self.mappings.retain(|&(start, size, map)| {
+            if start >= end || start + size <= addr {
+                return true;
+            }
+            touched = true;
+            if addr <= start && start + size <= end {
+                (map.unmap)(map.token);
+                false
+            } else {
+                true
+            }
+        });
+        touched
+    }
+
+    /// Copy the target of symlink `path` into `buf` (not NUL-terminated), truncating to fit.
+    pub fn readlinkat(&self, dirfd: Fd, path: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        let node = self.mounts.resolve(self.base(dirfd, path)?, path, false)?;
+        let mut target = String::new();
+        node.fs.readlink(node.ino, &mut target)?;
+        let n = target.len().min(buf.len());
+        buf[..n].copy_from_slice(&target.as_bytes()[..n]);
+        Ok(n)
+    }
+
+    pub fn mkdirat(&self, dirfd: Fd, path: &str, mode: u32) -> VfsResult<()> {
+        let (dir, name) = self.mounts.resolve_parent(self.base(dirfd, path)?, path)?;
+        if matches!(name, "." | "..") {
+            return Err(-(libc::EEXIST as isize));
+        }
+        dir.fs.mkdir(dir.ino, name, mode).map(|_| ())
+    }
+
+    pub fn unlinkat(&self, dirfd: Fd, path: &str, flags: i32) -> VfsResult<()> {
+        if flags & !libc::AT_REMOVEDIR != 0 {
+            return Err(-(libc::EINVAL as isize));
+        }
+        let base = self.base(dirfd, path)?;
+        let (dir, name) = match self.mounts.resolve_parent(base, path) {
+            // "/" itself.
+            Err(e) if e == -(libc::EEXIST as isize) => return Err(-(libc::EBUSY as isize)),
+            r => r?,
+        };
+        match name {
+            "." => return Err(-(libc::EINVAL as isize)),
+            ".." => return Err(-(libc::ENOTEMPTY as isize)),
+            _ => {}
+        }
+        let want_dir = flags & libc::AT_REMOVEDIR != 0;
+        if !want_dir && path.ends_with('/') {
+            return Err(-(libc::ENOTDIR as isize));
+        }
+        if self.mounts.is_mount_point(&join(&dir.path, name)) {
+            return Err(-(libc::EBUSY as isize));
+        }
+        dir.fs.unlink(dir.ino, name, want_dir)
+    }
+
+    pub fn renameat(
+        &self,
+        olddirfd: Fd,
+        oldpath: &str,
+        newdirfd: Fd,
+        newpath: &str,
+        flags: u32,
+    ) -> VfsResult<()> {
+        let (old, old_name) = self
+            .mounts
+            .resolve_parent(self.base(olddirfd, oldpath)?, oldpath)?;
+        let (new, new_name) = self
+            .mounts
+            .resolve_parent(self.base(newdirfd, newpath)?, newpath)?;
+        if matches!(old_name, "." | "..")
+            || matches!(new_name, "." | "..")
+            || self.mounts.is_mount_point(&join(&old.path, old_name))
+            || self.mounts.is_mount_point(&join(&new.path, new_name))
+        {
+            return Err(-(libc::EBUSY as isize));
+        }
+        if !core::ptr::addr_eq(old.fs, new.fs) {
+            return Err(-(libc::EXDEV as isize));
+        }
+        old.fs
+            .rename((old.ino, old_name), (new.ino, new_name), flags)
+    }
+
+    /// Copy the cwd into `buf` (NUL-terminated); returns the length including the NUL.
+    pub fn getcwd(&self, buf: &mut [u8]) -> isize {
+        let len = self.cwd.len() + 1;
+        if len + 1 > buf.len() {
+            return -(libc::ERANGE as isize);
+        }
+        buf[0] = b'/';
+        buf[1..len].copy_from_slice(self.cwd.as_bytes());
+        buf[len] = 0;
+        (len + 1) as isize
+    }
+
+    pub fn chdir(&mut self, path: &str) -> VfsResult<()> {
+        let node = self.mounts.resolve(&self.cwd, path, true)?;
+        if node.file_type()? != libc::S_IFDIR {
+            return Err(-(libc::ENOTDIR as isize));
+        }
+        self.cwd = node.path;
+        Ok(())
+    }
+
+    pub fn fchdir(&mut self, fd: Fd) -> VfsResult<()> {
+        let dir = self.get(fd)?.dir.clone();
+        self.cwd = dir.ok_or(-(libc::ENOTDIR as isize))?;
+        Ok(())
+    }
+
+    /// Release every open descriptor, highest first so stdout/stderr are closed last.
+    pub fn close_all(&mut self) {
+        for fd in (0..MAX_FDS).rev() {
+            // A descriptor some thread is blocked on is released when that call returns.
+            drop(self.fd_table[fd].take());
+        }
+    }
+}
+
+fn read_file(file: &FdEntry, buf: *mut u8, count: usize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    (file.ops.read)(file.private_data, buf, count)
+}
+
+fn write_file(file: &FdEntry, buf: *const u8, count: usize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    (file.ops.write)(file.private_data, buf, count)
+}
+
+fn pread_file(file: &FdEntry, buf: *mut u8, count: usize, offset: isize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    if offset < 0 {
+        return -(libc::EINVAL as isize);
+    }
+    match file.ops.pread {
+        Some(pread) => pread(file.private_data, buf, count, offset as usize),
+        None => -(libc::ESPIPE as isize),
+    }
+}
+
+fn pwrite_file(file: &FdEntry, buf: *const u8, count: usize, offset: isize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    if offset < 0 {
+        return -(libc::EINVAL as isize);
+    }
+    match file.ops.pwrite {
+        Some(pwrite) => pwrite(file.private_data, buf, count, offset as usize),
+        None => -(libc::ESPIPE as isize),
+    }
+}
+
+/// Copy up to `count` bytes from `input` to `output` through a kernel buffer.
+///
+/// Offsets given as `Some` are used and advanced instead of the files' positions.
+/// Returns the number of bytes copied; an error is only reported if nothing was copied.
+///
+/// When `output` takes less than was read, a seekable `input` is moved back to just after the
+/// bytes written. A stream cannot give bytes back, so those are lost, as with a short `write`
+/// of data already taken from a pipe; only the short count tells the caller.
+fn transfer(
+    input: &FdEntry,
+    mut in_off: Option<&mut isize>,
+    output: &FdEntry,
+    mut out_off: Option<&mut isize>,
+    count: usize,
+) -> isize {
+    const CHUNK: usize = 64 * 1024;
+
+    let mut buf = alloc::vec![0u8; count.min(CHUNK)];
+    let mut total = 0usize;
+    while total < count {
+        let want = (count - total).min(buf.len());
+        let n = match in_off.as_deref() {
+            Some(&off) => pread_file(input, buf.as_mut_ptr(), want, off),
+            None => read_file(input, buf.as_mut_ptr(), want),
+        };
+        if n <= 0 {
+            return if total > 0 { total as isize } else { n };
+        }
+        let written = match out_off.as_deref() {
+            Some(&off) => pwrite_file(output, buf.as_ptr(), n as usize, off),
+            None => write_file(output, buf.as_ptr(), n as usize),
+        };
+        let done = written.max(0);
+        match in_off.as_deref_mut() {
+            Some(off) => *off += done,
+            // Give back what was read but not written.
+            None if done < n && input.ops.pread.is_some() => {
+                (input.ops.llseek)(input.private_data, done - n, libc::SEEK_CUR);
+            }
+            None => {}
+        }
+        if written < 0 {
+            return if total > 0 { total as isize } else { written };
         }
+        if let Some(off) = out_off.as_deref_mut() {
+            *off += written;
+        }
+        total += written as usize;
+        if written < n {
+            break;
+        }
+    }
+    total as isize
+}
 
-        -(libc::ENOSYS as isize)
+/// `copy_file_range`: like [`transfer`], but only between regular files.
+fn copy_file_range(
+    input: &FdEntry,
+    in_off: Option<&mut isize>,
+    output: &FdEntry,
+    out_off: Option<&mut isize>,
+    len: usize,
+    flags: u32,
+) -> isize {
+    if flags != 0 {
+        return -(libc::EINVAL as isize);
+    }
+    for file in [input, output] {
+        let mut st: libc::stat = unsafe { core::mem::zeroed() };
+        let r = (file.ops.fstat)(file.private_data, &mut st);
+        if r < 0 {
+            return r;
+        }
+        if st.st_mode & libc::S_IFMT != libc::S_IFREG {
+            return -(libc::EINVAL as isize);
+        }
     }
+    if in_off.as_deref().is_some_and(|&o| o < 0) || out_off.as_deref().is_some_and(|&o| o < 0) {
+        return -(libc::EINVAL as isize);
+    }
+    transfer(input, in_off, output, out_off, len)
 }
 
-static VFS: GlobalCell<Vfs> = GlobalCell::new(Vfs::new());
+static VFS: KernelLock<Vfs> = KernelLock::new(Vfs::new());
+
+/// Run `op` on the file behind `fd` without holding the VFS lock.
+///
+/// Reads and writes may block until another thread makes the file ready, and that thread
+/// needs the lock to do so. The file is pinned, so closing `fd` meanwhile defers its release.
+fn with_file(fd: Fd, op: impl FnOnce(&FdEntry) -> isize) -> isize {
+    match VFS.with(|vfs| vfs.get(fd).map(|f| f.entry.clone())) {
+        Ok(file) => op(&file),
+        Err(e) => e,
+    }
+}
+
+fn status(result: VfsResult<()>) -> isize {
+    match result {
+        Ok(()) => 0,
+        Err(e) => e,
+    }
+}
 
 pub fn register_fd(fd: Fd, entry: FdEntry) -> VfsResult<()> {
     VFS.with_mut(|vfs| vfs.register_fd(fd, entry))
 }
 
+/// Populate the namespace with the synthetic `/proc` tree and mount the devices at `/dev`.
+pub fn init() {
+    let _ = with_fs(procfs::install);
+    let _ = mount(DEV_MOUNT, &DEVFS);
+}
+
+/// Mount `fs` over directory `path` (absolute); platforms call this from their bootstrap.
+pub fn mount(path: &str, fs: &'static dyn FileSystem) -> VfsResult<()> {
+    VFS.with_mut(|vfs| vfs.mount(path, fs))
+}
+
 pub fn register_device(path: &'static str, factory: DeviceFactory) -> VfsResult<()> {
     VFS.with_mut(|vfs| vfs.register_device(path, factory))
 }
 
+pub fn register_device_with(path: &'static str, open: DeviceOpenFn, ctx: *mut u8) -> VfsResult<()> {
+    VFS.with_mut(|vfs| vfs.register_device_with(path, open, ctx))
+}
+
 pub fn read(fd: Fd, buf: *mut u8, count: usize) -> isize {
-    VFS.with(|vfs| vfs.read(fd, buf, count))
+    with_file(fd, |f| read_file(f, buf, count))
 }
 
 pub fn write(fd: Fd, buf: *const u8, count: usize) -> isize {
-    VFS.with(|vfs| vfs.write(fd, buf, count))
+    with_file(fd, |f| write_file(f, buf, count))
 }
 
 pub fn lseek(fd: Fd, offset: isize, whence: i32) -> isize {
@@ -192,41 +836,590 @@ pub fn fstat(fd: Fd, statbuf: *mut libc::stat) -> isize {
     VFS.with(|vfs| vfs.fstat(fd, statbuf))
 }
 
+pub fn getdents64(fd: Fd, buf: *mut u8, count: usize) -> isize {
+    VFS.with(|vfs| vfs.getdents64(fd, buf, count))
+}
+
+pub fn poll(fd: Fd) -> i16 {
+    VFS.with(|vfs| vfs.poll(fd))
+}
+
+fn fd_result(result: VfsResult<Fd>) -> isize {
+    match result {
+        Ok(fd) => fd as isize,
+        Err(e) => e,
+    }
+}
+
+pub fn eventfd2(initval: u32, flags: i32) -> isize {
+    fd_result(VFS.with_mut(|vfs| vfs.eventfd2(initval, flags)))
+}
+
+pub fn epoll_create1(flags: i32) -> isize {
+    fd_result(VFS.with_mut(|vfs| vfs.epoll_create1(flags)))
+}
+
+fn pair_result(result: VfsResult<(Fd, Fd)>, fds: &mut [Fd; 2]) -> isize {
+    match result {
+        Ok((a, b)) => {
+            *fds = [a, b];
+            0
+        }
+        Err(e) => e,
+    }
+}
+
+pub fn pipe2(flags: i32, fds: &mut [Fd; 2]) -> isize {
+    pair_result(VFS.with_mut(|vfs| vfs.pipe2(flags)), fds)
+}
+
+pub fn socket(domain: i32, ty: i32, protocol: i32) -> isize {
+    fd_result(VFS.with_mut(|vfs| vfs.socket(domain, ty, protocol)))
+}
+
+pub fn socketpair(domain: i32, ty: i32, protocol: i32, fds: &mut [Fd; 2]) -> isize {
+    pair_result(
+        VFS.with_mut(|vfs| vfs.socketpair(domain, ty, protocol)),
+        fds,
+    )
+}
+
+/// Retry `op` while it fails with `EAGAIN`, sleeping on the readiness generation, unless the
+/// socket is non-blocking or nothing could ever wake us.
+fn socket_wait(fd: Fd, mut op: impl FnMut() -> isize) -> isize {
+    loop {
+        let seq = kpoll_seq();
+        let r = op();
+        if r != -(libc::EAGAIN as isize) {
+            return r;
+        }
+        let nonblock = VFS.with(|vfs| vfs.socket_of(fd).map(|s| s.nonblock()));
+        if nonblock != Ok(false) || !kpoll_wait(seq) {
+            return r;
+        }
+    }
+}
+
+pub fn ftruncate(fd: Fd, len: isize) -> isize {
+    status(VFS.with(|vfs| vfs.ftruncate(fd, len)))
+}
+
+pub fn mmap_shared(fd: Fd, offset: usize, len: usize, write: bool) -> isize {
+    match VFS.with_mut(|vfs| vfs.mmap_shared(fd, offset, len, write)) {
+        Ok(addr) => addr as isize,
+        Err(e) => e,
+    }
+}
+
+pub fn munmap_shared(addr: usize, len: usize) -> bool {
+    VFS.with_mut(|vfs| vfs.munmap_shared(addr, len))
+}
+
+pub fn listen(fd: Fd, backlog: i32) -> isize {
+    status(VFS.with(|vfs| vfs.listen(fd, backlog)))
+}
+
+pub fn sendto(fd: Fd, buf: *const u8, len: usize, flags: i32) -> isize {
+    with_file(fd, |f| match socket::instance(f) {
+        Some(_) => socket::send(f.private_data, buf, len, flags),
+        None => -(libc::ENOTSOCK as isize),
+    })
+}
+
+pub fn recvfrom(fd: Fd, buf: *mut u8, len: usize, flags: i32) -> isize {
+    with_file(fd, |f| match socket::instance(f) {
+        Some(_) => socket::recv(f.private_data, buf, len, flags),
+        None => -(libc::ENOTSOCK as isize),
+    })
+}
+
+pub fn shutdown(fd: Fd, how: i32) -> isize {
+    status(VFS.with(|vfs| vfs.shutdown(fd, how)))
+}
+
+pub fn pread(fd: Fd, buf: *mut u8, count: usize, offset: isize) -> isize {
+    with_file(fd, |f| pread_file(f, buf, count, offset))
+}
+
+pub fn pwrite(fd: Fd, buf: *const u8, count: usize, offset: isize) -> isize {
+    with_file(fd, |f| pwrite_file(f, buf, count, offset))
+}
+
+fn getcwd_raw(buf: *mut u8, size: usize) -> isize {
+    if buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    let buf = unsafe { core::slice::from_raw_parts_mut(buf, size) };
+    VFS.with(|vfs| vfs.getcwd(buf))
+}
+
+pub fn fchdir(fd: Fd) -> isize {
+    status(VFS.with_mut(|vfs| vfs.fchdir(fd)))
+}
+
+pub fn close_all() {
+    VFS.with_mut(|vfs| vfs.close_all())
+}
+
 pub(crate) fn fstat_raw(fd: Fd, statbuf: *mut u8) -> isize {
     fstat(fd, statbuf as *mut libc::stat)
 }
 
 pub const VFS_OPS: crate::VfsOps = crate::VfsOps {
-    init: || {},
+    init,
     read,
     write,
-    open: open_cstr,
+    openat: openat_cstr,
     close,
     lseek,
     ioctl,
     fstat: fstat_raw,
+    shutdown: close_all,
+    fstatat: fstatat_cstr,
+    getdents64,
+    mkdirat: mkdirat_cstr,
+    unlinkat: unlinkat_cstr,
+    renameat: renameat_cstr,
+    getcwd: getcwd_raw,
+    chdir: chdir_cstr,
+    fchdir,
+    pread,
+    pwrite,
+    sendfile: sendfile_raw,
+    copy_file_range: copy_file_range_raw,
+    poll,
+    pipe2,
+    eventfd2,
+    epoll_create1,
+    epoll_ctl: epoll_ctl_raw,
+    epoll_scan: epoll_scan_raw,
+    socket,
+    socketpair,
+    bind: bind_raw,
+    listen,
+    accept4: accept4_raw,
+    connect: connect_raw,
+    sendto,
+    recvfrom,
+    sock_shutdown: shutdown,
+    sockname: sockname_raw,
+    getsockopt: getsockopt_raw,
+    setsockopt,
+    memfd_create: memfd_create_cstr,
+    ftruncate,
+    mmap_shared,
+    munmap_shared,
+    readlinkat: readlinkat_cstr,
 };
 
+/// Borrow a NUL-terminated path as `&str`.
+///
 /// # Safety
-/// `path` must be a valid NUL-terminated string.
-pub unsafe fn open_cstr(path: *const u8, flags: i32, mode: u32) -> isize {
+/// `path` must be null or a valid NUL-terminated string that outlives the returned borrow.
+unsafe fn path_str<'a>(path: *const u8) -> VfsResult<&'a str> {
     if path.is_null() {
-        return -(libc::EFAULT as isize);
+        return Err(-(libc::EFAULT as isize));
     }
 
     let mut len = 0;
     while *path.add(len) != 0 {
         len += 1;
         if len > 4096 {
-            return -(libc::ENAMETOOLONG as isize);
+            return Err(-(libc::ENAMETOOLONG as isize));
         }
     }
     let slice = core::slice::from_raw_parts(path, len);
-    match core::str::from_utf8(slice) {
-        Ok(s) => VFS.with_mut(|vfs| match vfs.open(s, flags, mode) {
-            Ok(fd) => fd as isize,
-            Err(e) => e,
-        }),
-        Err(_) => -(libc::EINVAL as isize),
+    core::str::from_utf8(slice).map_err(|_| -(libc::EINVAL as isize))
+}
+
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+pub unsafe fn openat_cstr(dirfd: Fd, path: *const u8, flags: i32, mode: u32) -> isize {
+    let path = match path_str(path) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    match VFS.with_mut(|vfs| vfs.openat(dirfd, path, flags, mode)) {
+        Ok(fd) => fd as isize,
+        Err(e) => e,
+    }
+}
+
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+pub unsafe fn open_cstr(path: *const u8, flags: i32, mode: u32) -> isize {
+    openat_cstr(libc::AT_FDCWD, path, flags, mode)
+}
+
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+pub unsafe fn fstatat_cstr(dirfd: Fd, path: *const u8, statbuf: *mut u8, flags: i32) -> isize {
+    if statbuf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    let st = &mut *(statbuf as *mut libc::stat);
+    match path_str(path) {
+        Ok(p) => VFS.with(|vfs| vfs.fstatat(dirfd, p, st, flags)),
+        Err(e) => e,
+    }
+}
+
+/// # Safety
+/// `path` must be a valid NUL-terminated string and `buf` valid for `size` bytes.
+pub unsafe fn readlinkat_cstr(dirfd: Fd, path: *const u8, buf: *mut u8, size: usize) -> isize {
+    if size == 0 || size > isize::MAX as usize {
+        return -(libc::EINVAL as isize);
+    }
+    if buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    let buf = core::slice::from_raw_parts_mut(buf, size);
+    match path_str(path).and_then(|p| VFS.with(|vfs| vfs.readlinkat(dirfd, p, buf))) {
+        Ok(n) => n as isize,
+        Err(e) => e,
+    }
+}
+
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+pub unsafe fn mkdirat_cstr(dirfd: Fd, path: *const u8, mode: u32) -> isize {
+    status(path_str(path).and_then(|p| VFS.with(|vfs| vfs.mkdirat(dirfd, p, mode))))
+}
+
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+pub unsafe fn unlinkat_cstr(dirfd: Fd, path: *const u8, flags: i32) -> isize {
+    status(path_str(path).and_then(|p| VFS.with(|vfs| vfs.unlinkat(dirfd, p, flags))))
+}
+
+/// # Safety
+/// `oldpath` and `newpath` must be valid NUL-terminated strings.
+pub unsafe fn renameat_cstr(
+    olddirfd: Fd,
+    oldpath: *const u8,
+    newdirfd: Fd,
+    newpath: *const u8,
+    flags: u32,
+) -> isize {
+    status(path_str(oldpath).and_then(|old| {
+        let new = path_str(newpath)?;
+        VFS.with(|vfs| vfs.renameat(olddirfd, old, newdirfd, new, flags))
+    }))
+}
+
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+pub unsafe fn chdir_cstr(path: *const u8) -> isize {
+    status(path_str(path).and_then(|p| VFS.with_mut(|vfs| vfs.chdir(p))))
+}
+
+/// # Safety
+/// `offset` must be null or valid for reads and writes.
+pub unsafe fn sendfile_raw(out_fd: Fd, in_fd: Fd, offset: *mut isize, count: usize) -> isize {
+    let offset = offset.as_mut();
+    if offset.as_deref().is_some_and(|&o| o < 0) {
+        return -(libc::EINVAL as isize);
+    }
+    with_file(in_fd, |input| {
+        with_file(out_fd, |output| {
+            transfer(input, offset, output, None, count)
+        })
+    })
+}
+
+/// # Safety
+/// `off_in` and `off_out` must each be null or valid for reads and writes.
+pub unsafe fn copy_file_range_raw(
+    fd_in: Fd,
+    off_in: *mut isize,
+    fd_out: Fd,
+    off_out: *mut isize,
+    len: usize,
+    flags: u32,
+) -> isize {
+    with_file(fd_in, |input| {
+        with_file(fd_out, |output| {
+            copy_file_range(input, off_in.as_mut(), output, off_out.as_mut(), len, flags)
+        })
+    })
+}
+
+/// # Safety
+/// `event` must be null or point to a readable `struct epoll_event`.
+pub unsafe fn epoll_ctl_raw(epfd: Fd, op: i32, fd: Fd, event: *const u8) -> isize {
+    let event = (!event.is_null()).then(|| (event as *const libc::epoll_event).read_unaligned());
+    status(VFS.with(|vfs| vfs.epoll_ctl(epfd, op, fd, event)))
+}
+
+/// # Safety
+/// `events` must be valid for writes of `maxevents` `struct epoll_event`s.
+pub unsafe fn epoll_scan_raw(epfd: Fd, events: *mut u8, maxevents: usize) -> isize {
+    let out = core::slice::from_raw_parts_mut(events as *mut libc::epoll_event, maxevents);
+    VFS.with(|vfs| vfs.epoll_scan(epfd, out))
+}
+
+/// # Safety
+/// `addr` must be valid for reads of `addrlen` bytes.
+pub unsafe fn bind_raw(fd: Fd, addr: *const u8, addrlen: usize) -> isize {
+    if addr.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    let addr = core::slice::from_raw_parts(addr, addrlen);
+    status(VFS.with(|vfs| vfs.bind(fd, addr)))
+}
+
+/// # Safety
+/// `addr` must be valid for reads of `addrlen` bytes.
+pub unsafe fn connect_raw(fd: Fd, addr: *const u8, addrlen: usize) -> isize {
+    if addr.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    let addr = core::slice::from_raw_parts(addr, addrlen);
+    socket_wait(fd, || status(VFS.with(|vfs| vfs.connect(fd, addr))))
+}
+
+/// Store a socket address into a user `addr`/`addrlen` pair; `addrlen` gets the full length.
+///
+/// # Safety
+/// `addrlen` must be valid for reads and writes, and `addr` for writes of `*addrlen` bytes.
+unsafe fn store_name(
+    addr: *mut u8,
+    addrlen: *mut u32,
+    name: impl FnOnce(&mut [u8]) -> VfsResult<usize>,
+) -> isize {
+    let len = addrlen.read_unaligned();
+    if (len as i32) < 0 {
+        return -(libc::EINVAL as isize);
+    }
+    match name(core::slice::from_raw_parts_mut(addr, len as usize)) {
+        Ok(n) => {
+            addrlen.write_unaligned(n as u32);
+            0
+        }
+        Err(e) => e,
+    }
+}
+
+/// # Safety
+/// `addr` and `addrlen` must be both null or valid as for [`sockname_raw`].
+pub unsafe fn accept4_raw(fd: Fd, addr: *mut u8, addrlen: *mut u32, flags: i32) -> isize {
+    let r = socket_wait(fd, || fd_result(VFS.with_mut(|vfs| vfs.accept4(fd, flags))));
+    if r >= 0 && !addr.is_null() && !addrlen.is_null() {
+        let stored = store_name(addr, addrlen, |out| {
+            VFS.with(|vfs| vfs.sockname(r as Fd, true, out))
+        });
+        if stored < 0 {
+            close(r as Fd);
+            return stored;
+        }
+    }
+    r
+}
+
+/// # Safety
+/// `addrlen` must be valid for reads and writes, and `addr` for writes of `*addrlen` bytes.
+pub unsafe fn sockname_raw(fd: Fd, addr: *mut u8, addrlen: *mut u32, peer: bool) -> isize {
+    if addr.is_null() || addrlen.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    store_name(addr, addrlen, |out| {
+        VFS.with(|vfs| vfs.sockname(fd, peer, out))
+    })
+}
+
+/// # Safety
+/// `optlen` must be valid for reads and writes, and `optval` for writes of `*optlen` bytes.
+pub unsafe fn getsockopt_raw(
+    fd: Fd,
+    level: i32,
+    name: i32,
+    optval: *mut u8,
+    optlen: *mut u32,
+) -> isize {
+    if optval.is_null() || optlen.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    match VFS.with(|vfs| vfs.getsockopt(fd, level, name)) {
+        Ok(v) => {
+            let len = (optlen.read_unaligned() as usize).min(4);
+            core::ptr::copy_nonoverlapping(v.to_ne_bytes().as_ptr(), optval, len);
+            optlen.write_unaligned(len as u32);
+            0
+        }
+        Err(e) => e,
+    }
+}
+
+pub fn setsockopt(fd: Fd, level: i32, name: i32, optval: *const u8, optlen: usize) -> isize {
+    if optlen != 0 && optval.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    status(VFS.with(|vfs| vfs.setsockopt(fd, level, name, optlen)))
+}
+
+/// # Safety
+/// `name` must be null or a valid NUL-terminated string.
+pub unsafe fn memfd_create_cstr(name: *const u8, flags: u32) -> isize {
+    match path_str(name) {
+        Ok(name) => fd_result(VFS.with_mut(|vfs| vfs.memfd_create(name, flags))),
+        Err(e) => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_close_defers_release_while_in_use() {
+        let mut vfs = Vfs::new();
+        let fd = vfs.eventfd2(0, libc::EFD_NONBLOCK).unwrap();
+        let pinned = vfs.get(fd).unwrap().entry.clone();
+
+        assert_eq!(vfs.close(fd), 0);
+        assert_eq!(vfs.poll(fd), libc::POLLNVAL);
+
+        // The blocked call still owns a live file.
+        let one = 1u64.to_ne_bytes();
+        assert_eq!(write_file(&pinned, one.as_ptr(), 8), 8);
+        let mut out = [0u8; 8];
+        assert_eq!(read_file(&pinned, out.as_mut_ptr(), 8), 8);
+        assert_eq!(u64::from_ne_bytes(out), 1);
+        assert_eq!(Arc::strong_count(&pinned), 1);
+    }
+
+    #[test]
+    fn test_descriptor_table_holds_max_fds() {
+        let mut vfs = Vfs::new();
+        let mut last = 0;
+        while let Ok(fd) = vfs.eventfd2(0, 0) {
+            last = fd;
+        }
+        // `RLIMIT_NOFILE` reports `MAX_FDS`: the highest descriptor is one below it.
+        assert_eq!(last as usize, MAX_FDS - 1);
+        assert_eq!(vfs.eventfd2(0, 0), Err(-(libc::EMFILE as isize)));
+    }
+
+    /// Create a ramfs file holding `data`, positioned at its start.
+    fn ram_file(vfs: &mut Vfs, path: &str, data: &[u8]) -> Fd {
+        let flags = libc::O_CREAT | libc::O_TRUNC | libc::O_RDWR;
+        let fd = vfs.openat(libc::AT_FDCWD, path, flags, 0o644).unwrap();
+        let file = vfs.get(fd).unwrap().entry.clone();
+        assert_eq!(
+            write_file(&file, data.as_ptr(), data.len()),
+            data.len() as isize
+        );
+        assert_eq!(vfs.lseek(fd, 0, libc::SEEK_SET), 0);
+        fd
+    }
+
+    #[test]
+    fn test_pread_and_pwrite_keep_the_position() {
+        let mut vfs = Vfs::new();
+        let fd = ram_file(&mut vfs, "/pread-test", b"0123456789");
+        let file = vfs.get(fd).unwrap().entry.clone();
+        assert_eq!(vfs.lseek(fd, 2, libc::SEEK_SET), 2);
+
+        let mut out = [0u8; 4];
+        assert_eq!(pread_file(&file, out.as_mut_ptr(), 4, 6), 4);
+        assert_eq!(&out, b"6789");
+        assert_eq!(pwrite_file(&file, b"ab".as_ptr(), 2, 12), 2);
+        assert_eq!(vfs.lseek(fd, 0, libc::SEEK_CUR), 2);
+        // The write past the end left a zero-filled hole.
+        assert_eq!(pread_file(&file, out.as_mut_ptr(), 4, 10), 4);
+        assert_eq!(&out, b"\0\0ab");
+
+        // Streams have no offsets to read at.
+        let (r, _w) = vfs.pipe2(0).unwrap();
+        let pipe = vfs.get(r).unwrap().entry.clone();
+        assert_eq!(
+            pread_file(&pipe, out.as_mut_ptr(), 4, 0),
+            -(libc::ESPIPE as isize)
+        );
+    }
+
+    #[test]
+    fn test_transfer_with_offset_leaves_the_input_position() {
+        let mut vfs = Vfs::new();
+        let input = ram_file(&mut vfs, "/sendfile-in", b"hello, world");
+        let output = ram_file(&mut vfs, "/sendfile-out", b"");
+        let (i, o) = (
+            vfs.get(input).unwrap().entry.clone(),
+            vfs.get(output).unwrap().entry.clone(),
+        );
+
+        let mut off = 7;
+        assert_eq!(transfer(&i, Some(&mut off), &o, None, 100), 5);
+        assert_eq!(off, 12);
+        assert_eq!(vfs.lseek(input, 0, libc::SEEK_CUR), 0);
+        assert_eq!(vfs.lseek(output, 0, libc::SEEK_CUR), 5);
+
+        let (mut in_off, mut out_off) = (0, 5);
+        let copied = copy_file_range(&i, Some(&mut in_off), &o, Some(&mut out_off), 5, 0);
+        assert_eq!(copied, 5);
+        assert_eq!((in_off, out_off), (5, 10));
+        assert_eq!(vfs.lseek(output, 0, libc::SEEK_CUR), 5);
+        let mut out = [0u8; 10];
+        assert_eq!(pread_file(&o, out.as_mut_ptr(), 10, 0), 10);
+        assert_eq!(&out, b"worldhello");
+    }
+
+    #[test]
+    fn test_short_transfer_rewinds_a_seekable_input() {
+        let mut vfs = Vfs::new();
+        let data = alloc::vec![7u8; 80 * 1024];
+        let input = ram_file(&mut vfs, "/short-in", &data);
+        let (_r, w) = vfs.pipe2(libc::O_NONBLOCK).unwrap();
+        let (i, o) = (
+            vfs.get(input).unwrap().entry.clone(),
+            vfs.get(w).unwrap().entry.clone(),
+        );
+
+        // The pipe fills up part way through; the unsent rest stays unread.
+        let sent = transfer(&i, None, &o, None, data.len());
+        assert!(sent > 0 && (sent as usize) < data.len());
+        assert_eq!(vfs.lseek(input, 0, libc::SEEK_CUR), sent);
+        assert_eq!(
+            transfer(&i, None, &o, None, data.len()),
+            -(libc::EAGAIN as isize)
+        );
+        assert_eq!(vfs.lseek(input, 0, libc::SEEK_CUR), sent);
+    }
+
+    #[test]
+    fn test_epoll_rejects_loops() {
+        let mut vfs = Vfs::new();
+        let eps: Vec<Fd> = (0..=EPOLL_MAX_NESTS + 1)
+            .map(|_| vfs.epoll_create1(0).unwrap())
+            .collect();
+        let ev = Some(libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: 0,
+        });
+        let (a, b) = (eps[0], eps[1]);
+
+        assert_eq!(vfs.epoll_ctl(a, libc::EPOLL_CTL_ADD, b, ev), Ok(()));
+        assert_eq!(
+            vfs.epoll_ctl(b, libc::EPOLL_CTL_ADD, a, ev),
+            Err(-(libc::ELOOP as isize))
+        );
+        assert_eq!(
+            vfs.epoll_ctl(a, libc::EPOLL_CTL_ADD, a, ev),
+            Err(-(libc::EINVAL as isize))
+        );
+        assert_eq!(vfs.poll(a), 0);
+
+        // eps[i] watches eps[i + 1]: the chain below eps[1] may not grow past the limit.
+        for pair in eps[1..].windows(2).take(EPOLL_MAX_NESTS - 1) {
+            assert_eq!(
+                vfs.epoll_ctl(pair[0], libc::EPOLL_CTL_ADD, pair[1], ev),
+                Ok(())
+            );
+        }
+        let top = vfs.epoll_create1(0).unwrap();
+        assert_eq!(
+            vfs.epoll_ctl(top, libc::EPOLL_CTL_ADD, a, ev),
+            Err(-(libc::ELOOP as isize))
+        );
     }
 }
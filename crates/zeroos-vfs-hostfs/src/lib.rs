@@ -0,0 +1,406 @@
+//! A host directory proxied through frontend syscalls.
+//!
+//! Under Spike, the frontend server (fesvr) runs `openat`, `read`, `write`, `close` and a few
+//! more calls on the host when the guest sends it a syscall packet. [`HostFs`] mounts a host
+//! directory into the namespace this way, so a guest can read test fixtures and write outputs
+//! without baking them into the image. The transport is passed in as a [`HostSyscallFn`]
+//! (`htif::frontend_syscall` on Spike).
+//!
+//! fesvr cannot list directories, so reading a hostfs directory only returns `.` and `..`;
+//! files are still found by name. Symlinks are followed on the host.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ptr::addr_of_mut;
+
+use foundation::utils::KernelLock;
+use vfs_core::{
+    noop_getdents64, noop_ioctl, open_dir, poll_ready, DirEntry, FdEntry, FileOps, FileSystem, Ino,
+    VfsResult,
+};
+
+/// Run host syscall `n` (RISC-V Linux numbering); returns its result or `-errno`.
+pub type HostSyscallFn = fn(n: u64, args: [u64; 6]) -> i64;
+
+// The subset of fesvr's syscalls used here.
+const SYS_MKDIRAT: u64 = 34;
+const SYS_UNLINKAT: u64 = 35;
+const SYS_RENAMEAT: u64 = 38;
+const SYS_OPENAT: u64 = 56;
+const SYS_CLOSE: u64 = 57;
+const SYS_LSEEK: u64 = 62;
+const SYS_READ: u64 = 63;
+const SYS_WRITE: u64 = 64;
+const SYS_PREAD: u64 = 67;
+const SYS_PWRITE: u64 = 68;
+const SYS_FSTATAT: u64 = 79;
+const SYS_FSTAT: u64 = 80;
+
+const AT_FDCWD: u64 = -100i64 as u64;
+const AT_REMOVEDIR: u64 = 0x200;
+
+const ROOT: Ino = 0;
+
+/// `st_dev` of hostfs nodes.
+const HOSTFS_DEV: u64 = 3;
+
+fn addr<T>(ptr: *const T) -> u64 {
+    ptr as usize as u64
+}
+
+/// `struct stat` as fesvr writes it: the 64-bit generic layout, whatever the guest XLEN.
+#[repr(C)]
+#[derive(Default)]
+struct HostStat {
+    dev: u64,
+    ino: u64,
+    mode: u32,
+    nlink: u32,
+    uid: u32,
+    gid: u32,
+    rdev: u64,
+    _pad1: u64,
+    size: u64,
+    blksize: u32,
+    _pad2: u32,
+    blocks: u64,
+    atime: u64,
+    atime_nsec: u64,
+    mtime: u64,
+    mtime_nsec: u64,
+    ctime: u64,
+    ctime_nsec: u64,
+    _unused: [u32; 2],
+}
+
+impl HostStat {
+    /// Fill `st` for node `ino`; device and inode numbers are ours, not the host's.
+    fn fill(&self, ino: Ino, st: &mut libc::stat) {
+        *st = unsafe { core::mem::zeroed() };
+        st.st_dev = HOSTFS_DEV as _;
+        st.st_ino = (ino + 1) as _;
+        st.st_mode = self.mode as _;
+        st.st_nlink = self.nlink as _;
+        st.st_uid = self.uid as _;
+        st.st_gid = self.gid as _;
+        st.st_rdev = self.rdev as _;
+        st.st_size = self.size as _;
+        st.st_blksize = self.blksize as _;
+        st.st_blocks = self.blocks as _;
+        st.st_atime = self.atime as _;
+        st.st_atime_nsec = self.atime_nsec as _;
+        st.st_mtime = self.mtime as _;
+        st.st_mtime_nsec = self.mtime_nsec as _;
+        st.st_ctime = self.ctime as _;
+        st.st_ctime_nsec = self.ctime_nsec as _;
+    }
+
+    fn is_dir(&self) -> bool {
+        self.mode & libc::S_IFMT == libc::S_IFDIR
+    }
+}
+
+/// A host directory; put it in a static and mount it with `vfs_core::mount`.
+pub struct HostFs {
+    /// Host directory served at the mount point; the host resolves a relative one against
+    /// the simulator's working directory.
+    root: &'static str,
+    syscall: HostSyscallFn,
+    /// Paths below `root` (each starting with `/`) of the nodes looked up so far; node `i`
+    /// is entry `i - 1`.
+    nodes: KernelLock<Vec<String>>,
+}
+
+impl HostFs {
+    pub const fn new(root: &'static str, syscall: HostSyscallFn) -> Self {
+        Self {
+            root,
+            syscall,
+            nodes: KernelLock::new(Vec::new()),
+        }
+    }
+
+    fn call(&self, n: u64, args: [u64; 6]) -> VfsResult<u64> {
+        let ret = (self.syscall)(n, args);
+        if ret < 0 {
+            Err(ret as isize)
+        } else {
+            Ok(ret as u64)
+        }
+    }
+
+    fn path(&self, ino: Ino) -> VfsResult<String> {
+        if ino == ROOT {
+            return Ok(String::new());
+        }
+        self.nodes
+            .with(|nodes| nodes.get(ino - 1).cloned())
+            .ok_or(-(libc::ENOENT as isize))
+    }
+
+    fn child(&self, dir: Ino, name: &str) -> VfsResult<String> {
+        let mut path = self.path(dir)?;
+        path.push('/');
+        path.push_str(name);
+        Ok(path)
+    }
+
+    /// Node of `path`, allocated on first use.
+    fn intern(&self, path: String) -> Ino {
+        self.nodes
+            .with_mut(|nodes| match nodes.iter().position(|p| *p == path) {
+                Some(i) => i + 1,
+                None => {
+                    nodes.push(path);
+                    nodes.len()
+                }
+            })
+    }
+
+    /// Host path of `path`, NUL-terminated. fesvr takes a path as address and length, the
+    /// length counting the NUL.
+    fn host_path(&self, path: &str) -> String {
+        let mut host = String::with_capacity(self.root.len() + path.len() + 1);
+        host.push_str(self.root);
+        host.push_str(path);
+        host.push('\0');
+        host
+    }
+
+    /// Run a `*at` syscall on `path`, followed by the arguments in `rest`.
+    fn call_at(&self, n: u64, path: &str, rest: [u64; 3]) -> VfsResult<u64> {
+        let host = self.host_path(path);
+        let (ptr, len) = (addr(host.as_ptr()), host.len() as u64);
+        self.call(n, [AT_FDCWD, ptr, len, rest[0], rest[1], rest[2]])
+    }
+
+    fn host_stat(&self, path: &str) -> VfsResult<HostStat> {
+        let mut hs = HostStat::default();
+        self.call_at(SYS_FSTATAT, path, [addr(addr_of_mut!(hs)), 0, 0])?;
+        Ok(hs)
+    }
+}
+
+impl FileSystem for HostFs {
+    fn root(&self) -> Ino {
+        ROOT
+    }
+
+    fn lookup(&self, dir: Ino, name: &str) -> VfsResult<Ino> {
+        let path = self.child(dir, name)?;
+        self.host_stat(&path)?;
+        Ok(self.intern(path))
+    }
+
+    fn create(&self, dir: Ino, name: &str, mode: u32) -> VfsResult<Ino> {
+        let path = self.child(dir, name)?;
+        let flags = libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL;
+        let fd = self.call_at(SYS_OPENAT, &path, [flags as u64, mode as u64, 0])?;
+        let _ = self.call(SYS_CLOSE, [fd, 0, 0, 0, 0, 0]);
+        Ok(self.intern(path))
+    }
+
+    fn unlink(&self, dir: Ino, name: &str, want_dir: bool) -> VfsResult<()> {
+        let path = self.child(dir, name)?;
+        let flags = if want_dir { AT_REMOVEDIR } else { 0 };
+        self.call_at(SYS_UNLINKAT, &path, [flags, 0, 0]).map(|_| ())
+    }
+
+    fn readdir(&self, dir: Ino, index: usize) -> VfsResult<Option<DirEntry>> {
+        if !self.host_stat(&self.path(dir)?)?.is_dir() {
+            return Err(-(libc::ENOTDIR as isize));
+        }
+        Ok(match index {
+            0 | 1 => Some(DirEntry {
+                name: String::from(if index == 0 { "." } else { ".." }),
+                ino: dir,
+                d_type: libc::DT_DIR,
+            }),
+            _ => None,
+        })
+    }
+
+    fn stat(&self, ino: Ino, st: &mut libc::stat) -> VfsResult<()> {
+        self.host_stat(&self.path(ino)?)?.fill(ino, st);
+        Ok(())
+    }
+
+    fn open(&'static self, ino: Ino, flags: i32, mode: u32) -> VfsResult<FdEntry> {
+        let path = self.path(ino)?;
+        if self.host_stat(&path)?.is_dir() {
+            return Ok(open_dir(self, ino));
+        }
+        // The VFS has already created the file if it was missing.
+        let flags = flags & !(libc::O_CREAT | libc::O_EXCL);
+        let fd = self.call_at(SYS_OPENAT, &path, [flags as u32 as u64, mode as u64, 0])?;
+        let file = Box::new(HostFile { fs: self, fd, ino });
+        Ok(FdEntry {
+            ops: &HOST_FOPS,
+            private_data: Box::into_raw(file) as *mut u8,
+        })
+    }
+
+    fn mkdir(&self, dir: Ino, name: &str, mode: u32) -> VfsResult<Ino> {
+        let path = self.child(dir, name)?;
+        self.call_at(SYS_MKDIRAT, &path, [mode as u64, 0, 0])?;
+        Ok(self.intern(path))
+    }
+
+    fn rename(&self, old: (Ino, &str), new: (Ino, &str), flags: u32) -> VfsResult<()> {
+        if flags != 0 {
+            return Err(-(libc::EINVAL as isize));
+        }
+        let (old, new) = (self.child(old.0, old.1)?, self.child(new.0, new.1)?);
+        let (host_old, host_new) = (self.host_path(&old), self.host_path(&new));
+        self.call(
+            SYS_RENAMEAT,
+            [
+                AT_FDCWD,
+                addr(host_old.as_ptr()),
+                host_old.len() as u64,
+                AT_FDCWD,
+                addr(host_new.as_ptr()),
+                host_new.len() as u64,
+            ],
+        )?;
+        // Nodes follow the file, as inodes do.
+        self.nodes.with_mut(|nodes| {
+            for path in nodes.iter_mut() {
+                if let Some(rest) = path.strip_prefix(old.as_str()) {
+                    if rest.is_empty() || rest.starts_with('/') {
+                        *path = new.clone() + rest;
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+/// An open host file (`FdEntry::private_data`); the host keeps the file position.
+struct HostFile {
+    fs: &'static HostFs,
+    fd: u64,
+    ino: Ino,
+}
+
+fn host_file<'a>(file: *mut u8) -> &'a HostFile {
+    unsafe { &*(file as *const HostFile) }
+}
+
+fn host_call(file: *mut u8, n: u64, args: [u64; 3]) -> isize {
+    let f = host_file(file);
+    match f.fs.call(n, [f.fd, args[0], args[1], args[2], 0, 0]) {
+        Ok(n) => n as isize,
+        Err(e) => e,
+    }
+}
+
+fn host_read(file: *mut u8, buf: *mut u8, count: usize) -> isize {
+    host_call(file, SYS_READ, [addr(buf), count as u64, 0])
+}
+
+fn host_write(file: *mut u8, buf: *const u8, count: usize) -> isize {
+    host_call(file, SYS_WRITE, [addr(buf), count as u64, 0])
+}
+
+fn host_pread(file: *mut u8, buf: *mut u8, count: usize, offset: usize) -> isize {
+    host_call(file, SYS_PREAD, [addr(buf), count as u64, offset as u64])
+}
+
+fn host_pwrite(file: *mut u8, buf: *const u8, count: usize, offset: usize) -> isize {
+    host_call(file, SYS_PWRITE, [addr(buf), count as u64, offset as u64])
+}
+
+fn host_llseek(file: *mut u8, offset: isize, whence: i32) -> isize {
+    host_call(file, SYS_LSEEK, [offset as i64 as u64, whence as u64, 0])
+}
+
+fn host_release(file: *mut u8) -> isize {
+    let ret = host_call(file, SYS_CLOSE, [0; 3]);
+    drop(unsafe { Box::from_raw(file as *mut HostFile) });
+    ret.min(0)
+}
+
+fn host_fstat(file: *mut u8, st: *mut libc::stat) -> isize {
+    let mut hs = HostStat::default();
+    let ret = host_call(file, SYS_FSTAT, [addr(addr_of_mut!(hs)), 0, 0]);
+    if ret < 0 {
+        return ret;
+    }
+    hs.fill(host_file(file).ino, unsafe { &mut *st });
+    0
+}
+
+static HOST_FOPS: FileOps = FileOps {
+    read: host_read,
+    write: host_write,
+    release: host_release,
+    llseek: host_llseek,
+    ioctl: noop_ioctl,
+    fstat: host_fstat,
+    getdents64: noop_getdents64,
+    pread: Some(host_pread),
+    pwrite: Some(host_pwrite),
+    poll: poll_ready,
+    mmap: None,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A host with `fixtures/` holding `in.txt` ("hello"); every open returns host fd 7.
+    fn fake_host(n: u64, args: [u64; 6]) -> i64 {
+        let path =
+            || unsafe { core::slice::from_raw_parts(args[1] as *const u8, args[2] as usize) };
+        match n {
+            SYS_FSTATAT => {
+                let (mode, size) = match path() {
+                    b"fixtures\0" => (libc::S_IFDIR | 0o755, 0),
+                    b"fixtures/in.txt\0" => (libc::S_IFREG | 0o644, 5),
+                    _ => return -(libc::ENOENT as i64),
+                };
+                let hs = unsafe { &mut *(args[3] as *mut HostStat) };
+                hs.mode = mode;
+                hs.size = size;
+                0
+            }
+            SYS_OPENAT => 7,
+            SYS_READ if args[0] == 7 => {
+                let buf = unsafe { core::slice::from_raw_parts_mut(args[1] as *mut u8, 5) };
+                buf.copy_from_slice(b"hello");
+                5
+            }
+            SYS_CLOSE if args[0] == 7 => 0,
+            _ => -(libc::ENOSYS as i64),
+        }
+    }
+
+    #[test]
+    fn test_lookup_stat_and_read() {
+        static FS: HostFs = HostFs::new("fixtures", fake_host);
+
+        let ino = FS.lookup(ROOT, "in.txt").unwrap();
+        assert_eq!(FS.lookup(ROOT, "in.txt"), Ok(ino), "nodes are stable");
+        assert_eq!(FS.lookup(ROOT, "missing"), Err(-(libc::ENOENT as isize)));
+
+        let mut st: libc::stat = unsafe { core::mem::zeroed() };
+        FS.stat(ino, &mut st).unwrap();
+        assert_eq!(st.st_ino as usize, ino + 1);
+        assert_eq!(st.st_mode & libc::S_IFMT, libc::S_IFREG);
+        assert_eq!(st.st_size, 5);
+        assert!(FS.readdir(ROOT, 2).unwrap().is_none(), "fesvr cannot list");
+
+        let file = FS.open(ino, libc::O_RDONLY, 0).unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!((file.ops.read)(file.private_data, buf.as_mut_ptr(), 8), 5);
+        assert_eq!(&buf[..5], b"hello");
+        assert_eq!((file.ops.release)(file.private_data), 0);
+    }
+}
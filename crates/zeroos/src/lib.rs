@@ -81,6 +81,9 @@ pub use runtime_nostd::alloc;
 pub mod vfs {
     pub use vfs_core::*;
 
+    #[cfg(feature = "vfs-hostfs")]
+    pub use vfs_hostfs as hostfs;
+
     pub mod devices {
         #[cfg(feature = "vfs-device-console")]
         pub use device_console as console;
@@ -93,6 +96,37 @@ pub mod vfs {
 
         #[cfg(feature = "vfs-device-zero")]
         pub use device_zero as zero;
+
+        #[cfg(feature = "vfs-device-full")]
+        pub use device_full as full;
+
+        #[cfg(feature = "vfs-device-random")]
+        pub use device_random as random;
+
+        #[cfg(feature = "vfs-device-tty")]
+        pub use device_tty as tty;
+
+        #[cfg(feature = "vfs-device-kmsg")]
+        pub use device_kmsg as kmsg;
+
+        /// Create a `/dev` node for every enabled device crate (the console is registered by
+        /// the platform, which owns its output). Call once the VFS is initialized.
+        pub fn register_all() {
+            #[cfg(feature = "vfs-device-null")]
+            let _ = vfs_core::register_device("/dev/null", null::null_factory);
+            #[cfg(feature = "vfs-device-zero")]
+            let _ = vfs_core::register_device("/dev/zero", zero::zero_factory);
+            #[cfg(feature = "vfs-device-full")]
+            let _ = vfs_core::register_device("/dev/full", full::full_factory);
+            #[cfg(feature = "vfs-device-random")]
+            let _ = vfs_core::register_device("/dev/random", random::random_factory);
+            #[cfg(feature = "vfs-device-urandom")]
+            let _ = vfs_core::register_device("/dev/urandom", urandom::urandom_factory);
+            #[cfg(feature = "vfs-device-tty")]
+            let _ = vfs_core::register_device("/dev/tty", tty::tty_factory);
+            #[cfg(feature = "vfs-device-kmsg")]
+            let _ = vfs_core::register_device("/dev/kmsg", kmsg::kmsg_factory);
+        }
     }
 }
 
@@ -131,4 +165,9 @@ pub fn initialize() {
 
     #[cfg(feature = "random")]
     foundation::register_random(rng::RNG_OPS);
+
+    {
+        use zeroos_backtrace::{Backtrace, BacktraceCapture};
+        foundation::kfn::crash::kregister_unwinder(Backtrace::walk_frames);
+    }
 }
@@ -6,6 +6,7 @@ use std::process::{exit, Command, Stdio};
 use std::{io::BufRead, io::BufReader, io::Write};
 
 use build::host::backtrace as sym;
+use build::host::crash::{self, CrashRecord};
 
 #[derive(Args, Debug)]
 pub struct RunArgs {
@@ -22,7 +23,7 @@ pub struct RunArgs {
     #[arg(long, short = 'n', default_value = "1000000")]
     pub instructions: u64,
 
-    /// Symbolize `stack backtrace:` frame addresses using addr2line on the host
+    /// Symbolize `stack backtrace:` frames and crash records using addr2line on the host
     #[arg(long, default_value_t = true)]
     pub symbolize_backtrace: bool,
 
@@ -30,10 +31,30 @@ pub struct RunArgs {
     #[arg(long, env = "RISCV_ADDR2LINE")]
     pub addr2line: Option<PathBuf>,
 
+    /// Write kernel journal records (`@@J` lines, needs `journal-record`) to this file
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Feed a recorded journal to the guest on stdin (needs `journal-replay`)
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     pub spike_args: Vec<String>,
 }
 
+/// Marker that starts a kernel journal record in the guest's output.
+const JOURNAL_MARKER: &str = "@@J ";
+
+/// Split a journal record off an output line.
+///
+/// Returns the guest output preceding the record and the record itself (including the newline).
+/// A record may follow partial guest output that did not end in a newline.
+fn split_journal_record(line: &str) -> Option<(&str, &str)> {
+    let pos = line.find(JOURNAL_MARKER)?;
+    Some((&line[..pos], &line[pos..]))
+}
+
 pub fn run_command(args: RunArgs) -> Result<()> {
     if !args.binary.exists() {
         anyhow::bail!("Binary not found: {}", args.binary.display());
@@ -74,6 +95,19 @@ pub fn run_command(args: RunArgs) -> Result<()> {
     spike_cmd.stdout(Stdio::piped());
     spike_cmd.stderr(Stdio::inherit());
 
+    if let Some(path) = &args.replay {
+        let journal = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open journal {}", path.display()))?;
+        spike_cmd.stdin(Stdio::from(journal));
+    }
+    let mut journal_out = match &args.record {
+        Some(path) => Some(std::io::BufWriter::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("Failed to create journal {}", path.display()))?,
+        )),
+        None => None,
+    };
+
     let mut child = spike_cmd
         .spawn()
         .context("Failed to execute spike (is it installed?)")?;
@@ -95,6 +129,8 @@ pub fn run_command(args: RunArgs) -> Result<()> {
     // Backtrace symbolization state: buffer contiguous frame lines and rewrite them.
     let mut pending_frames: Vec<(usize, String)> = Vec::new(); // (frame_no, addr_hex)
     let mut in_backtrace = false;
+    // Crash record being read (between `@@CRASH BEGIN` and `@@CRASH END`).
+    let mut crash_record: Option<CrashRecord> = None;
 
     let mut line = String::new();
     loop {
@@ -106,6 +142,30 @@ pub fn run_command(args: RunArgs) -> Result<()> {
             break;
         }
 
+        if let Some(journal) = journal_out.as_mut() {
+            if let Some((output, record)) = split_journal_record(&line) {
+                journal
+                    .write_all(record.as_bytes())
+                    .context("Failed to write journal")?;
+                out.write_all(output.as_bytes()).ok();
+                out.flush().ok();
+                continue;
+            }
+        }
+
+        if let Some(record) = crash_record.as_mut() {
+            if !record.parse_line(&line) {
+                print_crash(&mut out, &args.binary, addr2line.as_deref(), record);
+                crash_record = None;
+            }
+            continue;
+        }
+        if let Some(output) = crash::split_crash_begin(&line) {
+            out.write_all(output.as_bytes()).ok();
+            crash_record = Some(CrashRecord::default());
+            continue;
+        }
+
         if line.trim_end() == "stack backtrace:" {
             in_backtrace = true;
             pending_frames.clear();
@@ -145,6 +205,15 @@ pub fn run_command(args: RunArgs) -> Result<()> {
         );
     }
 
+    // The guest died while printing its crash record.
+    if let Some(record) = &crash_record {
+        print_crash(&mut out, &args.binary, addr2line.as_deref(), record);
+    }
+
+    if let Some(mut journal) = journal_out {
+        journal.flush().context("Failed to write journal")?;
+    }
+
     let status = child.wait().context("Failed to wait for spike process")?;
 
     if !status.success() {
@@ -191,3 +260,33 @@ fn flush_symbolized_frames(
     }
     let _ = out.flush();
 }
+
+fn print_crash(out: &mut dyn Write, bin: &Path, addr2line: Option<&Path>, record: &CrashRecord) {
+    let symbolize = |addr: u64, is_return: bool| {
+        let a2l = addr2line?;
+        if is_return {
+            sym::symbolize_pc_with_fallback(bin, a2l, &format!("{addr:x}"))
+        } else {
+            sym::symbolize_addr(bin, a2l, &format!("{addr:#x}"))
+        }
+    };
+    let _ = record.render(out, &symbolize);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_journal_record() {
+        assert_eq!(split_journal_record("hello\n"), None);
+        assert_eq!(
+            split_journal_record("@@J 1 0200000000000000\n"),
+            Some(("", "@@J 1 0200000000000000\n"))
+        );
+        assert_eq!(
+            split_journal_record("partial@@J 2 abcd\n"),
+            Some(("partial", "@@J 2 abcd\n"))
+        );
+    }
+}
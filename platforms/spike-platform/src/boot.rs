@@ -4,6 +4,10 @@ extern crate alloc;
 pub use htif::{fromhost, tohost};
 
 extern "C" {
+    static __ehdr_start: u8;
+    static __text_end: u8;
+    static __init_array_start: u8;
+    static __bss_end: u8;
     static __heap_start: u8;
     static __heap_end: u8;
     static __stack_top: u8;
@@ -19,12 +23,45 @@ fn install_trap_vector() {
     }
 }
 
+/// Describe the loaded image and the boot stack for `/proc/self/maps`.
+#[cfg(feature = "memory")]
+fn register_image_regions() {
+    use core::ptr::addr_of;
+    use foundation::kfn::memory::kmap_region;
+    use foundation::ops::{MemoryRegion, IMAGE_PATH};
+
+    let region = |start: *const u8, end: *const u8, perms: &[u8; 4], name| {
+        kmap_region(MemoryRegion {
+            start: start as usize,
+            end: end as usize,
+            perms: *perms,
+            name,
+        })
+    };
+    let (text, text_end) = (addr_of!(__ehdr_start), addr_of!(__text_end));
+    let (data, data_end) = (addr_of!(__init_array_start), addr_of!(__bss_end));
+    region(text, text_end, b"r-xp", IMAGE_PATH);
+    region(text_end, data, b"r--p", IMAGE_PATH);
+    region(data, data_end, b"rw-p", IMAGE_PATH);
+    region(
+        addr_of!(__stack_bottom),
+        addr_of!(__stack_top),
+        b"rw-p",
+        "[stack]",
+    );
+}
+
 #[no_mangle]
 pub extern "C" fn __platform_bootstrap() {
     debug::writeln!("[BOOT] __platform_bootstrap");
 
     zeroos::initialize();
 
+    #[cfg(feature = "journal-record")]
+    foundation::kfn::journal::kjournal_set_mode(foundation::kfn::journal::JournalMode::Record);
+    #[cfg(feature = "journal-replay")]
+    foundation::kfn::journal::kjournal_set_mode(foundation::kfn::journal::JournalMode::Replay);
+
     #[cfg(feature = "memory")]
     {
         let heap_start = core::ptr::addr_of!(__heap_start) as usize;
@@ -40,6 +77,7 @@ pub extern "C" fn __platform_bootstrap() {
             _stack_top,
             _stack_bottom
         );
+        register_image_regions();
     }
 
     cfg_if::cfg_if! {
@@ -68,13 +106,18 @@ pub extern "C" fn __platform_bootstrap() {
             #[cfg(feature = "vfs")]
             {
                 foundation::kfn::vfs::kinit();
+                zeroos::vfs::devices::register_all();
 
                 #[cfg(feature = "vfs-device-console")]
                 {
                     debug::writeln!("[BOOT] Registering console file descriptors");
-                    register_console_fd(1, &STDOUT_FOPS);
-                    register_console_fd(2, &STDERR_FOPS);
+                    register_console_fd(1, &STDOUT);
+                    register_console_fd(2, &STDERR);
+                    let _ = STDOUT.register_device("/dev/console");
                 }
+
+                #[cfg(feature = "hostfs")]
+                mount_hostfs();
             }
 
             #[cfg(feature = "random")]
@@ -94,6 +137,17 @@ pub extern "C" fn __platform_bootstrap() {
                     core::arch::asm!("mv tp, x0");
                 }
             }
+        } else {
+            // no_std guests have no TLS, so the boot thread keeps its anchor in tp for good.
+            #[cfg(feature = "thread")]
+            {
+                let anchor = foundation::kfn::scheduler::kinit();
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                unsafe {
+                    core::arch::asm!("mv tp, {0}", in(reg) anchor);
+                    core::arch::asm!("csrw mscratch, x0");
+                }
+            }
         }
     }
 }
@@ -101,6 +155,7 @@ pub extern "C" fn __platform_bootstrap() {
 cfg_if::cfg_if! {
     if #[cfg(feature = "vfs-device-console")] {
         use zeroos::vfs::{self};
+        use zeroos::vfs::devices::console::{BufferMode, Output};
 
         fn htif_console_write(_file: *mut u8, buf: *const u8, count: usize) -> isize {
             unsafe {
@@ -112,18 +167,44 @@ cfg_if::cfg_if! {
             count as isize
         }
 
-        fn register_console_fd(fd: i32, ops: &'static vfs::FileOps) {
+        fn register_console_fd(fd: i32, output: &'static Output) {
             debug::writeln!("[HTIF] register_console_fd fd={}", fd);
-            let _ = vfs::register_fd(
-                fd,
-                vfs::FdEntry {
-                    ops,
-                    private_data: core::ptr::null_mut(),
-                },
-            );
+            let _ = vfs::register_fd(fd, output.entry());
         }
 
-        static STDOUT_FOPS: vfs::FileOps = vfs::devices::console::stdout_fops(htif_console_write);
-        static STDERR_FOPS: vfs::FileOps = vfs::devices::console::stderr_fops(htif_console_write);
+        /// Kernel-side buffering of the console streams, from `ZEROOS_CONSOLE_BUFFER`.
+        ///
+        /// The default is unbuffered like a Linux TTY: libc does its own buffering, and nothing
+        /// is lost if the guest aborts without closing its descriptors.
+        const CONSOLE_BUFFER: BufferMode = BufferMode::parse(option_env!("ZEROOS_CONSOLE_BUFFER"));
+
+        static STDOUT: Output = Output::new(htif_console_write, CONSOLE_BUFFER);
+        static STDERR: Output = Output::new(htif_console_write, CONSOLE_BUFFER);
+    }
+}
+
+#[cfg(feature = "hostfs")]
+const fn env_or(value: Option<&'static str>, default: &'static str) -> &'static str {
+    match value {
+        Some(v) => v,
+        None => default,
+    }
+}
+
+/// Host directory shared with the guest, relative to the simulator's working directory.
+#[cfg(feature = "hostfs")]
+const HOSTFS_ROOT: &str = env_or(option_env!("ZEROOS_HOSTFS_ROOT"), ".");
+/// Where the guest sees it.
+#[cfg(feature = "hostfs")]
+const HOSTFS_MOUNT: &str = env_or(option_env!("ZEROOS_HOSTFS_MOUNT"), "/host");
+
+#[cfg(feature = "hostfs")]
+fn mount_hostfs() {
+    use zeroos::vfs::hostfs::HostFs;
+
+    static HOSTFS: HostFs = HostFs::new(HOSTFS_ROOT, htif::frontend_syscall);
+    debug::writeln!("[BOOT] Mounting host {} at {}", HOSTFS_ROOT, HOSTFS_MOUNT);
+    if let Err(_err) = zeroos::vfs::mount(HOSTFS_MOUNT, &HOSTFS) {
+        debug::writeln!("[BOOT] hostfs mount failed: {}", _err);
     }
 }
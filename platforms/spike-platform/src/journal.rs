@@ -0,0 +1,87 @@
+//! Journal sink over the HTIF console.
+//!
+//! Each record is one line, `@@J <kind> <hex payload>\n`. Recording writes the lines to stdout
+//! (`cargo spike run --record` splits them off); replay reads the same lines back from stdin
+//! (`cargo spike run --replay`).
+
+const MARKER: &[u8] = b"@@J ";
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+#[no_mangle]
+/// # Safety
+/// `data` must be valid for reads of `len` bytes.
+pub unsafe extern "C" fn __platform_journal_write(kind: u8, data: *const u8, len: usize) {
+    let data = core::slice::from_raw_parts(data, len);
+    for &b in MARKER {
+        htif::putchar(b);
+    }
+    if kind >= 100 {
+        htif::putchar(b'0' + kind / 100);
+    }
+    if kind >= 10 {
+        htif::putchar(b'0' + kind / 10 % 10);
+    }
+    htif::putchar(b'0' + kind % 10);
+    htif::putchar(b' ');
+    for &b in data {
+        htif::putchar(HEX[(b >> 4) as usize]);
+        htif::putchar(HEX[(b & 0xf) as usize]);
+    }
+    htif::putchar(b'\n');
+}
+
+fn hex_val(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[no_mangle]
+/// Read the next record into `buf`; returns its length, or -1 on end of input / bad record.
+///
+/// # Safety
+/// `kind` must be valid for writes and `buf` for writes of `cap` bytes.
+pub unsafe extern "C" fn __platform_journal_read(kind: *mut u8, buf: *mut u8, cap: usize) -> isize {
+    // Skip to the next marker (anything else on stdin is ignored).
+    let mut matched = 0;
+    while matched < MARKER.len() {
+        let c = htif::getchar();
+        if c == 0xff {
+            return -1;
+        }
+        matched = if c == MARKER[matched] {
+            matched + 1
+        } else {
+            usize::from(c == MARKER[0])
+        };
+    }
+
+    let mut k: u8 = 0;
+    loop {
+        match htif::getchar() {
+            c @ b'0'..=b'9' => k = k.wrapping_mul(10).wrapping_add(c - b'0'),
+            b' ' => break,
+            _ => return -1,
+        }
+    }
+    *kind = k;
+
+    let mut len = 0usize;
+    loop {
+        let hi = htif::getchar();
+        if hi == b'\n' {
+            return len as isize;
+        }
+        let (Some(hi), Some(lo)) = (hex_val(hi), hex_val(htif::getchar())) else {
+            return -1;
+        };
+        if len == cap {
+            return -1;
+        }
+        *buf.add(len) = (hi << 4) | lo;
+        len += 1;
+    }
+}
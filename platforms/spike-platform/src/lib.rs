@@ -1,6 +1,8 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod boot;
+#[cfg(any(feature = "journal-record", feature = "journal-replay"))]
+mod journal;
 #[cfg(all(
     not(target_os = "none"),
     any(target_arch = "riscv32", target_arch = "riscv64")
@@ -9,6 +11,8 @@ mod trap;
 
 extern crate zeroos;
 
+zeroos::macros::require_at_most_one_feature!("journal-record", "journal-replay");
+
 // Platform ABI symbols:
 // - Mandatory:
 //   - `__platform_bootstrap()` (in `boot.rs`): platform init hook called by arch bootstrap.
@@ -17,6 +21,7 @@ extern crate zeroos;
 //   - `__platform_stdout_write(..)`: fundamental output primitive, used by panic handler.
 // - Optional:
 //   - `__debug_write(..)`: only required when the `debug` crate is enabled/linked.
+//   - `__platform_journal_{write,read}(..)` (in `journal.rs`): only required with `journal`.
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
@@ -40,17 +45,23 @@ cfg_if::cfg_if! {
         #[cfg(target_os = "none")]
         #[panic_handler]
         fn panic(info: &core::panic::PanicInfo) -> ! {
-            eprintln!("PANIC: {}", info);
+            use foundation::kfn::crash::{kcrash_report, CrashCause, CrashReport};
+
+            // Report first: it stops lock waiters, so a panic raised while the scheduler or log
+            // lock is held still gets its record and the thread table out. Exit like SIGABRT
+            // (128 + 6 = 134), the Linux convention, without going through signal delivery.
+            const EXIT_CODE: i32 = 128 + 6;
+            kcrash_report(&CrashReport {
+                cause: CrashCause::Panic,
+                message: format_args!("{}", info),
+                exit_code: EXIT_CODE,
+                regs: core::ptr::null(),
+            });
 
-            // Print backtrace (polymorphic: no-op if mode is "off")
-            // Safety: Called from panic handler with valid stack
-            unsafe {
-                use zeroos::runtime_nostd::BacktraceCapture;
-                zeroos::runtime_nostd::Backtrace::print_backtrace();
-            }
+            #[cfg(feature = "thread")]
+            foundation::kfn::scheduler::kdump_threads();
 
-            // Exit with SIGABRT (128 + 6 = 134) per Linux convention
-            __platform_abort(6)
+            foundation::kfn::kexit(EXIT_CODE)
         }
     }
 }
@@ -62,8 +73,8 @@ pub extern "C" fn __platform_exit(code: i32) -> ! {
 
 /// Abort the program with Linux-standard signal exit code.
 ///
-/// This is called by the panic handler (via `zeroos-runtime-nostd`) or
-/// signal handlers to terminate due to a signal.
+/// Kept for code that terminates on a signal by itself; the kernel's own panic and signal paths
+/// report through `foundation::kfn::crash` directly. Prints a crash record.
 ///
 /// # Arguments
 /// * `sig` - The signal number (e.g., SIGABRT=6)
@@ -72,7 +83,14 @@ pub extern "C" fn __platform_exit(code: i32) -> ! {
 /// For SIGABRT (6), this yields exit code 134.
 #[no_mangle]
 pub extern "C" fn __platform_abort(sig: i32) -> ! {
-    __platform_exit(128 + sig)
+    use foundation::kfn::crash::{kcrash, CrashCause, CrashReport};
+
+    kcrash(&CrashReport {
+        cause: CrashCause::Signal,
+        message: format_args!("aborted with signal {}", sig),
+        exit_code: 128 + sig,
+        regs: core::ptr::null(),
+    })
 }
 
 #[no_mangle]
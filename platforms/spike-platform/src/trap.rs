@@ -33,6 +33,40 @@ fn instr_len(addr: usize) -> usize {
     }
 }
 
+/// Turn a guest fault into a signal, or terminate with a crash report if nothing handles it.
+///
+/// # Safety
+/// `regs` must point to the trap frame of the faulting trap.
+unsafe fn raise_fault(regs: *mut TrapFrame, code: usize) {
+    use foundation::kfn::crash::{kcrash, CrashCause, CrashReport};
+
+    // Faults in kernel code (possible only once threads give the kernel its own context) are fatal.
+    #[cfg(feature = "thread")]
+    let from_guest = (*regs).from_kernel == 0;
+    #[cfg(not(feature = "thread"))]
+    let from_guest = true;
+
+    if from_guest {
+        let (pc, mtval) = ((*regs).mepc, (*regs).mtval);
+        if let Some(pc) = foundation::kfn::trap::kexception(regs as *mut u8, code, pc, mtval) {
+            (*regs).mepc = pc;
+            foundation::kfn::trap::kreturn_to_user(regs as *mut u8);
+            return;
+        }
+    }
+    kcrash(&CrashReport {
+        cause: CrashCause::Exception,
+        message: format_args!(
+            "{:?} (mcause {}) in {} code",
+            Exception::from(code),
+            code,
+            if from_guest { "guest" } else { "kernel" }
+        ),
+        exit_code: code as i32,
+        regs: regs as *const u8,
+    });
+}
+
 /// # Safety
 /// `regs` must be a non-null pointer to a valid `TrapFrame` for the current CPU trap context.
 #[no_mangle]
@@ -66,12 +100,28 @@ pub unsafe extern "C" fn trap_handler(regs: *mut u8) {
                 (*regs).a7,
             );
             (*regs).a0 = ret as usize;
+            foundation::kfn::trap::kreturn_to_user(regs as *mut u8);
         }
         code if code == (Exception::Breakpoint as usize) => {
             advance_mepc_for_breakpoint(regs);
         }
+        code if code == (Exception::LoadFault as usize)
+            || code == (Exception::StoreFault as usize) =>
+        {
+            // An access to the current kernel stack's anchor/guard is a kernel stack overflow.
+            #[cfg(feature = "thread")]
+            if (*regs).from_kernel != 0 {
+                let anchor: usize;
+                core::arch::asm!("mv {0}, tp", out(reg) anchor);
+                foundation::kfn::thread::kcheck_kstack_fault(
+                    anchor as *const foundation::kfn::thread::ThreadAnchor,
+                    (*regs).mtval,
+                );
+            }
+            raise_fault(regs, code);
+        }
         code => {
-            foundation::kfn::kexit(code as i32);
+            raise_fault(regs, code);
         }
     }
 }